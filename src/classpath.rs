@@ -0,0 +1,121 @@
+use crate::error::YajvmError;
+use crate::jar::Jar;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// The source of the class files which the compiler asks for the classes referred but not compiled yet, e.g. the
+/// classpath, the class files embedded with include_bytes!, or the ones served from a database.
+pub trait ClassSource {
+    /// Finds the class file of the class given by its internal name, e.g. "com/example/Main", and returns its path
+    /// shown in the error messages with the bytes, or None if the source doesn't have the class.
+    fn find_class(&mut self, class_name: &str) -> Result<Option<(String, Vec<u8>)>, YajvmError>;
+}
+
+/// The class files keyed by the internal names of the classes.
+impl<B: AsRef<[u8]>> ClassSource for HashMap<String, B> {
+    fn find_class(&mut self, class_name: &str) -> Result<Option<(String, Vec<u8>)>, YajvmError> {
+        Ok(self.get(class_name).map(|classfile_bytes| {
+            (
+                format!("{}.class", class_name),
+                classfile_bytes.as_ref().to_vec(),
+            )
+        }))
+    }
+}
+
+impl ClassSource for Jar {
+    fn find_class(&mut self, class_name: &str) -> Result<Option<(String, Vec<u8>)>, YajvmError> {
+        Ok(self
+            .read_class(class_name)?
+            .map(|classfile_bytes| (self.class_path(class_name), classfile_bytes)))
+    }
+}
+
+/// The directories and the JAR files where the classes are located, searched in the order they are added like
+/// the -classpath option of java.
+#[derive(Default)]
+pub struct Classpath {
+    entries: Vec<ClasspathEntry>,
+}
+
+enum ClasspathEntry {
+    /// The directory of the class files placed by their packages, e.g. "classes" for "classes/com/example/Main.class".
+    Directory(PathBuf),
+    Jar(Jar),
+}
+
+impl Classpath {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses the paths separated by the platform's separator, e.g. "classes:lib/app.jar", where the paths ending
+    /// with ".jar" are JAR files and the others are directories.
+    pub fn parse(classpath: &str) -> Result<Self, YajvmError> {
+        let mut ret = Self::new();
+        for path in std::env::split_paths(classpath) {
+            if path.extension().is_some_and(|extension| extension == "jar") {
+                let jar_path = path.to_str().ok_or_else(|| YajvmError::Io {
+                    path: path.to_string_lossy().into_owned(),
+                    source: std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "the path isn't valid UTF-8",
+                    ),
+                })?;
+                ret.add_jar(Jar::open(jar_path)?);
+            } else {
+                ret.add_directory(path);
+            }
+        }
+        Ok(ret)
+    }
+
+    pub fn add_directory(&mut self, path: impl Into<PathBuf>) {
+        self.entries.push(ClasspathEntry::Directory(path.into()));
+    }
+
+    pub fn add_jar(&mut self, jar: Jar) {
+        self.entries.push(ClasspathEntry::Jar(jar));
+    }
+}
+
+/// Returns whether the internal name of the class consists of the unqualified names separated by '/' (See 4.2.1),
+/// so that its path stays in the directory, i.e. it has no leading '/', no empty, "." or ".." segment, and neither
+/// the separators nor the drive prefixes of Windows.
+fn is_valid_class_name(class_name: &str) -> bool {
+    class_name
+        .split('/')
+        .all(|name| !name.is_empty() && !name.contains(['.', ';', '[', '\\', ':']))
+}
+
+/// Finds the class in the first entry containing it. The classes of invalid names are never found.
+impl ClassSource for Classpath {
+    fn find_class(&mut self, class_name: &str) -> Result<Option<(String, Vec<u8>)>, YajvmError> {
+        if !is_valid_class_name(class_name) {
+            return Ok(None);
+        }
+        for entry in &mut self.entries {
+            match entry {
+                ClasspathEntry::Directory(directory) => {
+                    let path = directory.join(format!("{}.class", class_name));
+                    if !path.is_file() {
+                        continue;
+                    }
+                    let path = path.to_string_lossy().into_owned();
+                    let classfile_bytes =
+                        std::fs::read(&path).map_err(|source| YajvmError::Io {
+                            path: path.clone(),
+                            source,
+                        })?;
+                    return Ok(Some((path, classfile_bytes)));
+                }
+                ClasspathEntry::Jar(jar) => {
+                    if let Some(class_file) = jar.find_class(class_name)? {
+                        return Ok(Some(class_file));
+                    }
+                }
+            }
+        }
+        Ok(None)
+    }
+}
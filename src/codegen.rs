@@ -1,5 +1,10 @@
 mod codegen_class;
+mod codegen_class_exceptions;
+mod codegen_class_instance_fields;
+mod codegen_class_lambda;
+pub(crate) mod codegen_class_stack_map;
 mod codegen_class_static_fields;
+mod codegen_class_string_concat;
 mod codegen_context;
 pub mod descriptor;
 
@@ -7,24 +12,43 @@ pub use codegen_class::*;
 pub use codegen_context::*;
 use std::collections::{HashMap, HashSet};
 
-use crate::compiled_class::CompiledClass;
+use crate::codegen::codegen_class_exceptions::load_pending_exception;
+use crate::codegen::codegen_class_static_fields::static_field_storage_type;
+use crate::codegen::descriptor::{parse_method_descriptor, FieldType};
+use crate::compiled_class::{CompiledClass, StaticFieldConstant, StaticFieldInfo};
+use crate::error::YajvmError;
+use crate::stdlib::array::{component_class_name, new_compiled_reference_array_class};
 use crate::Isolate;
 use inkwell::context::Context;
-use inkwell::values::{BasicValue, FunctionValue, PointerValue};
+use inkwell::types::BasicType;
+use inkwell::values::{
+    BasicMetadataValueEnum, BasicValue, BasicValueEnum, CallSiteValue, FunctionValue, PointerValue,
+};
+use inkwell::AddressSpace;
 
 pub struct CodeGen<'ctx> {
     ctx: *mut Context,
     pub cc: CodegenContext<'ctx>,
     tracing_enabled: bool,
     pub class_ids: HashMap<String, ClassID>,
-    class_parents: Vec<ClassID>,
+    pub class_parents: Vec<ClassID>,
+    /// The class IDs of the components of the array classes, e.g. "[Ljava/lang/String;" for "[[Ljava/lang/String;",
+    /// indexed by the class IDs.
+    pub component_class_ids: Vec<Option<ClassID>>,
     java_lang_object_class_id: ClassID,
     pub max_class_id: ClassID,
 
     pub classes: Vec<CompiledClass>,
+    /// The names of the classes in `classes`, which `missing_class` looks up for every class reference.
+    class_names: HashSet<String>,
     main_class_symbol: String,
     vtable_offsets: HashMap<String, usize>, // method symbol -> offset in vtable.
     vtables: HashMap<ClassID, Vec<PointerValue<'ctx>>>,
+    instance_field_offsets: HashMap<String, u32>, // "class.field" -> offset in the instance.
+    /// The class which failed to compile halfway, after which nothing can be compiled nor linked.
+    poisoned_by: Option<String>,
+    /// Whether `done_compilation` has succeeded.
+    linked: bool,
 }
 
 pub type ClassID = u32;
@@ -39,27 +63,126 @@ impl<'ctx> CodeGen<'ctx> {
             ctx,
             cc,
             class_parents: Vec::new(),
+            component_class_ids: Vec::new(),
             tracing_enabled: false,
             classes: Vec::new(),
+            class_names: HashSet::new(),
             class_ids: HashMap::new(),
             max_class_id: 0,
             java_lang_object_class_id: 0,
             main_class_symbol,
             vtable_offsets: HashMap::new(),
             vtables: HashMap::new(),
+            instance_field_offsets: HashMap::new(),
+            poisoned_by: None,
+            linked: false,
         }
     }
 
-    pub fn class_id(&self, class_name: &str) -> ClassID {
-        *self.class_ids.get(class_name).unwrap()
+    /// Returns the ID of the class, or None if the class isn't compiled or the IDs aren't assigned yet by
+    /// `done_compilation`.
+    pub fn class_id(&self, class_name: &str) -> Option<ClassID> {
+        self.class_ids.get(class_name).copied()
     }
 
-    pub fn compile(&mut self, path: &str) {
-        let mut compiler = ClassFileCompiler::new(String::from(path), self.tracing_enabled);
+    /// Returns the ID of the compiled class once `assign_class_ids` has assigned the IDs to all of them.
+    fn assigned_class_id(&self, class_name: &str) -> ClassID {
+        self.class_ids[class_name]
+    }
+
+    /// Returns the ID of the class referenced by the compiled code, or a linkage error if it isn't compiled.
+    fn resolve_class_id(&self, class_name: &str) -> Result<ClassID, YajvmError> {
+        self.class_id(class_name)
+            .ok_or_else(|| YajvmError::linkage(format!("class {} not found", class_name)))
+    }
+
+    /// Whether `done_compilation` has linked the classes, which the isolates need to be created.
+    pub fn is_linked(&self) -> bool {
+        self.linked
+    }
+
+    /// Returns the static fields of the class, whose offsets are assigned by `done_compilation`.
+    pub fn static_fields(&self, class_name: &str) -> Option<&[StaticFieldInfo]> {
+        self.classes
+            .iter()
+            .find(|c| c.class_name == class_name)
+            .map(|c| c.static_fields.as_slice())
+    }
+
+    pub fn has_class(&self, class_name: &str) -> bool {
+        self.class_names.contains(class_name)
+    }
+
+    /// Returns a class which is referred but not compiled yet with the class referring it, where the main class is
+    /// referred by no class. The classes in `skipped` are not returned, e.g. the ones known to be missing.
+    pub fn missing_class(&self, skipped: &HashSet<String>) -> Option<(String, Option<String>)> {
+        let is_missing =
+            |class_name: &str| !self.has_class(class_name) && !skipped.contains(class_name);
+        let main_class_name = self.main_class_name();
+        if is_missing(main_class_name) {
+            return Some((main_class_name.to_string(), None));
+        }
+        self.classes.iter().find_map(|class| {
+            class
+                .referenced_classes
+                .iter()
+                .find(|class_name| is_missing(class_name))
+                .map(|class_name| (class_name.clone(), Some(class.class_name.clone())))
+        })
+    }
+
+    pub fn compile(&mut self, path: &str) -> Result<(), YajvmError> {
+        self.check_not_poisoned()?;
+        let compiler = ClassFileCompiler::new(String::from(path), self.tracing_enabled)?;
+        self.compile_class_file(compiler)
+    }
+
+    /// Compiles the class file given as bytes, e.g. an entry of a JAR file. `path` is only used in the error messages.
+    pub fn compile_bytes(&mut self, path: &str, classfile_bytes: &[u8]) -> Result<(), YajvmError> {
+        self.check_not_poisoned()?;
+        let compiler = ClassFileCompiler::from_bytes(
+            String::from(path),
+            classfile_bytes,
+            self.tracing_enabled,
+        )?;
+        self.compile_class_file(compiler)
+    }
+
+    /// Returns the name of the class whose main method is called, e.g. "com/example/Main".
+    pub fn main_class_name(&self) -> &str {
+        self.main_class_symbol.split_once(".main:").unwrap().0
+    }
+
+    /// Returns an error if a class failed to compile halfway before.
+    fn check_not_poisoned(&self) -> Result<(), YajvmError> {
+        match &self.poisoned_by {
+            Some(class_name) => Err(YajvmError::Poisoned {
+                class_name: class_name.clone(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    fn compile_class_file(&mut self, mut compiler: ClassFileCompiler) -> Result<(), YajvmError> {
         compiler.initialize_class_object_info();
-        compiler.compile_methods(&mut self.cc);
+        if let Err(e) = compiler.compile_methods(&mut self.cc) {
+            // The functions, the class ID placeholders and the lambda classes of the methods compiled so far are
+            // left in the context, and they can't be told apart from the ones of the other classes.
+            self.poisoned_by = Some(compiler.class_name());
+            return Err(e);
+        }
         let class = compiler.as_class();
+        self.class_names.insert(class.class_name.clone());
         self.classes.push(class);
+        // The classes implementing the lambdas are synthesized while compiling the methods.
+        self.class_names.extend(
+            self.cc
+                .lambda_classes
+                .iter()
+                .map(|class| class.class_name.clone()),
+        );
+        self.classes.append(&mut self.cc.lambda_classes);
+        Ok(())
     }
 
     pub fn enable_tracing(&mut self) {
@@ -71,6 +194,7 @@ impl<'ctx> CodeGen<'ctx> {
     }
 
     pub fn add_class(&mut self, class: CompiledClass) {
+        self.class_names.insert(class.class_name.clone());
         self.classes.push(class);
         let class = self.classes.last_mut().unwrap();
 
@@ -112,6 +236,29 @@ impl<'ctx> CodeGen<'ctx> {
                     .add_global_mapping(&func, ptr as usize);
             }
         }
+
+        for special_method in &class.special_methods {
+            if let Some(ptr) = special_method.ptr {
+                let desc = special_method
+                    .symbol
+                    .chars()
+                    .skip_while(|c| *c != ':')
+                    .skip(1)
+                    .collect::<String>();
+
+                let method_type = descriptor::parse_method_descriptor(&desc);
+                let func_type = self
+                    .cc
+                    .llvm_function_type_from_method_type(&method_type, false);
+                let func =
+                    self.cc
+                        .module
+                        .add_function(special_method.symbol.as_str(), func_type, None);
+                self.cc
+                    .execution_engine
+                    .add_global_mapping(&func, ptr as usize);
+            }
+        }
     }
 }
 
@@ -124,15 +271,38 @@ impl Drop for CodeGen<'_> {
 }
 
 impl<'ctx> CodeGen<'ctx> {
-    pub fn done_compilation(&mut self) {
-        self.assign_class_ids();
-        self.resolve_static_field_offsets();
-        self.build_inheritance_tree();
-        self.construct_vtables();
-        self.compile_main_function();
+    pub fn done_compilation(&mut self) -> Result<(), YajvmError> {
+        self.check_not_poisoned()?;
+        self.define_array_classes();
+        self.assign_class_ids()?;
+        self.resolve_static_field_offsets()?;
+        self.build_inheritance_tree()?;
+        self.check_assignability_constraints()?;
+        self.resolve_instance_field_offsets()?;
+        self.construct_vtables()?;
+        self.resolve_inherited_methods()?;
+        self.construct_interface_dispatchers()?;
+        self.compile_main_function()?;
+        self.linked = true;
+        Ok(())
+    }
+
+    /// Defines the reference array classes used by the compiled code, unless the standard library does.
+    fn define_array_classes(&mut self) {
+        let mut class_names = self
+            .cc
+            .array_class_names
+            .iter()
+            .filter(|name| !self.has_class(name))
+            .cloned()
+            .collect::<Vec<_>>();
+        class_names.sort();
+        for class_name in class_names {
+            self.add_class(new_compiled_reference_array_class(&class_name));
+        }
     }
 
-    fn build_inheritance_tree(&mut self) {
+    fn build_inheritance_tree(&mut self) -> Result<(), YajvmError> {
         assert!(
             !self.class_ids.is_empty(),
             "class_ids must be assigned before building inheritance tree"
@@ -140,23 +310,33 @@ impl<'ctx> CodeGen<'ctx> {
 
         self.class_parents
             .resize((self.max_class_id + 1) as usize, 0);
+        self.component_class_ids
+            .resize((self.max_class_id + 1) as usize, None);
 
         // The root class is java/lang/Object.
-        let java_lang_object_class_id = *self.class_ids.get("java/lang/Object").unwrap();
+        let java_lang_object_class_id = self.resolve_class_id("java/lang/Object")?;
         self.java_lang_object_class_id = java_lang_object_class_id;
 
         for class in &self.classes {
-            let child = *self.class_ids.get(&class.class_name).unwrap();
+            let child = self.assigned_class_id(&class.class_name);
             let parent = if let Some(parent) = &class.super_class {
-                *self.class_ids.get(parent).unwrap()
+                self.resolve_class_id(parent)?
             } else {
                 java_lang_object_class_id
             };
+            // The interfaces are looked up by their IDs from now on.
+            for interface in &class.interfaces {
+                self.resolve_class_id(interface)?;
+            }
             self.class_parents[child as usize] = parent;
+            self.component_class_ids[child as usize] = component_class_name(&class.class_name)
+                .map(|component| self.resolve_class_id(component))
+                .transpose()?;
         }
+        Ok(())
     }
 
-    pub fn assign_class_ids(&mut self) {
+    pub fn assign_class_ids(&mut self) -> Result<(), YajvmError> {
         // Sort the classes by name to make the order of class IDs deterministic.
         self.classes.sort_by(|a, b| a.class_name.cmp(&b.class_name));
         for (i, class) in self.classes.iter().enumerate() {
@@ -165,36 +345,243 @@ impl<'ctx> CodeGen<'ctx> {
             self.max_class_id = i as ClassID;
         }
 
-        for (class_name, id) in self.class_ids.iter() {
-            let class_id = self.cc.i32_type.const_int(*id as u64, false);
-            if let Some(vals) = self.cc.class_id_values.get(class_name) {
-                for val in vals {
-                    val.replace_all_uses_with(class_id.into());
-                    // Removes the dummy load.
-                    val.as_instruction_value().unwrap().erase_from_basic_block()
+        for (class_name, vals) in self.cc.class_id_values.iter() {
+            let class_id = self
+                .cc
+                .i32_type
+                .const_int(self.resolve_class_id(class_name)? as u64, false);
+            for val in vals {
+                val.replace_all_uses_with(class_id.into());
+                // Removes the dummy load.
+                val.as_instruction_value().unwrap().erase_from_basic_block()
+            }
+        }
+        Ok(())
+    }
+
+    fn resolve_static_field_offsets(&mut self) -> Result<(), YajvmError> {
+        for class in self.classes.iter_mut() {
+            class.layout_static_fields();
+        }
+
+        // The fields can be accessed via the subclasses of the classes declaring them, e.g. getstatic
+        // Derived.baseValue, so the symbols are resolved against their declaring classes.
+        for (symbol, vals) in self.cc.static_field_offset_values.iter() {
+            let (class_name, field_name) = symbol.rsplit_once('.').unwrap();
+            let class = self.static_field_declaring_class(class_name, field_name)?;
+            let offset = class.static_field(field_name).unwrap().offset;
+            let resolved = self.cc.i32_type.const_int(offset as u64, false);
+            for val in vals {
+                val.replace_all_uses_with(resolved);
+                // Removes the dummy load.
+                val.as_instruction_value().unwrap().erase_from_basic_block();
+            }
+        }
+
+        for (symbol, vals) in self.cc.static_field_class_id_values.iter() {
+            let (class_name, field_name) = symbol.rsplit_once('.').unwrap();
+            let class = self.static_field_declaring_class(class_name, field_name)?;
+            let class_id = self.assigned_class_id(&class.class_name);
+            let resolved = self.cc.i32_type.const_int(class_id as u64, false);
+            for val in vals {
+                val.replace_all_uses_with(resolved);
+                // Removes the dummy load.
+                val.as_instruction_value().unwrap().erase_from_basic_block();
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the class declaring the static field, which is looked up in the class, its superinterfaces,
+    /// and then its super class as per JVM spec (See 5.4.3.2).
+    fn static_field_declaring_class(
+        &self,
+        class_name: &str,
+        field_name: &str,
+    ) -> Result<&CompiledClass, YajvmError> {
+        self.find_static_field_declaring_class(class_name, field_name)
+            .ok_or_else(|| {
+                YajvmError::linkage(format!(
+                    "static field {}.{} not found",
+                    class_name, field_name
+                ))
+            })
+    }
+
+    fn find_static_field_declaring_class(
+        &self,
+        class_name: &str,
+        field_name: &str,
+    ) -> Option<&CompiledClass> {
+        let class = self.classes.iter().find(|c| c.class_name == class_name)?;
+        if class.static_field(field_name).is_some() {
+            return Some(class);
+        }
+        class
+            .interfaces
+            .iter()
+            .chain(class.super_class.iter())
+            .find_map(|name| self.find_static_field_declaring_class(name, field_name))
+    }
+
+    /// Redirects the calls to static methods and the ones invoked by invokespecial, e.g. super.toString(),
+    /// which are not declared in the class named in the instruction to the ones declared in its nearest ancestor.
+    fn resolve_inherited_methods(&mut self) -> Result<(), YajvmError> {
+        assert!(
+            !self.vtables.is_empty(),
+            "vtables must be constructed before resolving inherited methods"
+        );
+
+        let invoked = self
+            .cc
+            .invoked_static_method_symbols
+            .iter()
+            .map(|symbol| (symbol, true))
+            .chain(
+                self.cc
+                    .invoked_special_method_symbols
+                    .iter()
+                    .map(|symbol| (symbol, false)),
+            );
+        for (symbol, is_static) in invoked {
+            let declared = self.declared_method_symbol(symbol, is_static)?;
+            if declared == *symbol {
+                continue;
+            }
+
+            let called = self.cc.module.get_function(symbol).unwrap();
+            let resolved = self.cc.module.get_function(&declared).unwrap();
+            called
+                .as_global_value()
+                .as_pointer_value()
+                .replace_all_uses_with(resolved.as_global_value().as_pointer_value());
+            unsafe { called.delete() }
+        }
+        Ok(())
+    }
+
+    /// Returns the symbol of the function implementing the method, which can be declared in any of the ancestors
+    /// of the class in the symbol.
+    fn declared_method_symbol(&self, symbol: &str, is_static: bool) -> Result<String, YajvmError> {
+        let (class_name, method) = symbol.split_once('.').unwrap();
+        let mut class_id = self.resolve_class_id(class_name)?;
+        loop {
+            let class = &self.classes[class_id as usize];
+            let declared = format!("{}.{}", class.class_name, method);
+            if is_static {
+                if class.static_methods.iter().any(|m| m.symbol == declared) {
+                    return Ok(declared);
+                }
+            } else {
+                if class.special_methods.iter().any(|m| m.symbol == declared) {
+                    return Ok(declared);
+                }
+                // Overrides in Rust libraries are named like "java/lang/Object.toString:()Ljava/lang/String;@my/Class".
+                let found = class.virtual_methods.iter().find(|m| {
+                    m.symbol == declared
+                        || m.symbol.split_once('@').is_some_and(|(s, owner)| {
+                            owner == class.class_name && s.split_once('.').unwrap().1 == method
+                        })
+                });
+                if let Some(m) = found {
+                    return Ok(m.symbol.clone());
                 }
             }
+            if class_id == self.java_lang_object_class_id {
+                return Err(YajvmError::linkage(format!("method {} not found", symbol)));
+            }
+            class_id = self.class_parents[class_id as usize];
         }
     }
 
-    fn resolve_static_field_offsets(&mut self) {
-        for i in 0..self.classes.len() {
-            let class = &mut self.classes[i];
-            class.static_fields.sort_by(|a, b| a.cmp(&b));
-
-            for (j, field) in class.static_fields.iter().enumerate() {
-                let offset = j * 8; // Each static field takes 8 bytes.
-                let offset_symbol = format!("{}.{}", class.class_name, field);
-                let resolved = self.cc.i32_type.const_int(offset as u64, false);
-                if let Some(vals) = self.cc.static_field_offset_values.get(&offset_symbol) {
-                    for val in vals {
-                        val.replace_all_uses_with(resolved.into());
-                        // Removes the dummy load.
-                        val.as_instruction_value().unwrap().erase_from_basic_block();
+    /// Checks the assignments between the classes left by the verifier against the class hierarchy. Any class is
+    /// assignable to an interface, as the interface types are checked at run time instead (See 4.10.1.2).
+    fn check_assignability_constraints(&self) -> Result<(), YajvmError> {
+        for class in &self.classes {
+            for constraint in &class.assignability_constraints {
+                let to = self.resolve_class_id(&constraint.to)?;
+                if self.classes[to as usize].is_interface {
+                    continue;
+                }
+                let mut class_id = self.resolve_class_id(&constraint.from)?;
+                while class_id != to {
+                    if class_id == self.java_lang_object_class_id {
+                        return Err(YajvmError::Verify(constraint.to_error(&class.class_name)));
                     }
+                    class_id = self.class_parents[class_id as usize];
                 }
             }
         }
+        Ok(())
+    }
+
+    fn resolve_instance_field_offsets(&mut self) -> Result<(), YajvmError> {
+        assert!(
+            !self.class_parents.is_empty(),
+            "class_parents must be built before resolving instance field offsets"
+        );
+
+        let mut done = HashSet::new();
+        for i in 0..self.classes.len() {
+            self.layout_instance_fields(i as ClassID, &mut done);
+        }
+
+        for (symbol, vals) in self.cc.instance_field_offset_values.iter() {
+            let (class_name, field_name) = symbol.rsplit_once('.').unwrap();
+            let offset = self.instance_field_offset(class_name, field_name)?;
+            let resolved = self.cc.i32_type.const_int(offset as u64, false);
+            for val in vals {
+                val.replace_all_uses_with(resolved);
+                // Removes the dummy load.
+                val.as_instruction_value().unwrap().erase_from_basic_block();
+            }
+        }
+        Ok(())
+    }
+
+    /// Assigns the offsets of the instance fields declared in the class right after the ones of its parent.
+    /// Each instance field takes 8 bytes, and the first 8 bytes of any object are reserved for the vtable pointer.
+    fn layout_instance_fields(&mut self, i: ClassID, done: &mut HashSet<ClassID>) {
+        if done.contains(&i) {
+            return;
+        }
+        done.insert(i);
+
+        let parent_size = if i == self.java_lang_object_class_id {
+            0
+        } else {
+            let parent_class_id = self.class_parents[i as usize];
+            self.layout_instance_fields(parent_class_id, done);
+            self.classes[parent_class_id as usize].instance_size
+        };
+
+        let class = &mut self.classes[i as usize];
+        let mut offset = parent_size.max(8);
+        for field in class.instance_fields.iter() {
+            self.instance_field_offsets
+                .insert(format!("{}.{}", class.class_name, field), offset);
+            offset += 8; // Each instance field takes 8 bytes.
+        }
+        class.instance_size = class.instance_size.max(offset);
+    }
+
+    /// Returns the offset of the field in the instance of the class. The field can be declared in any of its ancestors.
+    fn instance_field_offset(&self, class_name: &str, field_name: &str) -> Result<u32, YajvmError> {
+        let mut class_id = self.resolve_class_id(class_name)?;
+        loop {
+            let class = &self.classes[class_id as usize];
+            let symbol = format!("{}.{}", class.class_name, field_name);
+            if let Some(offset) = self.instance_field_offsets.get(&symbol) {
+                return Ok(*offset);
+            }
+            if class_id == self.java_lang_object_class_id {
+                return Err(YajvmError::linkage(format!(
+                    "instance field {}.{} not found",
+                    class_name, field_name
+                )));
+            }
+            class_id = self.class_parents[class_id as usize];
+        }
     }
 
     fn declare_main_function(&self) -> FunctionValue<'ctx> {
@@ -209,7 +596,17 @@ impl<'ctx> CodeGen<'ctx> {
         )
     }
 
-    fn compile_main_function(&mut self) {
+    fn compile_main_function(&mut self) -> Result<(), YajvmError> {
+        let (main_class_name, _) = self.main_class_symbol.split_once('.').unwrap();
+        let main_class_id = self.resolve_class_id(main_class_name)?;
+        let main_fn = self
+            .cc
+            .module
+            .get_function(self.main_class_symbol.as_str())
+            .ok_or_else(|| {
+                YajvmError::linkage(format!("method {} not found", self.main_class_symbol))
+            })?;
+
         let main = self.declare_main_function();
         let entry = self.cc.context.append_basic_block(main, "entry");
         self.cc.builder.position_at_end(entry);
@@ -218,38 +615,56 @@ impl<'ctx> CodeGen<'ctx> {
 
         // 1. Class object allocations.
         for class in &self.classes {
+            // Rust libraries provide "X.clinit", and the static initializer of Java classes is "X.<clinit>:()V".
             let clinit = if let Some(clinit) = self
                 .cc
                 .module
                 .get_function(format!("{}.clinit", class.class_name).as_str())
-            {
+                .or_else(|| {
+                    self.cc
+                        .module
+                        .get_function(format!("{}.<clinit>:()V", class.class_name).as_str())
+                }) {
                 clinit.as_global_value().as_pointer_value()
             } else {
                 self.cc.void_ptr.const_null()
             };
-            self.compile_class_object_allocation(
+            let class_obj = self.compile_class_object_allocation(
                 isolate_ptr,
                 &class.class_name,
                 class.static_field_size(),
                 class.instance_size,
                 clinit,
-            )
+            );
+            self.compile_static_field_constants(class_obj, class);
         }
 
-        // Call initialization.
+        // The other classes are initialized on their first active use, but the main class is before main runs
+        // as per JVM spec (See 5.2).
+        let main_class_id = self.cc.i32_type.const_int(main_class_id as u64, false);
         let need_initialization_true = self.cc.context.bool_type().const_int(1, false);
-        for i in 0..=self.max_class_id {
-            let class_id = self.cc.i32_type.const_int(i as u64, false);
-            self.cc.builder.build_call(
-                self.cc.get_class_object_fn,
-                &[
-                    isolate_ptr.into(),
-                    class_id.into(),
-                    need_initialization_true.into(),
-                ],
-                "get_class_object",
-            );
-        }
+        self.cc.builder.build_call(
+            self.cc.get_class_object_fn,
+            &[
+                isolate_ptr.into(),
+                main_class_id.into(),
+                need_initialization_true.into(),
+            ],
+            "get_class_object",
+        );
+        let exception = load_pending_exception(&self.cc, isolate_ptr);
+        let initialized = self.cc.builder.build_is_null(exception, "initialized");
+        let run_block = self.cc.context.append_basic_block(main, "run");
+        let failed_block = self
+            .cc
+            .context
+            .append_basic_block(main, "initialization_failed");
+        self.cc
+            .builder
+            .build_conditional_branch(initialized, run_block, failed_block);
+        self.cc.builder.position_at_end(failed_block);
+        self.cc.builder.build_return(None);
+        self.cc.builder.position_at_end(run_block);
 
         // After the class object initialization, we can create the array object for args.
         let allocate_args = {
@@ -280,32 +695,28 @@ impl<'ctx> CodeGen<'ctx> {
             .unwrap();
 
         // Finally, call the main function.
-        let main_fn = self
-            .cc
-            .module
-            .get_function(self.main_class_symbol.as_str())
-            .unwrap();
         self.cc.builder.build_call(
             main_fn,
             &[isolate_ptr.into(), args_allocated.into()],
             "call main function",
         );
         self.cc.builder.build_return(None);
+        Ok(())
     }
 
     fn compile_class_object_allocation(
         &self,
-        isolate_ptr: PointerValue,
+        isolate_ptr: PointerValue<'ctx>,
         class_name: &str,
         static_field_size: u32,
         instance_size: u32,
-        clinit: PointerValue,
-    ) {
+        clinit: PointerValue<'ctx>,
+    ) -> PointerValue<'ctx> {
         let class_id = self
             .cc
             .context
             .i32_type()
-            .const_int(self.class_id(class_name) as u64, false);
+            .const_int(self.assigned_class_id(class_name) as u64, false);
         let static_field_size = self
             .cc
             .context
@@ -326,21 +737,81 @@ impl<'ctx> CodeGen<'ctx> {
                 .as_pointer_value()
         };
 
-        self.cc.builder.build_call(
-            self.cc.new_class_object_fn,
-            &[
-                isolate_ptr.into(),
-                class_id.into(),
-                static_field_size.into(),
-                instance_size.into(),
-                vtable.into(),
-                clinit.into(),
-            ],
-            "new_class_object",
-        );
+        self.cc
+            .builder
+            .build_call(
+                self.cc.new_class_object_fn,
+                &[
+                    isolate_ptr.into(),
+                    class_id.into(),
+                    static_field_size.into(),
+                    instance_size.into(),
+                    vtable.into(),
+                    clinit.into(),
+                ],
+                "new_class_object",
+            )
+            .try_as_basic_value()
+            .left()
+            .unwrap()
+            .into_pointer_value()
+    }
+
+    /// Stores the values of the ConstantValue attributes to the static fields, which are visible before the
+    /// static initializer runs as per JVM spec (See 5.5).
+    fn compile_static_field_constants(&self, class_obj: PointerValue<'ctx>, class: &CompiledClass) {
+        if class.static_field_constants.is_empty() {
+            return;
+        }
+        let fields_ptr = self
+            .cc
+            .builder
+            .build_load(self.cc.void_ptr, class_obj, "static_fields_ptr")
+            .into_pointer_value();
+        for (field_name, constant) in &class.static_field_constants {
+            let field = class.static_field(field_name).unwrap();
+            let offset = self.cc.i32_type.const_int(field.offset as u64, false);
+            let value: BasicValueEnum = match constant {
+                // The fields of boolean, byte, char and short are narrower than the int constants.
+                StaticFieldConstant::Int(v) => {
+                    static_field_storage_type(&self.cc, &field.field_type())
+                        .into_int_type()
+                        .const_int(*v as u64, true)
+                        .into()
+                }
+                StaticFieldConstant::Long(v) => self.cc.i64_type.const_int(*v as u64, true).into(),
+                StaticFieldConstant::Float(v) => self
+                    .cc
+                    .context
+                    .f32_type()
+                    .const_float(f32::from_bits(*v) as f64)
+                    .into(),
+                StaticFieldConstant::Double(v) => self
+                    .cc
+                    .context
+                    .f64_type()
+                    .const_float(f64::from_bits(*v))
+                    .into(),
+                StaticFieldConstant::String(v) => self.cc.get_const_string_global(v).into(),
+            };
+            let ptr = unsafe {
+                let ptr = self.cc.builder.build_gep(
+                    self.cc.i8_type,
+                    fields_ptr,
+                    &[offset],
+                    "static_field_ptr_as_byte_ptr",
+                );
+                self.cc.builder.build_pointer_cast(
+                    ptr,
+                    value.get_type().ptr_type(AddressSpace::default()),
+                    "static_field_ptr",
+                )
+            };
+            self.cc.builder.build_store(ptr, value);
+        }
     }
 
-    fn construct_vtables(&mut self) {
+    fn construct_vtables(&mut self) -> Result<(), YajvmError> {
         assert!(
             !self.class_parents.is_empty(),
             "class_parents must be built before constructing vtables"
@@ -354,6 +825,245 @@ impl<'ctx> CodeGen<'ctx> {
                 done.insert(i);
             }
         }
+
+        for (symbol, vals) in self.cc.virtual_method_offset_values.iter() {
+            let offset = self.vtable_offset(symbol)?;
+            let resolved = self.cc.i32_type.const_int(offset as u64, false);
+            for val in vals {
+                val.replace_all_uses_with(resolved);
+                // Removes the dummy load.
+                val.as_instruction_value().unwrap().erase_from_basic_block();
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the offset of the method in the vtable. The method can be declared in any of the ancestors
+    /// of the class in the symbol, e.g. "my/Exception.getMessage:()Ljava/lang/String;" is resolved to
+    /// the offset of "java/lang/Throwable.getMessage:()Ljava/lang/String;".
+    fn vtable_offset(&self, symbol: &str) -> Result<usize, YajvmError> {
+        let (class_name, method) = symbol.split_once('.').unwrap();
+        self.find_vtable_offset(self.resolve_class_id(class_name)?, method, true)
+            .ok_or_else(|| YajvmError::linkage(format!("virtual method {} not found", symbol)))
+    }
+
+    /// Returns the offset of the method in the vtable of the class, e.g. "toString:()Ljava/lang/String;",
+    /// if it or any of its ancestors declares it. Private methods are only found if `include_private`,
+    /// as they are never overridden.
+    fn find_vtable_offset(
+        &self,
+        class_id: ClassID,
+        method: &str,
+        include_private: bool,
+    ) -> Option<usize> {
+        let mut class_id = class_id;
+        loop {
+            let class = &self.classes[class_id as usize];
+            let symbol = format!("{}.{}", class.class_name, method);
+            if let Some(offset) = self.vtable_offsets.get(&symbol) {
+                if include_private || !class.private_methods.contains(&symbol) {
+                    return Some(*offset);
+                }
+            }
+            if class_id == self.java_lang_object_class_id {
+                return None;
+            }
+            class_id = self.class_parents[class_id as usize];
+        }
+    }
+
+    /// Returns all the interfaces implemented by the class including the ones of its ancestors,
+    /// and the ones extended by them.
+    pub fn all_interfaces(&self, class_id: ClassID) -> Vec<ClassID> {
+        let mut ret = Vec::new();
+        let mut pending = Vec::new();
+        let mut class_id = class_id;
+        loop {
+            pending.extend(self.classes[class_id as usize].interfaces.iter().rev());
+            if class_id == self.java_lang_object_class_id {
+                break;
+            }
+            class_id = self.class_parents[class_id as usize];
+        }
+        while let Some(interface) = pending.pop() {
+            let interface_id = self.assigned_class_id(interface);
+            if !ret.contains(&interface_id) {
+                ret.push(interface_id);
+                pending.extend(self.classes[interface_id as usize].interfaces.iter().rev());
+            }
+        }
+        ret
+    }
+
+    /// Returns the methods of the interfaces implemented by the class, e.g. "run:()V", with their default
+    /// implementations, or null if abstract. The default method of the first interface wins.
+    fn interface_methods(&self, class_id: ClassID) -> Vec<(String, PointerValue<'ctx>)> {
+        let mut ret: Vec<(String, PointerValue<'ctx>)> = Vec::new();
+        for interface_id in self.all_interfaces(class_id) {
+            let interface = &self.classes[interface_id as usize];
+            for m in &interface.virtual_methods {
+                if interface.private_methods.contains(&m.symbol) {
+                    continue;
+                }
+                let (_, method) = m.symbol.split_once('.').unwrap();
+                let offset = self
+                    .vtable_offset(&m.symbol)
+                    .expect("the method is declared by the interface");
+                let func_ptr = self.vtables[&interface_id][offset];
+                match ret.iter_mut().find(|(name, _)| name == method) {
+                    Some((_, ptr)) if ptr.is_null() => *ptr = func_ptr,
+                    Some(_) => {}
+                    None => ret.push((method.to_string(), func_ptr)),
+                }
+            }
+        }
+        ret
+    }
+
+    /// Builds the functions dispatching the interface methods. Each dispatcher looks up the itable of the method,
+    /// i.e. the constant array of the implementations indexed by the class ID, with the class ID of the receiver
+    /// and calls the implementation. A null entry throws IncompatibleClassChangeError if the class of the receiver
+    /// doesn't implement the interface, or AbstractMethodError if it has no implementation of the method.
+    fn construct_interface_dispatchers(&mut self) -> Result<(), YajvmError> {
+        assert!(
+            !self.vtables.is_empty(),
+            "vtables must be constructed before building interface dispatchers"
+        );
+
+        let mut symbols = self
+            .cc
+            .invoked_interface_method_symbols
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        symbols.sort();
+        for symbol in symbols {
+            let (interface_name, method) = symbol.split_once('.').unwrap();
+            let interface_id = self.resolve_class_id(interface_name)?;
+            let dispatcher = self
+                .cc
+                .module
+                .get_function(format!("{}{}", INTERFACE_DISPATCH_PREFIX, symbol).as_str())
+                .unwrap();
+            let entry = self.cc.context.append_basic_block(dispatcher, "entry");
+            self.cc.builder.position_at_end(entry);
+            let args = dispatcher
+                .get_param_iter()
+                .map(|p| p.into())
+                .collect::<Vec<BasicMetadataValueEnum>>();
+
+            let interface = &self.classes[interface_id as usize];
+            if interface.private_methods.contains(&symbol) {
+                // Private interface methods are invoked by invokeinterface but never overridden.
+                let func = self.cc.module.get_function(&symbol).unwrap();
+                let ret = self.cc.builder.build_call(func, &args, "call");
+                build_return_call_result(&self.cc, ret);
+                continue;
+            }
+
+            // The itable holds the implementation of the method for each class ID, or null if the class doesn't
+            // implement the interface or the method is abstract.
+            let itable_entries = (0..=self.max_class_id)
+                .map(|class_id| self.itable_entry(class_id, interface_id, method))
+                .collect::<Vec<_>>();
+            let itable_type = self.cc.void_ptr.array_type(itable_entries.len() as u32);
+            let itable = self.cc.module.add_global(
+                itable_type,
+                None,
+                format!("itable###{}", symbol).as_str(),
+            );
+            itable.set_initializer(&self.cc.void_ptr.const_array(&itable_entries));
+            itable.set_constant(true);
+
+            let isolate = dispatcher.get_nth_param(0).unwrap();
+            let this = dispatcher.get_nth_param(1).unwrap();
+            let class_id = self
+                .cc
+                .builder
+                .build_call(
+                    self.cc.class_id_of_fn,
+                    &[isolate.into(), this.into()],
+                    "class_id",
+                )
+                .try_as_basic_value()
+                .left()
+                .unwrap()
+                .into_int_value();
+            let func_ptr = unsafe {
+                let slot = self.cc.builder.build_in_bounds_gep(
+                    itable_type,
+                    itable.as_pointer_value(),
+                    &[self.cc.i32_type.const_zero(), class_id],
+                    "itable_slot",
+                );
+                self.cc
+                    .builder
+                    .build_load(self.cc.void_ptr, slot, "func_ptr")
+                    .into_pointer_value()
+            };
+            let is_null = self.cc.builder.build_is_null(func_ptr, "is_null");
+            let call_block = self.cc.context.append_basic_block(dispatcher, "call");
+            let throw_block = self.cc.context.append_basic_block(dispatcher, "throw");
+            self.cc
+                .builder
+                .build_conditional_branch(is_null, throw_block, call_block);
+
+            self.cc.builder.position_at_end(call_block);
+            let ret =
+                self.cc
+                    .builder
+                    .build_indirect_call(dispatcher.get_type(), func_ptr, &args, "call");
+            build_return_call_result(&self.cc, ret);
+
+            // The exception is dispatched by the caller, which checks the pending exception after the call.
+            self.cc.builder.position_at_end(throw_block);
+            let method_name = self
+                .cc
+                .builder
+                .build_global_string_ptr(&abstract_method_signature(method), "method");
+            self.cc.builder.build_call(
+                self.cc.throw_interface_method_error_fn,
+                &[
+                    isolate.into(),
+                    this.into(),
+                    self.cc
+                        .i32_type
+                        .const_int(interface_id as u64, false)
+                        .into(),
+                    method_name.as_pointer_value().into(),
+                ],
+                "throw",
+            );
+            match dispatcher.get_type().get_return_type() {
+                Some(return_type) => self
+                    .cc
+                    .builder
+                    .build_return(Some(&return_type.const_zero())),
+                None => self.cc.builder.build_return(None),
+            };
+        }
+        Ok(())
+    }
+
+    /// Returns the implementation of the interface method for the class, or null if the class doesn't implement
+    /// the interface or has no implementation of the method.
+    fn itable_entry(
+        &self,
+        class_id: ClassID,
+        interface_id: ClassID,
+        method: &str,
+    ) -> PointerValue<'ctx> {
+        let class = &self.classes[class_id as usize];
+        if class.is_interface || !self.all_interfaces(class_id).contains(&interface_id) {
+            return self.cc.void_ptr.const_null();
+        }
+        match (
+            self.find_vtable_offset(class_id, method, false),
+            self.vtables.get(&class_id),
+        ) {
+            (Some(offset), Some(vtable)) => vtable[offset],
+            _ => self.cc.void_ptr.const_null(),
+        }
     }
 
     fn construct_vtable(&mut self, i: ClassID, done: &mut HashSet<ClassID>) {
@@ -365,6 +1075,14 @@ impl<'ctx> CodeGen<'ctx> {
             self.construct_vtable(parent_class_id, done);
             done.insert(parent_class_id);
         }
+        // The default methods are taken from the vtables of the interfaces.
+        for interface in self.classes[i as usize].interfaces.clone() {
+            let interface_id = self.assigned_class_id(&interface);
+            if !done.contains(&interface_id) {
+                self.construct_vtable(interface_id, done);
+                done.insert(interface_id);
+            }
+        }
         let class = &mut self.classes.get_mut(i as usize).unwrap();
 
         // Clone the parent vtable.
@@ -378,6 +1096,7 @@ impl<'ctx> CodeGen<'ctx> {
         class
             .virtual_methods
             .sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        let class = &self.classes[i as usize];
 
         for method in class.virtual_methods.iter() {
             let symbol = &method.symbol;
@@ -393,37 +1112,56 @@ impl<'ctx> CodeGen<'ctx> {
                     .collect::<String>();
                 let method_type = descriptor::parse_method_descriptor(&desc);
 
-                let f = self.cc.module.add_function(
-                    symbol.as_str(),
-                    self.cc
-                        .llvm_function_type_from_method_type(&method_type, false),
-                    None,
-                );
+                // It can be already declared by invokespecial, e.g. super.toString().
+                let f = self.cc.module.get_function(symbol).unwrap_or_else(|| {
+                    self.cc.module.add_function(
+                        symbol.as_str(),
+                        self.cc
+                            .llvm_function_type_from_method_type(&method_type, false),
+                        None,
+                    )
+                });
                 self.cc
                     .execution_engine
                     .add_global_mapping(&f, method_ptr as usize);
                 f.as_global_value().as_pointer_value()
             } else {
-                let func = self.cc.module.get_function(symbol.as_str()).unwrap();
-                func.as_global_value().as_pointer_value()
+                match self.cc.module.get_function(symbol.as_str()) {
+                    Some(func) if func.count_basic_blocks() > 0 => {
+                        func.as_global_value().as_pointer_value()
+                    }
+                    // Abstract methods are never called since their classes can't be instantiated.
+                    _ => self.cc.void_ptr.const_null(),
+                }
             };
 
-            if let Some(overrides) = &method.overrides {
-                let offset = self.vtable_offsets.get(overrides).unwrap();
+            let overridden_offset = if let Some(overrides) = &method.overrides {
+                Some(*self.vtable_offsets.get(overrides).unwrap())
+            } else if is_java_lang_object || class.private_methods.contains(symbol) {
+                None
+            } else {
+                let (_, method) = symbol.split_once('.').unwrap();
+                self.find_vtable_offset(parent_class_id, method, false)
+            };
+            if let Some(offset) = overridden_offset {
                 // This method is already in the vtable. so replace it with the new one.
-                methods[*offset] = func_ptr;
+                methods[offset] = func_ptr;
             } else {
                 let offset = methods.len();
                 methods.push(func_ptr);
                 self.vtable_offsets.insert(symbol.clone(), offset);
+            }
+        }
 
-                if let Some(vals) = self.cc.virtual_method_offset_values.get(symbol) {
-                    for val in vals {
-                        let offset = self.cc.i32_type.const_int(offset as u64, false);
-                        val.replace_all_uses_with(offset.into());
-                        // Removes the dummy load.
-                        val.as_instruction_value().unwrap().erase_from_basic_block();
-                    }
+        // The default methods not implemented by the class, and the abstract ones of abstract classes,
+        // which can be invoked by invokevirtual on the class.
+        if !class.is_interface {
+            for (method, func_ptr) in self.interface_methods(i) {
+                if self.find_vtable_offset(i, &method, false).is_none() {
+                    let offset = methods.len();
+                    methods.push(func_ptr);
+                    self.vtable_offsets
+                        .insert(format!("{}.{}", class.class_name, method), offset);
                 }
             }
         }
@@ -452,6 +1190,42 @@ impl<'ctx> CodeGen<'ctx> {
     }
 }
 
+/// Returns the interface method, e.g. "greet:()Ljava/lang/String;", in the form of the messages of
+/// AbstractMethodError, e.g. "abstract java.lang.String greet()".
+fn abstract_method_signature(method: &str) -> String {
+    fn type_name(field_type: &FieldType) -> String {
+        match field_type {
+            FieldType::BaseType(base_type) => base_type.to_string(),
+            FieldType::ArrayType(element_type) => format!("{}[]", type_name(element_type)),
+            object_type => object_type.to_string().replace('/', "."),
+        }
+    }
+    let (name, descriptor) = method.split_once(':').unwrap();
+    let method_type = parse_method_descriptor(&descriptor.to_string());
+    let parameter_types = method_type
+        .parameter_types
+        .iter()
+        .map(type_name)
+        .collect::<Vec<_>>();
+    let return_type = method_type
+        .return_type
+        .as_ref()
+        .map_or("void".to_string(), type_name);
+    format!(
+        "abstract {} {}({})",
+        return_type,
+        name,
+        parameter_types.join(", ")
+    )
+}
+
+fn build_return_call_result(cc: &CodegenContext, ret: CallSiteValue) {
+    match ret.try_as_basic_value().left() {
+        Some(v) => cc.builder.build_return(Some(&v)),
+        None => cc.builder.build_return(None),
+    };
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -467,7 +1241,8 @@ mod test {
     fn test_done_compilation() {
         let mut codegen = CodeGen::new("Main");
         let mut main = CompiledClass::new("Main", None);
-        let isolate: [u64; 6] = [0, 0, 0, 0, 0, 0];
+        // The second word is the pending exception of the Isolate, which must be left null.
+        let isolate: [u64; 7] = [0, 0, 0, 0, 0, 0, 0];
         extern "C" fn main_fn(isolate: &mut Isolate, _args: *mut u8) {
             unsafe {
                 let ptr = std::mem::transmute::<_, *mut u64>(isolate);
@@ -485,11 +1260,11 @@ mod test {
             match class_id {
                 0 => unsafe {
                     let ptr = std::mem::transmute::<_, *mut u64>(isolate);
-                    (*ptr.offset(1)) = 0xdeadbeaf_beafdead;
+                    (*ptr.offset(2)) = 0xdeadbeaf_beafdead;
                 },
                 1 => unsafe {
                     let ptr = std::mem::transmute::<_, *mut u64>(isolate);
-                    (*ptr.offset(2)) = 0xdeadbeaf_beafdead;
+                    (*ptr.offset(3)) = 0xdeadbeaf_beafdead;
                 },
                 _ => {}
             }
@@ -503,11 +1278,11 @@ mod test {
             match class_id {
                 0 => unsafe {
                     let ptr = std::mem::transmute::<_, *mut u64>(isolate);
-                    (*ptr.offset(3)) = 0xdeadbeaf_beafdead;
+                    (*ptr.offset(4)) = 0xdeadbeaf_beafdead;
                 },
                 1 => unsafe {
                     let ptr = std::mem::transmute::<_, *mut u64>(isolate);
-                    (*ptr.offset(4)) = 0xdeadbeaf_beafdead;
+                    (*ptr.offset(5)) = 0xdeadbeaf_beafdead;
                 },
                 _ => {}
             }
@@ -517,7 +1292,7 @@ mod test {
         extern "C" fn allocate_args(isolate: &mut Isolate, args: &mut Vec<String>) -> JavaArrayRef {
             unsafe {
                 let isolate_ptr = std::mem::transmute::<_, *mut u64>(isolate);
-                (*isolate_ptr.offset(5)) = 0xdeadbeaf_beafdead;
+                (*isolate_ptr.offset(6)) = 0xdeadbeaf_beafdead;
                 let args_ptr = std::mem::transmute::<_, *mut u64>(args);
                 (*args_ptr) = 0xbeaf;
             }
@@ -531,7 +1306,7 @@ mod test {
 
         codegen.add_class(dummy_java_lang_object());
         codegen.add_class(main);
-        codegen.done_compilation();
+        codegen.done_compilation().unwrap();
 
         codegen.cc.execution_engine.add_global_mapping(
             &codegen
@@ -578,11 +1353,13 @@ mod test {
         }
 
         assert_eq!(isolate[0], 0xdeadbeaf);
-        assert_eq!(isolate[1], 0xdeadbeaf_beafdead);
+        assert_eq!(isolate[1], 0);
         assert_eq!(isolate[2], 0xdeadbeaf_beafdead);
         assert_eq!(isolate[3], 0xdeadbeaf_beafdead);
+        // Only the main class (ID 0) is initialized before main runs.
         assert_eq!(isolate[4], 0xdeadbeaf_beafdead);
-        assert_eq!(isolate[5], 0xdeadbeaf_beafdead);
+        assert_eq!(isolate[5], 0);
+        assert_eq!(isolate[6], 0xdeadbeaf_beafdead);
         assert_eq!(args[0], 0xbeaf);
     }
 
@@ -594,18 +1371,32 @@ mod test {
         codegen.add_class(CompiledClass::new("c", Some("b".to_string())));
         codegen.add_class(CompiledClass::new("b", Some("a".to_string())));
 
-        codegen.assign_class_ids();
-        codegen.build_inheritance_tree();
+        codegen.assign_class_ids().unwrap();
+        codegen.build_inheritance_tree().unwrap();
         assert_eq!(codegen.java_lang_object_class_id, 3);
-        assert_eq!(codegen.class_id("a"), 0);
-        assert_eq!(codegen.class_id("b"), 1);
-        assert_eq!(codegen.class_id("c"), 2);
+        assert_eq!(codegen.class_id("a"), Some(0));
+        assert_eq!(codegen.class_id("b"), Some(1));
+        assert_eq!(codegen.class_id("c"), Some(2));
         assert_eq!(codegen.class_parents[0], 3);
         assert_eq!(codegen.class_parents[1], 0);
         assert_eq!(codegen.class_parents[2], 1);
         assert_eq!(codegen.class_parents[3], 3);
     }
 
+    #[test]
+    fn test_build_inheritance_tree_with_missing_component_class() {
+        let mut codegen = CodeGen::new("Main");
+        codegen.add_class(CompiledClass::new("java/lang/Object", None));
+        codegen.add_class(new_compiled_reference_array_class("[La;"));
+        codegen.add_class(new_compiled_reference_array_class("[[La;"));
+
+        codegen.assign_class_ids().unwrap();
+        match codegen.build_inheritance_tree() {
+            Err(YajvmError::Linkage { message }) => assert_eq!(message, "class a not found"),
+            r => panic!("{:?}", r),
+        }
+    }
+
     #[test]
     fn test_add_class() {
         let mut codegen = CodeGen::new("Main");
@@ -676,9 +1467,9 @@ mod test {
         codegen.add_class(dummy_java_lang_object());
 
         codegen.add_class(CompiledClass::new("MyClass", None));
-        codegen.assign_class_ids();
-        codegen.build_inheritance_tree();
-        codegen.construct_vtables();
+        codegen.assign_class_ids().unwrap();
+        codegen.build_inheritance_tree().unwrap();
+        codegen.construct_vtables().unwrap();
 
         let main = codegen.declare_main_function();
         let blk = codegen.cc.context.append_basic_block(main, "entry");
@@ -746,10 +1537,13 @@ mod test {
     fn test_resolve_static_field_offsets() {
         let mut codegen = CodeGen::new("Main");
         let mut a = CompiledClass::new("a", None);
-        a.static_fields = vec!["foo".to_string(), "bar".to_string(), "foobar".to_string()];
+        a.add_static_field("foo", "I");
+        a.add_static_field("bar", "J");
+        a.add_static_field("foobar", "Ljava/lang/String;");
         codegen.add_class(a);
         let mut b = CompiledClass::new("b", None);
-        b.static_fields = vec!["cat".to_string(), "dog".to_string()];
+        b.add_static_field("cat", "B");
+        b.add_static_field("dog", "S");
         codegen.add_class(b);
 
         fn add_get_static_field_offset_fn(cc: &mut CodegenContext, class_name: &str, field: &str) {
@@ -773,7 +1567,7 @@ mod test {
         add_get_static_field_offset_fn(&mut codegen.cc, "b", "dog");
         add_get_static_field_offset_fn(&mut codegen.cc, "b", "cat");
 
-        codegen.resolve_static_field_offsets();
+        codegen.resolve_static_field_offsets().unwrap();
         codegen.cc.module.print_to_stderr();
         codegen.cc.module.verify().unwrap();
 
@@ -790,11 +1584,91 @@ mod test {
             unsafe { fn_ptr() }
         }
 
-        assert_eq!(0, call_get_static_field_offset(&codegen.cc, "a", "bar"));
-        assert_eq!(8, call_get_static_field_offset(&codegen.cc, "a", "foo"));
-        assert_eq!(16, call_get_static_field_offset(&codegen.cc, "a", "foobar"));
-        assert_eq!(0, call_get_static_field_offset(&codegen.cc, "b", "cat"));
-        assert_eq!(8, call_get_static_field_offset(&codegen.cc, "b", "dog"));
+        // The references come first, and then the primitives from the largest to the smallest.
+        assert_eq!(0, call_get_static_field_offset(&codegen.cc, "a", "foobar"));
+        assert_eq!(8, call_get_static_field_offset(&codegen.cc, "a", "bar"));
+        assert_eq!(16, call_get_static_field_offset(&codegen.cc, "a", "foo"));
+        assert_eq!(0, call_get_static_field_offset(&codegen.cc, "b", "dog"));
+        assert_eq!(2, call_get_static_field_offset(&codegen.cc, "b", "cat"));
+    }
+
+    #[test]
+    fn test_resolve_instance_field_offsets() {
+        let mut codegen = CodeGen::new("Main");
+        codegen.add_class(dummy_java_lang_object());
+        let mut a = CompiledClass::new("a", None);
+        a.instance_fields = vec!["foo".to_string(), "bar".to_string()];
+        codegen.add_class(a);
+        let mut b = CompiledClass::new("b", Some("a".to_string()));
+        b.instance_fields = vec!["cat".to_string()];
+        codegen.add_class(b);
+        codegen.add_class(CompiledClass::new("c", Some("b".to_string())));
+
+        fn add_get_instance_field_offset_fn(
+            cc: &mut CodegenContext,
+            class_name: &str,
+            field: &str,
+        ) {
+            let fn_type = cc.i32_type.fn_type(&[], false);
+            let fn_value = cc.module.add_function(
+                format!("get_{}_{}", class_name, field).as_str(),
+                fn_type,
+                None,
+            );
+            let entry = cc.context.append_basic_block(fn_value, "entry");
+            cc.builder.position_at_end(entry);
+            let offset =
+                cc.get_instance_field_offset_value(&class_name.to_string(), &field.to_string());
+            cc.builder.build_return(Some(&offset.as_basic_value_enum()));
+        }
+
+        add_get_instance_field_offset_fn(&mut codegen.cc, "a", "foo");
+        add_get_instance_field_offset_fn(&mut codegen.cc, "a", "bar");
+        add_get_instance_field_offset_fn(&mut codegen.cc, "b", "cat");
+        // Inherited fields are resolved through the parents.
+        add_get_instance_field_offset_fn(&mut codegen.cc, "b", "foo");
+        add_get_instance_field_offset_fn(&mut codegen.cc, "c", "bar");
+        add_get_instance_field_offset_fn(&mut codegen.cc, "c", "cat");
+
+        codegen.assign_class_ids().unwrap();
+        codegen.build_inheritance_tree().unwrap();
+        codegen.resolve_instance_field_offsets().unwrap();
+        codegen.cc.module.print_to_stderr();
+        codegen.cc.module.verify().unwrap();
+
+        fn call_get_instance_field_offset(
+            cc: &CodegenContext,
+            class_name: &str,
+            field_name: &str,
+        ) -> u32 {
+            let fn_ptr = cc
+                .execution_engine
+                .get_function_address(format!("get_{}_{}", class_name, field_name).as_str())
+                .unwrap();
+            let fn_ptr = unsafe { std::mem::transmute::<_, unsafe extern "C" fn() -> u32>(fn_ptr) };
+            unsafe { fn_ptr() }
+        }
+
+        // The first 8 bytes are reserved for the vtable pointer.
+        assert_eq!(8, call_get_instance_field_offset(&codegen.cc, "a", "foo"));
+        assert_eq!(16, call_get_instance_field_offset(&codegen.cc, "a", "bar"));
+        assert_eq!(24, call_get_instance_field_offset(&codegen.cc, "b", "cat"));
+        assert_eq!(8, call_get_instance_field_offset(&codegen.cc, "b", "foo"));
+        assert_eq!(16, call_get_instance_field_offset(&codegen.cc, "c", "bar"));
+        assert_eq!(24, call_get_instance_field_offset(&codegen.cc, "c", "cat"));
+
+        assert_eq!(
+            24,
+            codegen.classes[codegen.class_id("a").unwrap() as usize].instance_size
+        );
+        assert_eq!(
+            32,
+            codegen.classes[codegen.class_id("b").unwrap() as usize].instance_size
+        );
+        assert_eq!(
+            32,
+            codegen.classes[codegen.class_id("c").unwrap() as usize].instance_size
+        );
     }
 
     #[test]
@@ -819,10 +1693,10 @@ mod test {
         add_get_class_id_fn(&mut codegen.cc, "b");
         add_get_class_id_fn(&mut codegen.cc, "c");
 
-        codegen.assign_class_ids();
-        assert_eq!(0, codegen.class_id("a"));
-        assert_eq!(1, codegen.class_id("b"));
-        assert_eq!(2, codegen.class_id("c"));
+        codegen.assign_class_ids().unwrap();
+        assert_eq!(Some(0), codegen.class_id("a"));
+        assert_eq!(Some(1), codegen.class_id("b"));
+        assert_eq!(Some(2), codegen.class_id("c"));
 
         codegen.cc.module.print_to_stderr();
         codegen.cc.module.verify().unwrap();
@@ -949,9 +1823,9 @@ mod test {
         add_get_virtual_method_offset_fn(&mut codegen.cc, "foo/bar/Native.BBB");
         add_get_virtual_method_offset_fn(&mut codegen.cc, "foo/bar/Native.CCC");
 
-        codegen.assign_class_ids();
-        codegen.build_inheritance_tree();
-        codegen.construct_vtables();
+        codegen.assign_class_ids().unwrap();
+        codegen.build_inheritance_tree().unwrap();
+        codegen.construct_vtables().unwrap();
 
         add_get_global_function(&codegen.cc, "vtable###foo/bar/Native");
 
@@ -5,24 +5,48 @@ use crate::codegen::descriptor::{
 #[allow(unused_imports)]
 use bitflags::Flags;
 #[warn(unused_imports)]
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 
-use crate::codegen::codegen_class_static_fields::load_class_obj_static_field_ptr;
+use crate::codegen::codegen_class_exceptions::{load_pending_exception, store_pending_exception};
+use crate::codegen::codegen_class_instance_fields::{
+    build_instance_field_value_narrowing, load_instance_field_ptr,
+};
+use crate::codegen::codegen_class_lambda::{LambdaClass, LambdaImplementation, LAMBDA_METAFACTORY};
+use crate::codegen::codegen_class_stack_map::{expand_stack_map_frames, FrameTypes};
+use crate::codegen::codegen_class_static_fields::{
+    build_class_initialization, build_static_field_class_initialization, build_static_field_load,
+    build_static_field_store,
+};
+use crate::codegen::codegen_class_string_concat::{
+    build_string_concat, MAKE_CONCAT_WITH_CONSTANTS,
+};
 use crate::codegen::codegen_context::CodegenContext;
-use crate::compiled_class::{CompiledClass, StaticMethodInfo};
+use crate::compiled_class::{
+    CompiledClass, SpecialMethodInfo, StaticFieldConstant, StaticFieldInfo, StaticMethodInfo,
+    VirtualMethodInfo,
+};
+use crate::error::YajvmError;
 use crate::tracing::{insert_call_tracing_after, insert_call_tracing_before};
-use classfile_parser::attribute_info::code_attribute_parser;
+use crate::verifier::{verify_class, AssignabilityConstraint, VerifyError};
+use classfile_parser::attribute_info::{
+    bootstrap_methods_attribute_parser, code_attribute_parser, constant_value_attribute_parser,
+    stack_map_table_attribute_parser, AttributeInfo, BootstrapMethod, CodeAttribute,
+    ExceptionEntry, VerificationTypeInfo,
+};
 use classfile_parser::class_parser;
 use classfile_parser::code_attribute::code_parser;
 use classfile_parser::code_attribute::Instruction;
 use classfile_parser::constant_info::ConstantInfo;
 use classfile_parser::field_info::FieldAccessFlags;
 use classfile_parser::method_info::{MethodAccessFlags, MethodInfo};
-use classfile_parser::ClassFile;
+use classfile_parser::{ClassAccessFlags, ClassFile};
 use inkwell::basic_block::BasicBlock;
-use inkwell::types::{BasicType, BasicTypeEnum};
+use inkwell::types::{BasicType, BasicTypeEnum, IntType};
 use inkwell::values::FunctionValue;
-use inkwell::values::{BasicValue, BasicValueEnum, PhiValue, PointerValue};
+use inkwell::values::{
+    BasicMetadataValueEnum, BasicValue, BasicValueEnum, FloatValue, IntValue, PhiValue,
+    PointerValue,
+};
 use inkwell::{AddressSpace, FloatPredicate, IntPredicate};
 
 // https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html
@@ -40,12 +64,15 @@ pub struct CompilationState<'ctx> {
     is_static: bool,
     labels: HashMap<usize, BasicBlock<'ctx>>,
     label_field_type_stack: HashMap<usize, Vec<FieldType>>,
+    /// The types of the locals at the beginning of the labels, which are None for the unusable ones.
+    label_locals_field_types: HashMap<usize, Vec<Option<FieldType>>>,
     label_phis: HashMap<BasicBlock<'ctx>, LabelPhis<'ctx>>,
-    ignored_instructions: HashSet<usize>,
+    exception_table: Vec<ExceptionEntry>,
 }
 
 struct LabelPhis<'ctx> {
-    local_phis: Vec<PhiValue<'ctx>>,
+    /// The phis of the usable locals with their indices.
+    local_phis: Vec<(usize, PhiValue<'ctx>)>,
     stack_phis: Vec<PhiValue<'ctx>>,
 }
 
@@ -68,8 +95,9 @@ impl<'ctx> CompilationState<'ctx> {
         self.labels.clear();
         self.field_type_stack.truncate(0);
         self.locals_field_types.truncate(0);
-        self.ignored_instructions.clear();
         self.label_field_type_stack.clear();
+        self.label_locals_field_types.clear();
+        self.exception_table.clear();
     }
 
     pub fn isolate_ptr(&self) -> PointerValue<'ctx> {
@@ -79,8 +107,9 @@ impl<'ctx> CompilationState<'ctx> {
             .into_pointer_value()
     }
 
+    /// Returns the types of the locals at the entry of the function, i.e. the parameters.
     pub fn local_field_types(&self) -> &Vec<Option<FieldType>> {
-        &self.locals_field_types
+        &self.label_locals_field_types[&0]
     }
 
     pub fn function(&self) -> FunctionValue<'ctx> {
@@ -108,22 +137,23 @@ impl<'ctx> CompilationState<'ctx> {
         self.locals_field_types.resize(count, None);
     }
 
-    fn initialize_local(
-        &mut self,
-        index: usize,
-        field_type: FieldType,
-        value: BasicValueEnum<'ctx>,
-    ) {
-        if index >= self.locals.len() {
-            self.locals.resize(index + 1, None);
-            self.locals_field_types.resize(index + 1, None);
+    /// Records the type of the value stored in the local while analyzing the code. Longs and doubles also take
+    /// the next local, which becomes unusable.
+    fn store_local_type(&mut self, index: usize, field_type: FieldType) {
+        let is_wide = matches!(
+            field_type,
+            FieldType::BaseType(BaseType::Double) | FieldType::BaseType(BaseType::Long)
+        );
+        let len = index + if is_wide { 2 } else { 1 };
+        if len > self.locals.len() {
+            self.locals.resize(len, None);
         }
-
-        if self.locals[index].is_none() {
-            self.locals[index] = Some(value);
+        if len > self.locals_field_types.len() {
+            self.locals_field_types.resize(len, None);
         }
-        if self.locals_field_types[index].is_none() {
-            self.locals_field_types[index] = Some(field_type);
+        self.locals_field_types[index] = Some(field_type);
+        if is_wide {
+            self.locals_field_types[index + 1] = None;
         }
     }
 
@@ -137,8 +167,8 @@ impl<'ctx> CompilationState<'ctx> {
 
     fn switch_to_block(&mut self, ctx: &CodegenContext<'ctx>, target_blk: BasicBlock<'ctx>) {
         let phis = self.label_phis.get(&target_blk).unwrap();
-        for (i, phi) in phis.local_phis.iter().enumerate() {
-            self.locals[i] = Some(phi.as_basic_value());
+        for (i, phi) in phis.local_phis.iter() {
+            self.locals[*i] = Some(phi.as_basic_value());
         }
         self.value_stack.clear();
         for (_, phi) in phis.stack_phis.iter().enumerate() {
@@ -150,8 +180,8 @@ impl<'ctx> CompilationState<'ctx> {
     fn set_locals_edges(&mut self, ctx: &CodegenContext<'ctx>, target_blk: BasicBlock<'ctx>) {
         let current_blk = ctx.builder.get_insert_block().unwrap();
         let phis = self.label_phis.get(&target_blk).unwrap();
-        for (i, phi) in phis.local_phis.iter().enumerate() {
-            let value = self.locals[i].unwrap();
+        for (i, phi) in phis.local_phis.iter() {
+            let value = self.locals[*i].unwrap();
             phi.add_incoming(&[(&value, current_blk)]);
         }
         for (i, phi) in phis.stack_phis.iter().enumerate() {
@@ -166,15 +196,56 @@ pub struct ClassFileCompiler {
     class_name: String,
     super_class_name: String,
     class_file: ClassFile,
-    class_static_fields: Vec<String>,
+    class_static_fields: Vec<StaticFieldInfo>,
+    static_field_constants: Vec<(String, StaticFieldConstant)>,
+    class_instance_fields: Vec<String>,
     static_methods: Vec<String>,
     virtual_methods: Vec<String>,
+    special_methods: Vec<String>,
+    private_methods: Vec<String>,
+    bootstrap_methods: Vec<BootstrapMethod>,
+    assignability_constraints: Vec<AssignabilityConstraint>,
 }
 
+/// The prefix of the functions dispatching the interface methods to the implementation of the receiver's class,
+/// e.g. "invokeinterface###java/lang/Runnable.run:()V".
+pub const INTERFACE_DISPATCH_PREFIX: &str = "invokeinterface###";
+
+/// The type of null pushed by aconst_null, which is a reference of no class.
+const NULL_TYPE: FieldType = FieldType::ObjectType(String::new());
+
 impl<'ctx> ClassFileCompiler {
-    pub fn new(path: String, tracing_enabled: bool) -> Self {
-        let classfile_bytes = std::fs::read(path.clone()).unwrap();
-        let (_, class_file) = class_parser(&classfile_bytes).unwrap();
+    pub fn new(path: String, tracing_enabled: bool) -> Result<Self, YajvmError> {
+        let classfile_bytes = std::fs::read(path.clone()).map_err(|source| YajvmError::Io {
+            path: path.clone(),
+            source,
+        })?;
+        Self::from_bytes(path, &classfile_bytes, tracing_enabled)
+    }
+
+    /// Parses the class file read from `path`, which is only used in the error messages.
+    pub fn from_bytes(
+        path: String,
+        classfile_bytes: &[u8],
+        tracing_enabled: bool,
+    ) -> Result<Self, YajvmError> {
+        let class_file = match class_parser(classfile_bytes) {
+            Ok(([], class_file)) => class_file,
+            Ok(_) => {
+                return Err(YajvmError::ClassFormat {
+                    path,
+                    message: "extra bytes at the end".to_string(),
+                })
+            }
+            Err(_) => {
+                return Err(YajvmError::ClassFormat {
+                    path,
+                    message: "truncated or malformed class file".to_string(),
+                })
+            }
+        };
+        // The compiler trusts the class file from now on, e.g. the constant pool indices.
+        let assignability_constraints = verify_class(&class_file)?;
 
         let mut ret = Self {
             tracing_enabled,
@@ -182,8 +253,14 @@ impl<'ctx> ClassFileCompiler {
             super_class_name: String::default(),
             class_file,
             class_static_fields: Vec::default(),
+            static_field_constants: Vec::default(),
+            class_instance_fields: Vec::default(),
             static_methods: Vec::default(),
             virtual_methods: Vec::default(),
+            special_methods: Vec::default(),
+            private_methods: Vec::default(),
+            bootstrap_methods: Vec::default(),
+            assignability_constraints,
         };
 
         let class_name = match ret.get_const(ret.class_file.this_class as usize) {
@@ -201,7 +278,13 @@ impl<'ctx> ClassFileCompiler {
 
         ret.class_name = class_name;
         ret.super_class_name = super_class;
-        ret
+        for attr_info in &ret.class_file.attributes {
+            if ret.get_utf8_const(attr_info.attribute_name_index as usize) == "BootstrapMethods" {
+                let (_, attr) = bootstrap_methods_attribute_parser(&attr_info.info).unwrap();
+                ret.bootstrap_methods = attr.bootstrap_methods;
+            }
+        }
+        Ok(ret)
     }
 
     pub fn class_name(&self) -> String {
@@ -214,6 +297,8 @@ impl<'ctx> ClassFileCompiler {
             Some(self.super_class_name.clone()),
         );
         c.static_fields = self.class_static_fields.clone();
+        c.static_field_constants = self.static_field_constants.clone();
+        c.instance_fields = self.class_instance_fields.clone();
 
         for m in &self.static_methods {
             c.static_methods.push(StaticMethodInfo {
@@ -221,6 +306,33 @@ impl<'ctx> ClassFileCompiler {
                 ptr: None,
             });
         }
+        for m in &self.virtual_methods {
+            c.virtual_methods.push(VirtualMethodInfo {
+                symbol: m.clone(),
+                ptr: None,
+                // Resolved against the ancestors when constructing the vtable.
+                overrides: None,
+            });
+        }
+        for m in &self.special_methods {
+            c.special_methods.push(SpecialMethodInfo {
+                symbol: m.clone(),
+                ptr: None,
+            });
+        }
+        c.private_methods = self.private_methods.clone();
+        c.interfaces = self
+            .class_file
+            .interfaces
+            .iter()
+            .map(|index| self.get_class_name_const(*index as usize))
+            .collect();
+        c.is_interface = self
+            .class_file
+            .access_flags
+            .contains(ClassAccessFlags::INTERFACE);
+        c.referenced_classes = self.referenced_class_names();
+        c.assignability_constraints = self.assignability_constraints.clone();
         c
     }
 
@@ -228,6 +340,97 @@ impl<'ctx> ClassFileCompiler {
         &self.class_file.methods
     }
 
+    /// Returns the classes which the class needs to be linked, i.e. the super class, the interfaces, the classes
+    /// referred by the bytecode such as the owners of the invoked methods and the accessed fields, and the classes
+    /// of the assignments left to the class hierarchy by the verifier. The arrays are replaced by their element
+    /// classes, and the bootstrap methods are not included as the compiler implements them by itself.
+    pub fn referenced_class_names(&self) -> Vec<String> {
+        let mut class_indices: Vec<u16> = Vec::new();
+        if self.class_file.super_class != 0 {
+            class_indices.push(self.class_file.super_class);
+        }
+        class_indices.extend(&self.class_file.interfaces);
+
+        let member_class_index = |index: u16| match self.get_const(index as usize) {
+            ConstantInfo::FieldRef(field_ref) => field_ref.class_index,
+            ConstantInfo::MethodRef(method_ref) => method_ref.class_index,
+            ConstantInfo::InterfaceMethodRef(method_ref) => method_ref.class_index,
+            v => unreachable!("{:?}", v),
+        };
+        for method in &self.class_file.methods {
+            for attr_info in self.code_attributes(method) {
+                let (_, code_attr) = code_attribute_parser(&attr_info.info).unwrap();
+                let (_, code) = code_parser(&code_attr.code).unwrap();
+                for (_, instr) in &code {
+                    match instr {
+                        Instruction::New(index)
+                        | Instruction::Checkcast(index)
+                        | Instruction::Instanceof(index)
+                        | Instruction::Anewarray(index)
+                        | Instruction::Multianewarray { index, .. } => class_indices.push(*index),
+                        Instruction::Getstatic(index)
+                        | Instruction::Putstatic(index)
+                        | Instruction::Getfield(index)
+                        | Instruction::Putfield(index)
+                        | Instruction::Invokevirtual(index)
+                        | Instruction::Invokespecial(index)
+                        | Instruction::Invokestatic(index)
+                        | Instruction::Invokeinterface { index, .. } => {
+                            class_indices.push(member_class_index(*index))
+                        }
+                        Instruction::Invokedynamic(index) => {
+                            let (bootstrap_method, bootstrap_arguments, _, _) =
+                                self.resolve_invoke_dynamic(*index);
+                            if bootstrap_method == LAMBDA_METAFACTORY {
+                                if let ConstantInfo::MethodHandle(method_handle) =
+                                    self.get_const(bootstrap_arguments[1] as usize)
+                                {
+                                    class_indices
+                                        .push(member_class_index(method_handle.reference_index));
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                for entry in &code_attr.exception_table {
+                    if entry.catch_type != 0 {
+                        class_indices.push(entry.catch_type);
+                    }
+                }
+            }
+        }
+
+        let mut class_names: Vec<String> = Vec::new();
+        for index in class_indices {
+            let class_name = self.get_class_name_const(index as usize);
+            let element_name = class_name.trim_start_matches('[');
+            let class_name = if element_name.len() == class_name.len() {
+                class_name
+            } else if let Some(element_name) = element_name
+                .strip_prefix('L')
+                .and_then(|name| name.strip_suffix(';'))
+            {
+                element_name.to_string()
+            } else {
+                // The arrays of the primitive types.
+                continue;
+            };
+            if class_name != self.class_name && !class_names.contains(&class_name) {
+                class_names.push(class_name);
+            }
+        }
+        // The verifier needs the classes of the assignments to check them against the class hierarchy.
+        for constraint in &self.assignability_constraints {
+            for class_name in [&constraint.from, &constraint.to] {
+                if *class_name != self.class_name && !class_names.contains(class_name) {
+                    class_names.push(class_name.clone());
+                }
+            }
+        }
+        class_names
+    }
+
     /// Get a constant from the constant pool.
     /// The index is 1-based as in the original JVM spec.
     fn get_const(&self, index: usize) -> &ConstantInfo {
@@ -255,6 +458,135 @@ impl<'ctx> ClassFileCompiler {
         (class_name, field_name, descriptor)
     }
 
+    fn get_class_name_const(&self, index: usize) -> String {
+        match self.get_const(index) {
+            ConstantInfo::Class(class_name) => self.get_utf8_const(class_name.name_index as usize),
+            v => unreachable!("{:?}", v),
+        }
+    }
+
+    /// Resolves the field reference into (class_name, field_name, descriptor).
+    fn resolve_field_ref(&self, index: u16) -> (String, String, String) {
+        let field_ref = match self.get_const(index as usize) {
+            ConstantInfo::FieldRef(field_ref) => field_ref,
+            v => unreachable!("{:?}", v),
+        };
+        self.resolve_class_field(
+            field_ref.class_index as usize,
+            field_ref.name_and_type_index as usize,
+        )
+    }
+
+    /// Resolves the method reference, which refers to an interface method for static and default
+    /// methods of interfaces.
+    fn resolve_method_ref(&self, index: u16) -> (String, String, String) {
+        let (class_index, name_and_type_index) = match self.get_const(index as usize) {
+            ConstantInfo::MethodRef(method_ref) => {
+                (method_ref.class_index, method_ref.name_and_type_index)
+            }
+            ConstantInfo::InterfaceMethodRef(method_ref) => {
+                (method_ref.class_index, method_ref.name_and_type_index)
+            }
+            v => unreachable!("{:?}", v),
+        };
+        self.resolve_class_field(class_index as usize, name_and_type_index as usize)
+    }
+
+    /// Resolves the call site of invokedynamic into its bootstrap method, e.g.
+    /// "java/lang/invoke/StringConcatFactory.makeConcatWithConstants", the bootstrap arguments, and the
+    /// name and the descriptor of the call site.
+    fn resolve_invoke_dynamic(&self, index: u16) -> (String, Vec<u16>, String, String) {
+        let invoke_dynamic = match self.get_const(index as usize) {
+            ConstantInfo::InvokeDynamic(invoke_dynamic) => invoke_dynamic,
+            v => unreachable!("{:?}", v),
+        };
+        let bootstrap_method =
+            &self.bootstrap_methods[invoke_dynamic.bootstrap_method_attr_index as usize];
+        let method_ref_index = match self.get_const(bootstrap_method.bootstrap_method_ref as usize)
+        {
+            ConstantInfo::MethodHandle(method_handle) => method_handle.reference_index,
+            v => unreachable!("{:?}", v),
+        };
+        let (class_name, method_name, _) = self.resolve_method_ref(method_ref_index);
+        let (name, descriptor) = match self.get_const(invoke_dynamic.name_and_type_index as usize) {
+            ConstantInfo::NameAndType(name_and_type) => (
+                self.get_utf8_const(name_and_type.name_index as usize),
+                self.get_utf8_const(name_and_type.descriptor_index as usize),
+            ),
+            v => unreachable!("{:?}", v),
+        };
+        (
+            format!("{}.{}", class_name, method_name),
+            bootstrap_method.bootstrap_arguments.clone(),
+            name,
+            descriptor,
+        )
+    }
+
+    /// Returns the class implementing the functional interface for the call site of
+    /// LambdaMetafactory.metafactory, whose bootstrap arguments are the erased descriptor of the interface
+    /// method, the method handle of the implementation, and the instantiated descriptor.
+    fn lambda_class(&self, index: u16) -> LambdaClass {
+        let (_, bootstrap_arguments, method_name, descriptor) = self.resolve_invoke_dynamic(index);
+        let method_descriptor = match self.get_const(bootstrap_arguments[0] as usize) {
+            ConstantInfo::MethodType(method_type) => {
+                self.get_utf8_const(method_type.descriptor_index as usize)
+            }
+            v => unreachable!("{:?}", v),
+        };
+        let implementation = match self.get_const(bootstrap_arguments[1] as usize) {
+            ConstantInfo::MethodHandle(method_handle) => {
+                let (class_name, method_name, descriptor) =
+                    self.resolve_method_ref(method_handle.reference_index);
+                LambdaImplementation {
+                    kind: method_handle.reference_kind,
+                    class_name,
+                    method_name,
+                    descriptor,
+                }
+            }
+            v => unreachable!("{:?}", v),
+        };
+        let call_site_type = parse_method_descriptor(&descriptor);
+        let interface_name = match call_site_type.return_type.unwrap() {
+            FieldType::ObjectType(name) => name,
+            v => unreachable!("{:?}", v),
+        };
+        LambdaClass {
+            // The call sites are distinguished by their constants.
+            class_name: format!("{}$$Lambda${}", self.class_name(), index),
+            interface_name,
+            method_name,
+            method_descriptor,
+            captured_types: call_site_type.parameter_types,
+            implementation,
+        }
+    }
+
+    /// Returns the recipe of makeConcatWithConstants, where \u{1} stands for the next argument and \u{2} for
+    /// the next constant, and the constants. javac passes the string constants separately only when they
+    /// contain the tags themselves, so they are never inlined into the recipe. Returns the constant as the error
+    /// if its type is not supported.
+    fn string_concat_recipe(
+        &self,
+        bootstrap_arguments: &[u16],
+    ) -> Result<(String, Vec<String>), String> {
+        let recipe = match self.get_const(bootstrap_arguments[0] as usize) {
+            ConstantInfo::String(s) => self.get_utf8_const(s.string_index as usize),
+            v => unreachable!("{:?}", v),
+        };
+        let constants = bootstrap_arguments[1..]
+            .iter()
+            .map(|index| match self.get_const(*index as usize) {
+                ConstantInfo::String(s) => Ok(self.get_utf8_const(s.string_index as usize)),
+                ConstantInfo::Integer(i) => Ok(i.value.to_string()),
+                ConstantInfo::Long(l) => Ok(l.value.to_string()),
+                v => Err(format!("{:?}", v)),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok((recipe, constants))
+    }
+
     fn get_utf8_const(&self, index: usize) -> String {
         match self.get_const(index) {
             ConstantInfo::Utf8(name) => name.utf8_string.clone(),
@@ -262,16 +594,58 @@ impl<'ctx> ClassFileCompiler {
         }
     }
 
+    /// Returns the Code attributes of the method. Other attributes such as Exceptions are ignored.
+    fn code_attributes<'a>(&self, method: &'a MethodInfo) -> Vec<&'a AttributeInfo> {
+        method
+            .attributes
+            .iter()
+            .filter(|attr_info| {
+                self.get_utf8_const(attr_info.attribute_name_index as usize) == "Code"
+            })
+            .collect()
+    }
+
     pub fn initialize_class_object_info(&mut self) {
         for f in &self.class_file.fields {
+            let field_name = self.get_utf8_const(f.name_index as usize);
             if f.access_flags.contains(FieldAccessFlags::STATIC) {
-                let field_name = self.get_utf8_const(f.name_index as usize);
-                self.class_static_fields.push(field_name);
+                for attr_info in &f.attributes {
+                    if self.get_utf8_const(attr_info.attribute_name_index as usize)
+                        == "ConstantValue"
+                    {
+                        let (_, attr) = constant_value_attribute_parser(&attr_info.info).unwrap();
+                        let constant = self.static_field_constant(attr.constant_value_index);
+                        self.static_field_constants
+                            .push((field_name.clone(), constant));
+                    }
+                }
+                self.class_static_fields.push(StaticFieldInfo {
+                    name: field_name,
+                    descriptor: self.get_utf8_const(f.descriptor_index as usize),
+                    offset: 0,
+                });
+            } else {
+                self.class_instance_fields.push(field_name);
+            }
+        }
+    }
+
+    /// Returns the value of the ConstantValue attribute. The fields of boolean, byte, char and short are
+    /// also given by int constants.
+    fn static_field_constant(&self, index: u16) -> StaticFieldConstant {
+        match self.get_const(index as usize) {
+            ConstantInfo::Integer(v) => StaticFieldConstant::Int(v.value),
+            ConstantInfo::Long(v) => StaticFieldConstant::Long(v.value),
+            ConstantInfo::Float(v) => StaticFieldConstant::Float(v.value.to_bits()),
+            ConstantInfo::Double(v) => StaticFieldConstant::Double(v.value.to_bits()),
+            ConstantInfo::String(v) => {
+                StaticFieldConstant::String(self.get_utf8_const(v.string_index as usize))
             }
+            v => unreachable!("{:?}", v),
         }
     }
 
-    pub fn compile_methods(&mut self, ctx: &mut CodegenContext<'ctx>) {
+    pub fn compile_methods(&mut self, ctx: &mut CodegenContext<'ctx>) -> Result<(), YajvmError> {
         let mut state = CompilationState {
             value_stack: Vec::new(),
             param_count: 0,
@@ -283,22 +657,29 @@ impl<'ctx> ClassFileCompiler {
             labels: HashMap::new(),
             locals_field_types: Vec::new(),
             label_phis: HashMap::new(),
-            ignored_instructions: HashSet::new(),
             function_method_type: None,
             label_field_type_stack: HashMap::new(),
+            label_locals_field_types: HashMap::new(),
+            exception_table: Vec::new(),
         };
 
         for method in &self.class_file.methods {
-            self.compile_method(ctx, &method, &mut state);
+            self.compile_method(ctx, &method, &mut state)?;
+            let symbol = state.function_symbol.clone().unwrap();
             if state.is_static {
-                self.static_methods
-                    .push(state.function_symbol.clone().unwrap());
+                self.static_methods.push(symbol);
+            } else if self.get_utf8_const(method.name_index as usize) == "<init>" {
+                // Constructors are only invoked by invokespecial.
+                self.special_methods.push(symbol);
             } else {
-                self.virtual_methods
-                    .push(state.function_symbol.clone().unwrap());
+                if method.access_flags.contains(MethodAccessFlags::PRIVATE) {
+                    self.private_methods.push(symbol.clone());
+                }
+                self.virtual_methods.push(symbol);
             }
             state.reset(); // Reuse the same state for all methods.
         }
+        Ok(())
     }
 
     fn compile_method(
@@ -306,10 +687,14 @@ impl<'ctx> ClassFileCompiler {
         ctx: &mut CodegenContext<'ctx>,
         method: &MethodInfo,
         state: &mut CompilationState<'ctx>,
-    ) {
-        self.analyze(ctx, method, state);
+    ) -> Result<(), YajvmError> {
+        self.analyze(ctx, method, state)?;
+        if method.access_flags.contains(MethodAccessFlags::ABSTRACT) {
+            return Ok(());
+        }
         self.build_phis(ctx, state);
         self.compile(ctx, method, state);
+        Ok(())
     }
 
     fn build_phis(&self, ctx: &CodegenContext<'ctx>, state: &mut CompilationState<'ctx>) {
@@ -328,13 +713,14 @@ impl<'ctx> ClassFileCompiler {
                 continue; // Entry label shouldn't have phis.
             }
             ctx.builder.position_at_end(block);
-            for (i, v) in state.locals.iter().enumerate() {
+            for (i, v) in state.label_locals_field_types[i].iter().enumerate() {
                 if let Some(v) = v {
+                    let llvm_typ: BasicTypeEnum = ctx.llvm_type_from_field_type(v);
                     let phi = ctx.builder.build_phi(
-                        v.get_type(),
+                        llvm_typ,
                         format!("locals[{}]_at_{}", i, block.get_name().to_str().unwrap()).as_str(),
                     );
-                    local_phis.push(phi);
+                    local_phis.push((i, phi));
                 }
             }
             for (i, v) in state
@@ -369,7 +755,27 @@ impl<'ctx> ClassFileCompiler {
         descriptor: &MethodType,
         is_static: bool,
     ) -> (FunctionValue<'ctx>, String) {
-        let symbol = format!("{}.{}:{}", &self.class_name(), method_name, descriptor_str);
+        self.get_method_by_symbol(
+            ctx,
+            &self.class_name(),
+            method_name,
+            descriptor_str,
+            descriptor,
+            is_static,
+        )
+    }
+
+    /// Returns the function of the method of the class, declaring it if it hasn't been compiled yet.
+    fn get_method_by_symbol(
+        &self,
+        ctx: &CodegenContext<'ctx>,
+        class_name: &String,
+        method_name: &String,
+        descriptor_str: &String,
+        descriptor: &MethodType,
+        is_static: bool,
+    ) -> (FunctionValue<'ctx>, String) {
+        let symbol = format!("{}.{}:{}", class_name, method_name, descriptor_str);
         let f = ctx.module.get_function(&symbol).unwrap_or_else(|| {
             let fn_type = ctx.llvm_function_type_from_method_type(&descriptor, is_static);
             let function = ctx.module.add_function(&symbol, fn_type, None);
@@ -378,7 +784,8 @@ impl<'ctx> ClassFileCompiler {
         (f, symbol)
     }
 
-    fn analyze_load_constant(&self, state: &mut CompilationState<'ctx>, index: u16) {
+    /// Returns false if the constant isn't supported.
+    fn analyze_load_constant(&self, state: &mut CompilationState<'ctx>, index: u16) -> bool {
         match self.get_const(index as usize) {
             ConstantInfo::String(_) => {
                 state
@@ -405,8 +812,9 @@ impl<'ctx> ClassFileCompiler {
                     .field_type_stack
                     .push(FieldType::BaseType(BaseType::Double));
             }
-            v => unreachable!("{:?}", v),
+            _ => return false,
         };
+        true
     }
 
     fn analyze(
@@ -414,7 +822,7 @@ impl<'ctx> ClassFileCompiler {
         ctx: &CodegenContext<'ctx>,
         method: &MethodInfo,
         state: &mut CompilationState<'ctx>,
-    ) {
+    ) -> Result<(), YajvmError> {
         let method_name = match self.get_const(method.name_index as usize) {
             ConstantInfo::Utf8(name) => name.utf8_string.clone(),
             _ => unreachable!(),
@@ -456,29 +864,45 @@ impl<'ctx> ClassFileCompiler {
                 }
             };
 
-            state.initialize_local(local_index, typ.clone(), *p);
+            state.store_local_type(local_index, typ.clone());
+            state.set_local(local_index, *p);
             state.param_count += 1;
             match typ {
                 FieldType::BaseType(BaseType::Double) | FieldType::BaseType(BaseType::Long) => {
-                    state.initialize_local(local_index, typ.clone(), *p);
                     local_index += 2;
                 }
                 _ => local_index += 1,
             }
         }
 
-        let entry_block = ctx.context.append_basic_block(function, "entry");
-        state.labels.insert(0, entry_block);
-
         state.function = Some(function);
         state.function_symbol = Some(function_symbol);
+        state.is_static = is_static;
         state.function_method_type = Some(descriptor);
 
-        assert!(
-            state.field_type_stack.is_empty(),
-            "{:?}",
-            state.field_type_stack
-        );
+        if method.access_flags.contains(MethodAccessFlags::ABSTRACT) {
+            // Abstract methods have no code, so only the declaration is needed.
+            return Ok(());
+        }
+        let unsupported = |addr: usize, instruction: String| YajvmError::UnsupportedOpcode {
+            class_name: self.class_name.clone(),
+            method: format!("{}{}", method_name, descriptor_str),
+            offset: addr,
+            instruction,
+        };
+
+        // The type checks which the verifier already made, failing as the verifier would if they disagree.
+        let verify_error = |addr: usize, message: String| {
+            YajvmError::Verify(VerifyError {
+                class_name: self.class_name.clone(),
+                method: Some(format!("{}{}", method_name, descriptor_str)),
+                offset: Some(addr),
+                message,
+            })
+        };
+
+        let entry_block = ctx.context.append_basic_block(function, "entry");
+        state.labels.insert(0, entry_block);
 
         // Analyze the code to find all the labels, plus the local variable types.
         const INT: FieldType = FieldType::BaseType(BaseType::Int);
@@ -489,16 +913,35 @@ impl<'ctx> ClassFileCompiler {
         let long_basic_type_enum: BasicTypeEnum = ctx.llvm_type_from_field_type(&LONG);
         let float_basic_type_enum: BasicTypeEnum = ctx.llvm_type_from_field_type(&FLOAT);
         let double_basic_type_enum: BasicTypeEnum = ctx.llvm_type_from_field_type(&DOUBLE);
-        for attr_info in &method.attributes {
+        for attr_info in self.code_attributes(method) {
             let (_, code_attr) = code_attribute_parser(&attr_info.info).unwrap();
             state.reserve_locals(code_attr.max_locals as usize);
+            state
+                .label_locals_field_types
+                .insert(0, state.locals_field_types.clone());
 
+            // The frames give the types at the branch targets and the exception handlers, so the labels there
+            // start with them instead of the ones derived from the instructions.
             let (_, code) = code_parser(&code_attr.code).unwrap();
+            for (addr, frame) in self.stack_map_frames(&code_attr, &code, state) {
+                let basic_block = ctx
+                    .context
+                    .append_basic_block(state.function(), format!("l{}", addr).as_str());
+                state.labels.insert(addr, basic_block);
+                state.label_field_type_stack.insert(addr, frame.stack);
+                state.label_locals_field_types.insert(addr, frame.locals);
+            }
+
+            state.exception_table = code_attr.exception_table.clone();
+
             for (addr, instr) in code.iter() {
                 if let Some(stack) = state.label_field_type_stack.get(addr) {
                     // Swap the stack.
                     state.field_type_stack = stack.clone();
                 }
+                if let Some(locals) = state.label_locals_field_types.get(addr) {
+                    state.locals_field_types = locals.clone();
+                }
 
                 // println!("{}: {:?}: {:?}", addr, instr, state.field_type_stack);
                 match instr {
@@ -509,149 +952,45 @@ impl<'ctx> ClassFileCompiler {
                             FieldType::ArrayType(array_type) => {
                                 state.field_type_stack.push(*array_type);
                             }
+                            // Always throws NullPointerException.
+                            t if t == NULL_TYPE => state.field_type_stack.push(NULL_TYPE),
                             _ => unreachable!(),
                         }
                     }
+
+                    Instruction::Aconstnull => {
+                        state.field_type_stack.push(NULL_TYPE);
+                    }
                     Instruction::Iload0
                     | Instruction::Iload1
                     | Instruction::Iload2
-                    | Instruction::Iload3 => {
-                        let index = match instr {
-                            Instruction::Iload0 => 0,
-                            Instruction::Iload1 => 1,
-                            Instruction::Iload2 => 2,
-                            Instruction::Iload3 => 3,
-                            _ => unreachable!(),
-                        };
-                        state.field_type_stack.push(INT);
-                        state.initialize_local(index, INT, int_basic_type_enum.const_zero());
-                    }
-
-                    Instruction::Iload(index) => {
+                    | Instruction::Iload3
+                    | Instruction::Iload(_) => {
                         state.field_type_stack.push(INT);
-                        state.initialize_local(
-                            *index as usize,
-                            INT,
-                            int_basic_type_enum.const_zero(),
-                        );
                     }
 
                     Instruction::Lload0
                     | Instruction::Lload1
                     | Instruction::Lload2
-                    | Instruction::Lload3 => {
-                        let index = match instr {
-                            Instruction::Lload0 => 0,
-                            Instruction::Lload1 => 1,
-                            Instruction::Lload2 => 2,
-                            Instruction::Lload3 => 3,
-                            _ => unreachable!(),
-                        };
-                        state
-                            .field_type_stack
-                            .push(FieldType::BaseType(BaseType::Long));
-                        state.initialize_local(
-                            index,
-                            FieldType::BaseType(BaseType::Long),
-                            ctx.i64_type.const_zero().into(),
-                        );
-                        state.initialize_local(
-                            index + 1,
-                            FieldType::BaseType(BaseType::Long),
-                            ctx.i64_type.const_zero().into(),
-                        );
-                    }
-
-                    Instruction::Lload(index) => {
-                        let index = *index as usize;
-                        state
-                            .field_type_stack
-                            .push(FieldType::BaseType(BaseType::Long));
-                        state.initialize_local(
-                            index,
-                            FieldType::BaseType(BaseType::Long),
-                            ctx.i64_type.const_zero().into(),
-                        );
-                        state.initialize_local(
-                            index + 1,
-                            FieldType::BaseType(BaseType::Long),
-                            ctx.i64_type.const_zero().into(),
-                        );
+                    | Instruction::Lload3
+                    | Instruction::Lload(_) => {
+                        state.field_type_stack.push(LONG);
                     }
 
                     Instruction::Dload0
                     | Instruction::Dload1
                     | Instruction::Dload2
-                    | Instruction::Dload3 => {
-                        let index = match instr {
-                            Instruction::Dload0 => 0,
-                            Instruction::Dload1 => 1,
-                            Instruction::Dload2 => 2,
-                            Instruction::Dload3 => 3,
-                            _ => unreachable!(),
-                        };
-                        state
-                            .field_type_stack
-                            .push(FieldType::BaseType(BaseType::Double));
-                        state.initialize_local(
-                            index,
-                            FieldType::BaseType(BaseType::Double),
-                            ctx.f64_type.const_zero().into(),
-                        );
-                        state.initialize_local(
-                            index + 1,
-                            FieldType::BaseType(BaseType::Double),
-                            ctx.f64_type.const_zero().into(),
-                        );
-                    }
-
-                    Instruction::Dload(index) => {
-                        let index = *index as usize;
-                        state
-                            .field_type_stack
-                            .push(FieldType::BaseType(BaseType::Double));
-                        state.initialize_local(
-                            index,
-                            FieldType::BaseType(BaseType::Double),
-                            ctx.f64_type.const_zero().into(),
-                        );
-                        state.initialize_local(
-                            index + 1,
-                            FieldType::BaseType(BaseType::Double),
-                            ctx.f64_type.const_zero().into(),
-                        );
+                    | Instruction::Dload3
+                    | Instruction::Dload(_) => {
+                        state.field_type_stack.push(DOUBLE);
                     }
 
                     Instruction::Fload0
                     | Instruction::Fload1
                     | Instruction::Fload2
-                    | Instruction::Fload3 => {
-                        let index = match instr {
-                            Instruction::Fload0 => 0,
-                            Instruction::Fload1 => 1,
-                            Instruction::Fload2 => 2,
-                            Instruction::Fload3 => 3,
-                            _ => unreachable!(),
-                        };
-                        state
-                            .field_type_stack
-                            .push(FieldType::BaseType(BaseType::Float));
-                        state.initialize_local(
-                            index,
-                            FieldType::BaseType(BaseType::Float),
-                            ctx.f32_type.const_zero().into(),
-                        );
-                    }
-
-                    Instruction::Fload(index) => {
-                        state
-                            .field_type_stack
-                            .push(FieldType::BaseType(BaseType::Float));
-                        state.initialize_local(
-                            *index as usize,
-                            FieldType::BaseType(BaseType::Float),
-                            ctx.f32_type.const_zero().into(),
-                        );
+                    | Instruction::Fload3
+                    | Instruction::Fload(_) => {
+                        state.field_type_stack.push(FLOAT);
                     }
 
                     Instruction::Arraylength => {
@@ -694,16 +1033,12 @@ impl<'ctx> ClassFileCompiler {
                         };
 
                         let field_type = state.field_type_stack.pop().unwrap();
-                        state.initialize_local(index, field_type, int_basic_type_enum.const_zero());
+                        state.store_local_type(index, field_type);
                     }
 
                     Instruction::Istore(index) => {
                         let field_type = state.field_type_stack.pop().unwrap();
-                        state.initialize_local(
-                            *index as usize,
-                            field_type,
-                            int_basic_type_enum.const_zero(),
-                        );
+                        state.store_local_type(*index as usize, field_type);
                     }
 
                     Instruction::Lstore0
@@ -719,20 +1054,12 @@ impl<'ctx> ClassFileCompiler {
                         };
 
                         let field_type = state.field_type_stack.pop().unwrap();
-                        state.initialize_local(
-                            index,
-                            field_type,
-                            long_basic_type_enum.const_zero(),
-                        );
+                        state.store_local_type(index, field_type);
                     }
 
                     Instruction::Lstore(index) => {
                         let field_type = state.field_type_stack.pop().unwrap();
-                        state.initialize_local(
-                            *index as usize,
-                            field_type,
-                            long_basic_type_enum.const_zero(),
-                        );
+                        state.store_local_type(*index as usize, field_type);
                     }
 
                     Instruction::Fstore0
@@ -748,20 +1075,12 @@ impl<'ctx> ClassFileCompiler {
                         };
 
                         let field_type = state.field_type_stack.pop().unwrap();
-                        state.initialize_local(
-                            index,
-                            field_type,
-                            float_basic_type_enum.const_zero(),
-                        );
+                        state.store_local_type(index, field_type);
                     }
 
                     Instruction::Fstore(index) => {
                         let field_type = state.field_type_stack.pop().unwrap();
-                        state.initialize_local(
-                            *index as usize,
-                            field_type,
-                            float_basic_type_enum.const_zero(),
-                        );
+                        state.store_local_type(*index as usize, field_type);
                     }
 
                     Instruction::Dstore0
@@ -777,20 +1096,12 @@ impl<'ctx> ClassFileCompiler {
                         };
 
                         let field_type = state.field_type_stack.pop().unwrap();
-                        state.initialize_local(
-                            index,
-                            field_type,
-                            double_basic_type_enum.const_zero(),
-                        );
+                        state.store_local_type(index, field_type);
                     }
 
                     Instruction::Dstore(index) => {
                         let field_type = state.field_type_stack.pop().unwrap();
-                        state.initialize_local(
-                            *index as usize,
-                            field_type,
-                            double_basic_type_enum.const_zero(),
-                        );
+                        state.store_local_type(*index as usize, field_type);
                     }
 
                     Instruction::Bipush(_) => {
@@ -801,29 +1112,8 @@ impl<'ctx> ClassFileCompiler {
                     }
 
                     Instruction::Invokevirtual(index) => {
-                        let sig = {
-                            let method_ref = match self.get_const(*index as usize) {
-                                ConstantInfo::MethodRef(method_ref) => method_ref,
-                                v => unreachable!("{:?}", v),
-                            };
-                            let (class_name, method_name, raw) = self.resolve_class_field(
-                                method_ref.class_index as usize,
-                                method_ref.name_and_type_index as usize,
-                            );
-
-                            match &*class_name {
-                                "java/lang/Integer" => match &*method_name {
-                                    "intValue" => {
-                                        // Ignore this instruction.
-                                        state.ignored_instructions.insert(*addr);
-                                    }
-                                    _ => unreachable!(),
-                                },
-                                _ => {}
-                            }
-
-                            parse_method_descriptor(&raw)
-                        };
+                        let (_, _, descriptor) = self.resolve_method_ref(*index);
+                        let sig = parse_method_descriptor(&descriptor);
                         for _ in 0..sig.parameter_types.len() + 1
                         // +1 because it takes this pointer.
                         {
@@ -833,16 +1123,57 @@ impl<'ctx> ClassFileCompiler {
                             state.field_type_stack.push(return_type);
                         }
                     }
-                    Instruction::Invokespecial(index) => {
-                        let method_ref = match self.get_const(*index as usize) {
-                            ConstantInfo::MethodRef(method_ref) => method_ref,
-                            v => unreachable!("{:?}", v),
-                        };
+                    Instruction::Invokedynamic(index) => {
+                        let (bootstrap_method, bootstrap_arguments, _, descriptor) =
+                            self.resolve_invoke_dynamic(*index);
+                        if bootstrap_method != MAKE_CONCAT_WITH_CONSTANTS
+                            && bootstrap_method != LAMBDA_METAFACTORY
+                        {
+                            return Err(unsupported(
+                                *addr,
+                                format!(
+                                    "{:?} with the bootstrap method {}",
+                                    instr, bootstrap_method
+                                ),
+                            ));
+                        }
+                        if bootstrap_method == LAMBDA_METAFACTORY {
+                            if let Err(reason) = self.lambda_class(*index).check_supported() {
+                                return Err(unsupported(
+                                    *addr,
+                                    format!("{:?} with the {}", instr, reason),
+                                ));
+                            }
+                        }
+                        if bootstrap_method == MAKE_CONCAT_WITH_CONSTANTS {
+                            if let Err(constant) = self.string_concat_recipe(&bootstrap_arguments) {
+                                return Err(unsupported(
+                                    *addr,
+                                    format!("{:?} with the constant {}", instr, constant),
+                                ));
+                            }
+                        }
+                        let sig = parse_method_descriptor(&descriptor);
+                        for _ in 0..sig.parameter_types.len() {
+                            state.field_type_stack.pop();
+                        }
+                        state.field_type_stack.push(sig.return_type.unwrap());
+                    }
 
-                        let (_, _, descriptor) = self.resolve_class_field(
-                            method_ref.class_index as usize,
-                            method_ref.name_and_type_index as usize,
-                        );
+                    Instruction::Invokeinterface { index, .. } => {
+                        let (_, _, descriptor) = self.resolve_method_ref(*index);
+                        let sig = parse_method_descriptor(&descriptor);
+                        // +1 because it takes this pointer.
+                        for _ in 0..sig.parameter_types.len() + 1 {
+                            state.field_type_stack.pop();
+                        }
+                        if let Some(return_type) = sig.return_type {
+                            state.field_type_stack.push(return_type);
+                        }
+                    }
+
+                    Instruction::Invokespecial(index) => {
+                        let (_, _, descriptor) = self.resolve_method_ref(*index);
 
                         let sig = parse_method_descriptor(&descriptor);
                         // Special takes an implicit this pointer.
@@ -889,37 +1220,24 @@ impl<'ctx> ClassFileCompiler {
                     }
 
                     Instruction::Iinc { index, value: _ } => {
-                        state.initialize_local(
-                            *index as usize,
-                            INT,
-                            int_basic_type_enum.const_zero(),
-                        );
+                        state.store_local_type(*index as usize, INT);
                     }
-                    Instruction::Ldc(index) => self.analyze_load_constant(state, *index as u16),
-                    Instruction::Ldc2W(index) => self.analyze_load_constant(state, *index),
-
-                    Instruction::Invokestatic(index) => {
-                        let method_ref = match self.get_const(*index as usize) {
-                            ConstantInfo::MethodRef(method_ref) => method_ref,
-                            v => unreachable!("{:?}", v),
-                        };
-
-                        let (class_name, method_name, descriptor) = self.resolve_class_field(
-                            method_ref.class_index as usize,
-                            method_ref.name_and_type_index as usize,
-                        );
-
-                        match &*class_name {
-                            "java/lang/Integer" => match &*method_name {
-                                "valueOf" => {
-                                    // Ignore this instruction.
-                                    state.ignored_instructions.insert(*addr);
-                                }
-                                _ => unreachable!(),
-                            },
-                            _ => {}
+                    Instruction::IincWide { index, value: _ } => {
+                        state.store_local_type(*index as usize, INT);
+                    }
+                    Instruction::Ldc(index) => {
+                        if !self.analyze_load_constant(state, *index as u16) {
+                            return Err(unsupported(*addr, format!("{:?}", instr)));
+                        }
+                    }
+                    Instruction::Ldc2W(index) => {
+                        if !self.analyze_load_constant(state, *index) {
+                            return Err(unsupported(*addr, format!("{:?}", instr)));
                         }
+                    }
 
+                    Instruction::Invokestatic(index) => {
+                        let (_, _, descriptor) = self.resolve_method_ref(*index);
                         let sig = parse_method_descriptor(&descriptor);
                         // Then the parameters.
                         for _ in 0..sig.parameter_types.len() {
@@ -932,7 +1250,14 @@ impl<'ctx> ClassFileCompiler {
                     Instruction::Imul
                     | Instruction::Iadd
                     | Instruction::Isub
-                    | Instruction::Idiv => {
+                    | Instruction::Idiv
+                    | Instruction::Irem
+                    | Instruction::Iand
+                    | Instruction::Ior
+                    | Instruction::Ixor
+                    | Instruction::Ishl
+                    | Instruction::Ishr
+                    | Instruction::Iushr => {
                         state.field_type_stack.pop();
                         state.field_type_stack.pop();
                         state.field_type_stack.push(INT);
@@ -941,7 +1266,14 @@ impl<'ctx> ClassFileCompiler {
                     Instruction::Lmul
                     | Instruction::Ladd
                     | Instruction::Lsub
-                    | Instruction::Ldiv => {
+                    | Instruction::Ldiv
+                    | Instruction::Lrem
+                    | Instruction::Land
+                    | Instruction::Lor
+                    | Instruction::Lxor
+                    | Instruction::Lshl
+                    | Instruction::Lshr
+                    | Instruction::Lushr => {
                         state.field_type_stack.pop();
                         state.field_type_stack.pop();
                         state.field_type_stack.push(LONG);
@@ -950,7 +1282,8 @@ impl<'ctx> ClassFileCompiler {
                     Instruction::Fmul
                     | Instruction::Fadd
                     | Instruction::Fsub
-                    | Instruction::Fdiv => {
+                    | Instruction::Fdiv
+                    | Instruction::Frem => {
                         state.field_type_stack.pop();
                         state.field_type_stack.pop();
                         state.field_type_stack.push(FLOAT);
@@ -959,24 +1292,97 @@ impl<'ctx> ClassFileCompiler {
                     Instruction::Dmul
                     | Instruction::Dadd
                     | Instruction::Dsub
-                    | Instruction::Ddiv => {
+                    | Instruction::Ddiv
+                    | Instruction::Drem => {
                         state.field_type_stack.pop();
                         state.field_type_stack.pop();
                         state.field_type_stack.push(DOUBLE);
                     }
 
+                    Instruction::Ineg
+                    | Instruction::Lneg
+                    | Instruction::Fneg
+                    | Instruction::Dneg => {
+                        // The type of the value on the stack doesn't change.
+                    }
+
                     Instruction::I2b | Instruction::I2c | Instruction::I2s => {
                         // This is just rounding the integer to a byte at runtime.
                         state.field_type_stack.pop();
                         state.field_type_stack.push(INT);
                     }
+                    Instruction::L2i | Instruction::F2i | Instruction::D2i => {
+                        state.field_type_stack.pop();
+                        state.field_type_stack.push(INT);
+                    }
+                    Instruction::I2l | Instruction::F2l | Instruction::D2l => {
+                        state.field_type_stack.pop();
+                        state.field_type_stack.push(LONG);
+                    }
+                    Instruction::I2f | Instruction::L2f | Instruction::D2f => {
+                        state.field_type_stack.pop();
+                        state.field_type_stack.push(FLOAT);
+                    }
+                    Instruction::I2d | Instruction::L2d | Instruction::F2d => {
+                        state.field_type_stack.pop();
+                        state.field_type_stack.push(DOUBLE);
+                    }
 
-                    Instruction::Goto(diff) => {
-                        self.ensure_label_with_offset(ctx, state, *addr, *diff);
-                    }
-
-                    Instruction::Ireturn | Instruction::Areturn => {
-                        state.field_type_stack.pop().unwrap();
+                    Instruction::Goto(diff) => {
+                        self.ensure_label_with_offset(ctx, state, *addr, (*diff).into());
+                    }
+
+                    Instruction::Tableswitch {
+                        default, offsets, ..
+                    } => {
+                        state.field_type_stack.pop(); // Key.
+                        let addr = *addr;
+                        self.ensure_label_with_offset(ctx, state, addr, *default);
+                        for offset in offsets {
+                            self.ensure_label_with_offset(ctx, state, addr, *offset);
+                        }
+                    }
+
+                    Instruction::Lookupswitch { default, pairs } => {
+                        state.field_type_stack.pop(); // Key.
+                        let addr = *addr;
+                        self.ensure_label_with_offset(ctx, state, addr, *default);
+                        for (_, offset) in pairs {
+                            self.ensure_label_with_offset(ctx, state, addr, *offset);
+                        }
+                    }
+
+                    Instruction::Ireturn
+                    | Instruction::Lreturn
+                    | Instruction::Freturn
+                    | Instruction::Dreturn
+                    | Instruction::Areturn => {
+                        let value_type = state.field_type_stack.pop().unwrap();
+                        let expected: BasicTypeEnum = match instr {
+                            Instruction::Ireturn => int_basic_type_enum,
+                            Instruction::Lreturn => long_basic_type_enum,
+                            Instruction::Freturn => float_basic_type_enum,
+                            Instruction::Dreturn => double_basic_type_enum,
+                            _ => ctx.void_ptr.into(),
+                        };
+                        let Some(return_type) = &state.function_method_type().return_type else {
+                            return Err(verify_error(
+                                *addr,
+                                format!("{:?} in a void method", instr),
+                            ));
+                        };
+                        if ctx.llvm_type_from_field_type::<BasicTypeEnum>(return_type) != expected {
+                            return Err(verify_error(
+                                *addr,
+                                format!("{:?} in a method returning {}", instr, return_type),
+                            ));
+                        }
+                        if ctx.llvm_type_from_field_type::<BasicTypeEnum>(&value_type) != expected {
+                            return Err(verify_error(
+                                *addr,
+                                format!("{:?} of {}", instr, value_type),
+                            ));
+                        }
                     }
 
                     Instruction::IfIcmpge(diff)
@@ -988,7 +1394,7 @@ impl<'ctx> ClassFileCompiler {
                         state.field_type_stack.pop();
                         state.field_type_stack.pop();
                         let addr = *addr;
-                        self.ensure_label_with_offset(ctx, state, addr, *diff);
+                        self.ensure_label_with_offset(ctx, state, addr, (*diff).into());
                         // Next instruction is the "else" target.
                         self.ensure_label_with_offset(ctx, state, addr, 3);
                     }
@@ -1008,14 +1414,26 @@ impl<'ctx> ClassFileCompiler {
                         state.field_type_stack.push(INT);
                     }
 
+                    Instruction::IfAcmpeq(diff) | Instruction::IfAcmpne(diff) => {
+                        state.field_type_stack.pop();
+                        state.field_type_stack.pop();
+                        let addr = *addr;
+                        self.ensure_label_with_offset(ctx, state, addr, (*diff).into());
+                        // Next instruction is the "else" target.
+                        self.ensure_label_with_offset(ctx, state, addr, 3);
+                    }
+
                     Instruction::Ifne(diff)
                     | Instruction::Ifeq(diff)
                     | Instruction::Ifgt(diff)
                     | Instruction::Ifge(diff)
-                    | Instruction::Ifle(diff) => {
+                    | Instruction::Iflt(diff)
+                    | Instruction::Ifle(diff)
+                    | Instruction::Ifnull(diff)
+                    | Instruction::Ifnonnull(diff) => {
                         state.field_type_stack.pop();
                         let addr = *addr;
-                        self.ensure_label_with_offset(ctx, state, addr, *diff);
+                        self.ensure_label_with_offset(ctx, state, addr, (*diff).into());
                         // Next instruction is the "else" target.
                         self.ensure_label_with_offset(ctx, state, addr, 3);
                     }
@@ -1065,8 +1483,12 @@ impl<'ctx> ClassFileCompiler {
                             _ => unreachable!(),
                         };
                         let field_type = state.field_type_stack.pop().unwrap();
-                        let local_type: BasicTypeEnum = ctx.llvm_type_from_field_type(&field_type);
-                        state.initialize_local(index, field_type, local_type.const_zero());
+                        state.store_local_type(index, field_type);
+                    }
+
+                    Instruction::Astore(index) => {
+                        let field_type = state.field_type_stack.pop().unwrap();
+                        state.store_local_type(*index as usize, field_type);
                     }
 
                     Instruction::Aload0
@@ -1084,13 +1506,92 @@ impl<'ctx> ClassFileCompiler {
                         state.field_type_stack.push(filed_type);
                     }
 
+                    Instruction::Aload(index) => {
+                        let filed_type = state.locals_field_types[*index as usize].clone().unwrap();
+                        state.field_type_stack.push(filed_type);
+                    }
+
+                    Instruction::New(index) => {
+                        let class_name = self.get_class_name_const(*index as usize);
+                        state
+                            .field_type_stack
+                            .push(FieldType::ObjectType(class_name));
+                    }
+
+                    Instruction::Instanceof(_) => {
+                        state.field_type_stack.pop();
+                        state.field_type_stack.push(INT);
+                    }
+
+                    Instruction::Checkcast(index) => {
+                        let class_name = self.get_class_name_const(*index as usize);
+                        state.field_type_stack.pop();
+                        state.field_type_stack.push(parse_field_type_descriptor(
+                            &class_name_to_descriptor(&class_name),
+                        ));
+                    }
+
+                    Instruction::Anewarray(index) => {
+                        let class_name = self.get_class_name_const(*index as usize);
+                        state.field_type_stack.pop(); // Size.
+                        state.field_type_stack.push(FieldType::ArrayType(Box::new(
+                            parse_field_type_descriptor(&class_name_to_descriptor(&class_name)),
+                        )));
+                    }
+
+                    Instruction::Multianewarray { index, dimensions } => {
+                        let class_name = self.get_class_name_const(*index as usize);
+                        for _ in 0..*dimensions {
+                            state.field_type_stack.pop();
+                        }
+                        state
+                            .field_type_stack
+                            .push(parse_field_type_descriptor(&class_name));
+                    }
+
+                    Instruction::Dup
+                    | Instruction::Dupx1
+                    | Instruction::Dupx2
+                    | Instruction::Dup2
+                    | Instruction::Dup2x1
+                    | Instruction::Dup2x2
+                    | Instruction::Pop
+                    | Instruction::Pop2
+                    | Instruction::Swap => {
+                        shuffle_operand_stack(&mut state.field_type_stack, instr, |t| {
+                            matches!(
+                                t,
+                                FieldType::BaseType(BaseType::Long)
+                                    | FieldType::BaseType(BaseType::Double)
+                            )
+                        });
+                    }
+
+                    Instruction::Athrow => {
+                        state.field_type_stack.pop();
+                    }
+
+                    Instruction::Getfield(index) => {
+                        let (_, _, descriptor) = self.resolve_field_ref(*index);
+                        state.field_type_stack.pop(); // Object ref.
+                        state
+                            .field_type_stack
+                            .push(parse_field_type_descriptor(&descriptor));
+                    }
+
+                    Instruction::Putfield(_) => {
+                        state.field_type_stack.pop(); // Value.
+                        state.field_type_stack.pop(); // Object ref.
+                    }
+
                     Instruction::Iastore
                     | Instruction::Bastore
                     | Instruction::Sastore
                     | Instruction::Castore
                     | Instruction::Lastore
                     | Instruction::Fastore
-                    | Instruction::Dastore => {
+                    | Instruction::Dastore
+                    | Instruction::Aastore => {
                         state.field_type_stack.pop(); // Array ref.
                         state.field_type_stack.pop(); // Index.
                         state.field_type_stack.pop(); // Value.
@@ -1123,26 +1624,90 @@ impl<'ctx> ClassFileCompiler {
                         state.field_type_stack.push(DOUBLE);
                     }
 
-                    _ => {
-                        panic!("{}: {:?}", addr, instr)
-                    }
+                    _ => return Err(unsupported(*addr, format!("{:?}", instr))),
                 }
             }
+            if !state.field_type_stack.is_empty() {
+                return Err(verify_error(
+                    code.last().unwrap().0,
+                    format!(
+                        "operand stack {:?} left at the end of the code",
+                        state.field_type_stack
+                    ),
+                ));
+            }
         }
+        Ok(())
+    }
 
-        assert!(
-            state.field_type_stack.is_empty(),
-            "{:?}",
-            state.field_type_stack
+    /// Returns the frames of the StackMapTable attribute of the code, which is absent if the method has no
+    /// branches.
+    fn stack_map_frames(
+        &self,
+        code_attr: &CodeAttribute,
+        code: &[(usize, Instruction)],
+        state: &CompilationState<'ctx>,
+    ) -> Vec<(usize, FrameTypes)> {
+        let Some(attr_info) = code_attr.attributes.iter().find(|attr_info| {
+            self.get_utf8_const(attr_info.attribute_name_index as usize) == "StackMapTable"
+        }) else {
+            return Vec::new();
+        };
+        let (_, attr) = stack_map_table_attribute_parser(&attr_info.info).unwrap();
+
+        let mut initial_locals = Vec::new();
+        if !state.is_static {
+            initial_locals.push(Some(FieldType::ObjectType(self.class_name())));
+        }
+        initial_locals.extend(
+            state
+                .function_method_type()
+                .parameter_types
+                .iter()
+                .cloned()
+                .map(Some),
         );
+        let resolve = |v: &VerificationTypeInfo| match v {
+            VerificationTypeInfo::Integer => FieldType::BaseType(BaseType::Int),
+            VerificationTypeInfo::Float => FieldType::BaseType(BaseType::Float),
+            VerificationTypeInfo::Long => FieldType::BaseType(BaseType::Long),
+            VerificationTypeInfo::Double => FieldType::BaseType(BaseType::Double),
+            VerificationTypeInfo::Null => NULL_TYPE,
+            VerificationTypeInfo::UninitializedThis => FieldType::ObjectType(self.class_name()),
+            VerificationTypeInfo::Object { class } => parse_field_type_descriptor(
+                &class_name_to_descriptor(&self.get_class_name_const(*class as usize)),
+            ),
+            // The object created by the new instruction at the offset, but not initialized yet.
+            VerificationTypeInfo::Uninitialized { offset } => {
+                match code.iter().find(|(addr, _)| *addr == *offset as usize) {
+                    Some((_, Instruction::New(index))) => {
+                        FieldType::ObjectType(self.get_class_name_const(*index as usize))
+                    }
+                    v => unreachable!("{:?}", v),
+                }
+            }
+            VerificationTypeInfo::Top => unreachable!(),
+        };
+        // The frames are checked by the verifier.
+        expand_stack_map_frames(&attr.entries, initial_locals, |v| match v {
+            VerificationTypeInfo::Top => Ok(None),
+            v => Ok(Some(resolve(v))),
+        })
+        .unwrap()
+        .into_iter()
+        .map(|entry| (entry.offset, FrameTypes::new(entry)))
+        .collect()
     }
 
+    /// Ensures the label of the branch target or the fall-through of the conditional branch at the address. Every
+    /// branch target has a stack map frame, so the labels created here are the fall-throughs, which start with the
+    /// types as they are.
     fn ensure_label_with_offset(
         &self,
         ctx: &CodegenContext<'ctx>,
         state: &mut CompilationState<'ctx>,
         current_addr: usize,
-        offset: i16,
+        offset: i32,
     ) {
         let target_addr = (current_addr as isize + offset as isize) as usize;
         // If the target_addr doesn't have a label assigned, create it.
@@ -1154,7 +1719,278 @@ impl<'ctx> ClassFileCompiler {
             state
                 .label_field_type_stack
                 .insert(target_addr, state.field_type_stack.clone());
+            state
+                .label_locals_field_types
+                .insert(target_addr, state.locals_field_types.clone());
+        }
+    }
+
+    /// Initializes the class on its first active use, i.e. new, getstatic, putstatic or invokestatic, where
+    /// `field_name` is given for the static field accesses. The class being compiled and its ancestors are
+    /// always initialized before any of its code runs.
+    fn initialize_class(
+        &self,
+        ctx: &mut CodegenContext<'ctx>,
+        state: &mut CompilationState<'ctx>,
+        addr: usize,
+        class_name: &String,
+        field_name: Option<&String>,
+    ) {
+        if *class_name == self.class_name() {
+            return;
+        }
+        match field_name {
+            Some(field_name) => build_static_field_class_initialization(
+                ctx,
+                class_name,
+                field_name,
+                state.isolate_ptr(),
+            ),
+            None => build_class_initialization(ctx, class_name, state.isolate_ptr()),
+        }
+        self.check_pending_exception(ctx, state, addr);
+    }
+
+    /// Inserts the check of the pending exception right after the instruction at `addr` which may throw,
+    /// and dispatches it to the exception handlers if any.
+    fn check_pending_exception(
+        &self,
+        ctx: &mut CodegenContext<'ctx>,
+        state: &mut CompilationState<'ctx>,
+        addr: usize,
+    ) {
+        let exception = load_pending_exception(ctx, state.isolate_ptr());
+        let is_null = ctx.builder.build_is_null(exception, "no_exception");
+        let no_exception_blk = ctx
+            .context
+            .append_basic_block(state.function(), format!("l{}_no_exception", addr).as_str());
+        let exception_blk = ctx
+            .context
+            .append_basic_block(state.function(), format!("l{}_exception", addr).as_str());
+        ctx.builder
+            .build_conditional_branch(is_null, no_exception_blk, exception_blk);
+
+        ctx.builder.position_at_end(exception_blk);
+        self.dispatch_exception(ctx, state, addr, exception);
+
+        ctx.builder.position_at_end(no_exception_blk);
+    }
+
+    /// Jumps to the first exception handler covering `addr` whose catch type matches the exception.
+    /// If there's no such handler, returns from the function leaving the exception pending so that
+    /// the caller can dispatch it.
+    fn dispatch_exception(
+        &self,
+        ctx: &mut CodegenContext<'ctx>,
+        state: &mut CompilationState<'ctx>,
+        addr: usize,
+        exception: PointerValue<'ctx>,
+    ) {
+        let entries: Vec<ExceptionEntry> = state
+            .exception_table
+            .iter()
+            .filter(|entry| entry.start_pc as usize <= addr && addr < entry.end_pc as usize)
+            .cloned()
+            .collect();
+
+        for entry in entries {
+            let handler_blk = state.labels[&(entry.handler_pc as usize)];
+            let caught_blk = if entry.catch_type == 0 {
+                // Catch-any handler, e.g. finally.
+                None
+            } else {
+                let exception_class = self.get_class_name_const(entry.catch_type as usize);
+                let class_id = ctx.get_class_id_value(&exception_class);
+                let matched = ctx
+                    .builder
+                    .build_call(
+                        ctx.is_instance_of_fn,
+                        &[
+                            state.isolate_ptr().into(),
+                            exception.into(),
+                            class_id.into(),
+                        ],
+                        "is_instance_of",
+                    )
+                    .try_as_basic_value()
+                    .left()
+                    .unwrap()
+                    .into_int_value();
+                let caught_blk = ctx.context.append_basic_block(
+                    state.function(),
+                    format!("l{}_caught_at_{}", addr, entry.handler_pc).as_str(),
+                );
+                let next_blk = ctx.context.append_basic_block(
+                    state.function(),
+                    format!("l{}_not_caught_at_{}", addr, entry.handler_pc).as_str(),
+                );
+                ctx.builder
+                    .build_conditional_branch(matched, caught_blk, next_blk);
+                Some((caught_blk, next_blk))
+            };
+
+            if let Some((caught_blk, _)) = caught_blk {
+                ctx.builder.position_at_end(caught_blk);
+            }
+            store_pending_exception(ctx, state.isolate_ptr(), ctx.void_ptr.const_null());
+            // The handler starts with the exception as the only value on the stack.
+            let stack = std::mem::replace(&mut state.value_stack, vec![exception.into()]);
+            state.set_locals_edges(ctx, handler_blk);
+            state.value_stack = stack;
+            ctx.builder.build_unconditional_branch(handler_blk);
+
+            match caught_blk {
+                Some((_, next_blk)) => ctx.builder.position_at_end(next_blk),
+                None => return, // Catch-any handler catches everything, so the rest is unreachable.
+            }
+        }
+
+        // Propagate the exception to the caller. The return value is never used.
+        match state.function().get_type().get_return_type() {
+            Some(return_type) => {
+                ctx.builder.build_return(Some(&return_type.const_zero()));
+            }
+            None => {
+                ctx.builder.build_return(None);
+            }
+        }
+    }
+
+    /// Calls `throw_fn` to throw the exception if `cond` is true. Otherwise, continues at the new block.
+    fn build_throw_if(
+        &self,
+        ctx: &mut CodegenContext<'ctx>,
+        state: &mut CompilationState<'ctx>,
+        addr: usize,
+        cond: IntValue<'ctx>,
+        throw_fn: FunctionValue<'ctx>,
+        args: &[BasicMetadataValueEnum<'ctx>],
+    ) {
+        let throw_blk = ctx
+            .context
+            .append_basic_block(state.function(), format!("l{}_throw", addr).as_str());
+        let continue_blk = ctx
+            .context
+            .append_basic_block(state.function(), format!("l{}_continue", addr).as_str());
+        ctx.builder
+            .build_conditional_branch(cond, throw_blk, continue_blk);
+
+        ctx.builder.position_at_end(throw_blk);
+        let mut throw_args = vec![state.isolate_ptr().into()];
+        throw_args.extend_from_slice(args);
+        ctx.builder.build_call(throw_fn, &throw_args, "throw");
+        let exception = load_pending_exception(ctx, state.isolate_ptr());
+        self.dispatch_exception(ctx, state, addr, exception);
+
+        ctx.builder.position_at_end(continue_blk);
+    }
+
+    /// Throws NullPointerException if the object is null.
+    fn build_null_check(
+        &self,
+        ctx: &mut CodegenContext<'ctx>,
+        state: &mut CompilationState<'ctx>,
+        addr: usize,
+        obj_ptr: PointerValue<'ctx>,
+    ) {
+        let is_null = ctx.builder.build_is_null(obj_ptr, "is_null");
+        let throw_fn = ctx.throw_null_pointer_exception_fn;
+        self.build_throw_if(ctx, state, addr, is_null, throw_fn, &[]);
+    }
+
+    /// Throws NullPointerException if the array is null, or ArrayIndexOutOfBoundsException if the index is
+    /// out of the bounds of the array.
+    fn build_array_index_check(
+        &self,
+        ctx: &mut CodegenContext<'ctx>,
+        state: &mut CompilationState<'ctx>,
+        addr: usize,
+        array_ref: PointerValue<'ctx>,
+        index: IntValue<'ctx>,
+    ) {
+        self.build_null_check(ctx, state, addr, array_ref);
+        let length = load_array_length(ctx, array_ref);
+        // Negative indexes are also caught by the unsigned comparison.
+        let out_of_bounds =
+            ctx.builder
+                .build_int_compare(IntPredicate::UGE, index, length, "out_of_bounds");
+        let throw_fn = ctx.throw_array_index_out_of_bounds_exception_fn;
+        self.build_throw_if(
+            ctx,
+            state,
+            addr,
+            out_of_bounds,
+            throw_fn,
+            &[index.into(), length.into()],
+        );
+    }
+
+    /// Builds the signed division or remainder as per JVM spec: throws ArithmeticException if the divisor
+    /// is zero, and the division of the minimum value by -1 overflows to the minimum value (with the remainder 0)
+    /// instead of being undefined.
+    fn build_int_signed_div_or_rem(
+        &self,
+        ctx: &mut CodegenContext<'ctx>,
+        state: &mut CompilationState<'ctx>,
+        addr: usize,
+        dividend: IntValue<'ctx>,
+        divisor: IntValue<'ctx>,
+        is_rem: bool,
+    ) -> IntValue<'ctx> {
+        let typ = divisor.get_type();
+        let is_zero =
+            ctx.builder
+                .build_int_compare(IntPredicate::EQ, divisor, typ.const_zero(), "is_zero");
+        let throw_fn = ctx.throw_arithmetic_exception_fn;
+        self.build_throw_if(ctx, state, addr, is_zero, throw_fn, &[]);
+
+        let is_minus_one = ctx.builder.build_int_compare(
+            IntPredicate::EQ,
+            divisor,
+            typ.const_all_ones(),
+            "is_minus_one",
+        );
+        let safe_divisor = ctx
+            .builder
+            .build_select(is_minus_one, typ.const_int(1, false), divisor, "divisor")
+            .into_int_value();
+        if is_rem {
+            // x % 1 == x % -1 == 0.
+            return ctx
+                .builder
+                .build_int_signed_rem(dividend, safe_divisor, "remainder");
+        }
+        let quotient = ctx
+            .builder
+            .build_int_signed_div(dividend, safe_divisor, "quotient");
+        // Negation wraps around, so that MIN / -1 == MIN.
+        let negated = ctx.builder.build_int_neg(dividend, "negated");
+        ctx.builder
+            .build_select(is_minus_one, negated, quotient, "result")
+            .into_int_value()
+    }
+
+    /// Builds the switch on the key. `cases` are pairs of (match, offset) relative to `addr`.
+    fn build_switch(
+        &self,
+        ctx: &mut CodegenContext<'ctx>,
+        state: &mut CompilationState<'ctx>,
+        addr: usize,
+        key: IntValue<'ctx>,
+        default: i32,
+        cases: &[(i32, i32)],
+    ) {
+        let default_label = self.label_with_offset(state, addr, default);
+        state.set_locals_edges(ctx, default_label);
+        let mut llvm_cases = Vec::with_capacity(cases.len());
+        for (value, offset) in cases {
+            let label = self.label_with_offset(state, addr, *offset);
+            // Each case is a distinct edge even if it shares the label with others,
+            // so the phis need the incoming values for each of them.
+            state.set_locals_edges(ctx, label);
+            llvm_cases.push((ctx.i32_type.const_int(*value as u64, true), label));
         }
+        ctx.builder.build_switch(key, default_label, &llvm_cases);
     }
 
     fn load_constant(
@@ -1193,7 +2029,7 @@ impl<'ctx> ClassFileCompiler {
         &self,
         state: &mut CompilationState<'ctx>,
         current_addr: usize,
-        offset: i16,
+        offset: i32,
     ) -> BasicBlock<'ctx> {
         let target_addr = (current_addr as isize + offset as isize) as usize;
         state.labels[&target_addr]
@@ -1205,7 +2041,7 @@ impl<'ctx> ClassFileCompiler {
         method: &MethodInfo,
         state: &mut CompilationState<'ctx>,
     ) {
-        for attr_info in &method.attributes {
+        for attr_info in self.code_attributes(method) {
             let (_, code_attr) = code_attribute_parser(&attr_info.info).unwrap();
             let (_, code) = code_parser(&code_attr.code).unwrap();
             let mut terminated = true;
@@ -1227,10 +2063,6 @@ impl<'ctx> ClassFileCompiler {
                     insert_call_tracing_before(ctx, state);
                 }
 
-                if state.ignored_instructions.contains(&addr) {
-                    continue;
-                }
-
                 terminated = false;
                 match instr {
                     // ---- Allocations ----
@@ -1257,6 +2089,22 @@ impl<'ctx> ClassFileCompiler {
                         };
 
                         let size = state.pop_value();
+                        let is_negative = ctx.builder.build_int_compare(
+                            IntPredicate::SLT,
+                            size.into_int_value(),
+                            ctx.i32_type.const_zero(),
+                            "is_negative",
+                        );
+                        let throw_fn = ctx.throw_negative_array_size_exception_fn;
+                        self.build_throw_if(
+                            ctx,
+                            state,
+                            addr,
+                            is_negative,
+                            throw_fn,
+                            &[size.into()],
+                        );
+
                         let array_ptr = ctx
                             .builder
                             .build_call(f, &[state.isolate_ptr().into(), size.into()], "array_ptr")
@@ -1266,6 +2114,118 @@ impl<'ctx> ClassFileCompiler {
                         state.push_value(array_ptr.into());
                     }
 
+                    Instruction::Anewarray(index) => {
+                        let class_name = format!(
+                            "[{}",
+                            class_name_to_descriptor(&self.get_class_name_const(index as usize))
+                        );
+                        let class_id = ctx.get_class_id_value(&class_name);
+
+                        let size = state.pop_value();
+                        let is_negative = ctx.builder.build_int_compare(
+                            IntPredicate::SLT,
+                            size.into_int_value(),
+                            ctx.i32_type.const_zero(),
+                            "is_negative",
+                        );
+                        let throw_fn = ctx.throw_negative_array_size_exception_fn;
+                        self.build_throw_if(
+                            ctx,
+                            state,
+                            addr,
+                            is_negative,
+                            throw_fn,
+                            &[size.into()],
+                        );
+
+                        let array_ptr = ctx
+                            .builder
+                            .build_call(
+                                ctx.new_java_array_fn,
+                                &[state.isolate_ptr().into(), class_id.into(), size.into()],
+                                "array_ptr",
+                            )
+                            .try_as_basic_value()
+                            .left()
+                            .unwrap();
+                        state.push_value(array_ptr);
+                    }
+
+                    Instruction::Multianewarray { index, dimensions } => {
+                        let class_name = self.get_class_name_const(index as usize);
+                        let class_id = ctx.get_class_id_value(&class_name);
+
+                        // The sizes are passed to the runtime in the order of the dimensions.
+                        let sizes_type = ctx.i32_type.array_type(dimensions as u32);
+                        let sizes_ptr = ctx.build_entry_alloca(sizes_type, "sizes");
+                        for i in (0..dimensions as u64).rev() {
+                            let size = state.pop_value();
+                            let size_ptr = unsafe {
+                                ctx.builder.build_gep(
+                                    sizes_type,
+                                    sizes_ptr,
+                                    &[ctx.i32_type.const_zero(), ctx.i32_type.const_int(i, false)],
+                                    "size_ptr",
+                                )
+                            };
+                            ctx.builder.build_store(size_ptr, size);
+                        }
+
+                        let array_ptr = ctx
+                            .builder
+                            .build_call(
+                                ctx.new_multi_array_fn,
+                                &[
+                                    state.isolate_ptr().into(),
+                                    class_id.into(),
+                                    sizes_ptr.into(),
+                                    ctx.i32_type.const_int(dimensions as u64, false).into(),
+                                ],
+                                "array_ptr",
+                            )
+                            .try_as_basic_value()
+                            .left()
+                            .unwrap();
+                        self.check_pending_exception(ctx, state, addr);
+                        state.push_value(array_ptr);
+                    }
+
+                    Instruction::New(index) => {
+                        let class_name = self.get_class_name_const(index as usize);
+                        self.initialize_class(ctx, state, addr, &class_name, None);
+                        let class_id = ctx.get_class_id_value(&class_name);
+                        let obj_ptr = ctx
+                            .builder
+                            .build_call(
+                                ctx.new_instance_fn,
+                                &[state.isolate_ptr().into(), class_id.into()],
+                                "new_instance",
+                            )
+                            .try_as_basic_value()
+                            .left()
+                            .unwrap();
+                        state.push_value(obj_ptr);
+                    }
+
+                    // ---- stack manipulations ----
+                    Instruction::Dup
+                    | Instruction::Dupx1
+                    | Instruction::Dupx2
+                    | Instruction::Dup2
+                    | Instruction::Dup2x1
+                    | Instruction::Dup2x2
+                    | Instruction::Pop
+                    | Instruction::Pop2
+                    | Instruction::Swap => {
+                        let i64_type = ctx.i64_type;
+                        let f64_type = ctx.f64_type;
+                        shuffle_operand_stack(&mut state.value_stack, &instr, |v| match v {
+                            BasicValueEnum::IntValue(v) => v.get_type() == i64_type,
+                            BasicValueEnum::FloatValue(v) => v.get_type() == f64_type,
+                            _ => false,
+                        });
+                    }
+
                     // ---- consts ----
                     Instruction::Iconstm1 => {
                         state.push_value(ctx.i32_type.const_int((-1i64) as u64, true).into());
@@ -1374,6 +2334,13 @@ impl<'ctx> ClassFileCompiler {
                         // load onto the stack a reference from an array
                         let index = state.pop_value();
                         let array_ref = state.pop_value().into_pointer_value();
+                        self.build_array_index_check(
+                            ctx,
+                            state,
+                            addr,
+                            array_ref,
+                            index.into_int_value(),
+                        );
 
                         // First, we need to dereference the array_ref to get the address to the first element of the array.
                         let array_data_ptr_ptr = ctx
@@ -1391,8 +2358,6 @@ impl<'ctx> ClassFileCompiler {
                             "array_data_ptr",
                         );
 
-                        // TODO: out of bounds check.
-
                         // Next, we need to get the pointer to the element at the index.
                         let element_ptr = unsafe {
                             ctx.builder.build_gep(
@@ -1409,6 +2374,10 @@ impl<'ctx> ClassFileCompiler {
                         state.push_value(element);
                     }
 
+                    Instruction::Aconstnull => {
+                        state.push_value(ctx.void_ptr.const_null().into());
+                    }
+
                     Instruction::Aload0
                     | Instruction::Aload1
                     | Instruction::Aload2
@@ -1424,6 +2393,11 @@ impl<'ctx> ClassFileCompiler {
                         state.push_value(v);
                     }
 
+                    Instruction::Aload(index) => {
+                        let v = state.get_local(index as usize);
+                        state.push_value(v);
+                    }
+
                     Instruction::Iaload
                     | Instruction::Baload
                     | Instruction::Saload
@@ -1444,6 +2418,13 @@ impl<'ctx> ClassFileCompiler {
 
                         let index = state.pop_value();
                         let array_ref = state.pop_value().into_pointer_value();
+                        self.build_array_index_check(
+                            ctx,
+                            state,
+                            addr,
+                            array_ref,
+                            index.into_int_value(),
+                        );
 
                         // First, we need to dereference the array_ref to get the address to the first element of the array.
                         let array_data_ptr_ptr = ctx
@@ -1532,11 +2513,55 @@ impl<'ctx> ClassFileCompiler {
                     Instruction::Istore(index)
                     | Instruction::Lstore(index)
                     | Instruction::Fstore(index)
-                    | Instruction::Dstore(index) => {
+                    | Instruction::Dstore(index)
+                    | Instruction::Astore(index) => {
                         let v = state.pop_value();
                         state.set_local(index as usize, v);
                     }
 
+                    Instruction::Aastore => {
+                        let value = state.pop_value();
+                        let index = state.pop_value();
+                        let array_ref = state.pop_value().into_pointer_value();
+                        self.build_array_index_check(
+                            ctx,
+                            state,
+                            addr,
+                            array_ref,
+                            index.into_int_value(),
+                        );
+                        ctx.builder.build_call(
+                            ctx.check_array_store_fn,
+                            &[state.isolate_ptr().into(), array_ref.into(), value.into()],
+                            "check_array_store",
+                        );
+                        self.check_pending_exception(ctx, state, addr);
+
+                        let array_data_ptr_ptr = ctx
+                            .builder
+                            .build_struct_gep(
+                                ctx.java_array_struct_type,
+                                array_ref,
+                                1,
+                                "array_data_ptr_ptr",
+                            )
+                            .unwrap();
+                        let array_data_ptr = ctx.builder.build_load(
+                            ctx.void_ptr,
+                            array_data_ptr_ptr,
+                            "array_data_ptr",
+                        );
+                        let element_ptr = unsafe {
+                            ctx.builder.build_gep(
+                                ctx.ptr_sized_type.ptr_type(AddressSpace::default()),
+                                array_data_ptr.into_pointer_value(),
+                                &[index.into_int_value()],
+                                "element_ptr",
+                            )
+                        };
+                        ctx.builder.build_store(element_ptr, value);
+                    }
+
                     Instruction::Iastore
                     | Instruction::Bastore
                     | Instruction::Sastore
@@ -1547,6 +2572,13 @@ impl<'ctx> ClassFileCompiler {
                         let value = state.pop_value();
                         let index = state.pop_value();
                         let array_ref = state.pop_value().into_pointer_value();
+                        self.build_array_index_check(
+                            ctx,
+                            state,
+                            addr,
+                            array_ref,
+                            index.into_int_value(),
+                        );
 
                         // First, we need to dereference the array_ref to get the address to the first element of the array.
                         let array_data_ptr_ptr = ctx
@@ -1578,15 +2610,7 @@ impl<'ctx> ClassFileCompiler {
 
                     // ------- function calls --------
                     Instruction::Invokevirtual(index) => {
-                        let method_ref = match self.get_const(index as usize) {
-                            ConstantInfo::MethodRef(method_ref) => method_ref,
-                            v => unreachable!("{:?}", v),
-                        };
-
-                        let (class_name, method_name, descriptor) = self.resolve_class_field(
-                            method_ref.class_index as usize,
-                            method_ref.name_and_type_index as usize,
-                        );
+                        let (class_name, method_name, descriptor) = self.resolve_method_ref(index);
 
                         let fn_type = {
                             let method_type = descriptor::parse_method_descriptor(&descriptor);
@@ -1611,6 +2635,7 @@ impl<'ctx> ClassFileCompiler {
                             .into_pointer_value();
 
                         let obj_ptr = state.pop_value().into_pointer_value();
+                        self.build_null_check(ctx, state, addr, obj_ptr);
                         // Next, we need to load the vtable pointer from the object which is the first field.
                         // The pointer we deal with is *i8, so simply dereference it.
                         let vtable_ptr =
@@ -1648,46 +2673,164 @@ impl<'ctx> ClassFileCompiler {
                         if let Some(ret) = ret_val {
                             state.push_value(ret);
                         }
+                        self.check_pending_exception(ctx, state, addr);
                     }
 
-                    Instruction::Invokestatic(index) => {
-                        let method_ref = match self.get_const(index as usize) {
-                            ConstantInfo::MethodRef(method_ref) => method_ref,
-                            v => unreachable!("{:?}", v),
-                        };
+                    Instruction::Invokedynamic(index) => {
+                        let (bootstrap_method, bootstrap_arguments, _, descriptor) =
+                            self.resolve_invoke_dynamic(index);
+                        let sig = parse_method_descriptor(&descriptor);
+                        let mut args = Vec::new();
+                        for _ in 0..sig.parameter_types.len() {
+                            args.push(state.pop_value());
+                        }
+                        args.reverse();
+                        if bootstrap_method == LAMBDA_METAFACTORY {
+                            let lambda_class = self.lambda_class(index);
+                            // The same call site can be compiled more than once, e.g. in finally blocks.
+                            if !ctx
+                                .lambda_classes
+                                .iter()
+                                .any(|c| c.class_name == lambda_class.class_name)
+                            {
+                                lambda_class.build_method(ctx);
+                                ctx.lambda_classes.push(lambda_class.as_class());
+                            }
+                            let obj_ptr =
+                                lambda_class.build_new_instance(ctx, state.isolate_ptr(), &args);
+                            state.push_value(obj_ptr.into());
+                        } else {
+                            // The constants are checked in analyze.
+                            let (recipe, constants) =
+                                self.string_concat_recipe(&bootstrap_arguments).unwrap();
+                            let ret = build_string_concat(
+                                ctx,
+                                state.isolate_ptr(),
+                                &recipe,
+                                &constants,
+                                &sig.parameter_types,
+                                &args,
+                            );
+                            state.push_value(ret.into());
+                            self.check_pending_exception(ctx, state, addr);
+                        }
+                    }
 
-                        let (class_name, method_name, descriptor_str) = self.resolve_class_field(
-                            method_ref.class_index as usize,
-                            method_ref.name_and_type_index as usize,
+                    Instruction::Invokeinterface { index, .. } => {
+                        let (class_name, method_name, descriptor) = self.resolve_method_ref(index);
+                        let fn_type = ctx.llvm_function_type_from_method_type(
+                            &parse_method_descriptor(&descriptor),
+                            false, /* interface invocation == non static */
                         );
 
+                        let mut args: Vec<BasicMetadataValueEnum> = Vec::new();
+                        for _ in 0..fn_type.count_param_types() - 1 {
+                            // -1 because the first parameter is the runtime object pointer.
+                            // This includes the this pointer.
+                            args.push(state.pop_value().into());
+                        }
+                        let obj_ptr = args.last().unwrap().into_pointer_value();
+                        self.build_null_check(ctx, state, addr, obj_ptr);
+                        args.push(state.isolate_ptr().into()); // First argument is always rt_ctx_ptr.
+                        args.reverse();
+
+                        // The dispatcher is built after all the classes implementing the interface are known.
+                        let symbol = format!("{}.{}:{}", class_name, method_name, descriptor);
+                        let dispatcher_symbol = format!("{}{}", INTERFACE_DISPATCH_PREFIX, symbol);
+                        let dispatcher = ctx
+                            .module
+                            .get_function(&dispatcher_symbol)
+                            .unwrap_or_else(|| {
+                                ctx.module.add_function(&dispatcher_symbol, fn_type, None)
+                            });
+                        ctx.invoked_interface_method_symbols.insert(symbol);
+
+                        let ret_val = ctx
+                            .builder
+                            .build_call(dispatcher, &args, "call")
+                            .try_as_basic_value()
+                            .left();
+
+                        if let Some(ret) = ret_val {
+                            state.push_value(ret);
+                        }
+                        self.check_pending_exception(ctx, state, addr);
+                    }
+
+                    Instruction::Invokestatic(index) => {
+                        let (class_name, method_name, descriptor_str) =
+                            self.resolve_method_ref(index);
+
                         let descriptor = parse_method_descriptor(&descriptor_str);
+                        self.initialize_class(ctx, state, addr, &class_name, None);
+
+                        let (method, symbol) = self.get_method_by_symbol(
+                            ctx,
+                            &class_name,
+                            &method_name,
+                            &descriptor_str,
+                            &descriptor,
+                            true, // static invocation.
+                        );
+                        // The method can be declared in any of the ancestors of the class.
+                        ctx.invoked_static_method_symbols.insert(symbol);
 
-                        if !self.class_name.clone().eq(&class_name) {
-                            panic!("TODO: non-local static invocation: {}", class_name)
+                        let mut args = Vec::new();
+                        for _ in 0..method.count_params() - 1 {
+                            // -1 because the first parameter is the runtime object pointer.
+                            args.push(state.pop_value().into());
+                        }
+
+                        // Get the runtime context pointer from the first parameter of this function.
+                        let rt_ctx_ptr = state
+                            .function()
+                            .get_nth_param(0)
+                            .unwrap()
+                            .into_pointer_value();
+
+                        args.push(rt_ctx_ptr.into()); // First argument is always rt_ctx_ptr.
+                        args.reverse();
+
+                        let ret_val = ctx
+                            .builder
+                            .build_call(method, &args, "call")
+                            .try_as_basic_value()
+                            .left();
+
+                        if let Some(ret) = ret_val {
+                            state.push_value(ret);
+                        }
+                        self.check_pending_exception(ctx, state, addr);
+                    }
+
+                    Instruction::Invokespecial(index) => {
+                        let (class_name, method_name, descriptor_str) =
+                            self.resolve_method_ref(index);
+
+                        if class_name == "java/lang/Object" && method_name == "<init>" {
+                            // java/lang/Object has nothing to initialize, so just drop the this pointer.
+                            state.pop_value();
                         } else {
-                            let (method, _) = self.get_local_method_by_symbol(
+                            let descriptor = parse_method_descriptor(&descriptor_str);
+                            let (method, symbol) = self.get_method_by_symbol(
                                 ctx,
+                                &class_name,
                                 &method_name,
                                 &descriptor_str,
                                 &descriptor,
-                                true, // static invocation.
+                                false, // special invocation always takes this pointer.
                             );
+                            // The method can be declared in any of the ancestors of the class, e.g. super.toString().
+                            ctx.invoked_special_method_symbols.insert(symbol);
 
                             let mut args = Vec::new();
                             for _ in 0..method.count_params() - 1 {
                                 // -1 because the first parameter is the runtime object pointer.
+                                // This includes the this pointer.
                                 args.push(state.pop_value().into());
                             }
 
-                            // Get the runtime context pointer from the first parameter of this function.
-                            let rt_ctx_ptr = state
-                                .function()
-                                .get_nth_param(0)
-                                .unwrap()
-                                .into_pointer_value();
-
-                            args.push(rt_ctx_ptr.into()); // First argument is always rt_ctx_ptr.
+                            args.push(state.isolate_ptr().into()); // First argument is always rt_ctx_ptr.
                             args.reverse();
 
                             let ret_val = ctx
@@ -1699,19 +2842,45 @@ impl<'ctx> ClassFileCompiler {
                             if let Some(ret) = ret_val {
                                 state.push_value(ret);
                             }
+                            self.check_pending_exception(ctx, state, addr);
                         }
                     }
 
-                    Instruction::Invokespecial(index) => {
-                        let method_ref = match self.get_const(index as usize) {
-                            ConstantInfo::MethodRef(method_ref) => method_ref,
-                            v => unreachable!("{:?}", v),
-                        };
+                    Instruction::Athrow => {
+                        let exception = state.pop_value().into_pointer_value();
+                        self.build_null_check(ctx, state, addr, exception);
+                        store_pending_exception(ctx, state.isolate_ptr(), exception);
+                        self.dispatch_exception(ctx, state, addr, exception);
+                        terminated = true;
+                    }
+
+                    Instruction::Getfield(index) => {
+                        let (class_name, field_name, descriptor) = self.resolve_field_ref(index);
+                        let typ: BasicTypeEnum = ctx
+                            .llvm_type_from_field_type(&parse_field_type_descriptor(&descriptor));
+                        let obj_ptr = state.pop_value().into_pointer_value();
+                        self.build_null_check(ctx, state, addr, obj_ptr);
+                        let field_ptr =
+                            load_instance_field_ptr(ctx, &class_name, obj_ptr, &field_name, typ);
+                        let loaded = ctx.builder.build_load(typ, field_ptr, &field_name);
+                        state.push_value(loaded);
+                    }
 
-                        let (_, _, _) = self.resolve_class_field(
-                            method_ref.class_index as usize,
-                            method_ref.name_and_type_index as usize,
+                    Instruction::Putfield(index) => {
+                        let (class_name, field_name, descriptor) = self.resolve_field_ref(index);
+                        let field_type = parse_field_type_descriptor(&descriptor);
+                        let value = state.pop_value();
+                        let value = build_instance_field_value_narrowing(ctx, &field_type, value);
+                        let obj_ptr = state.pop_value().into_pointer_value();
+                        self.build_null_check(ctx, state, addr, obj_ptr);
+                        let field_ptr = load_instance_field_ptr(
+                            ctx,
+                            &class_name,
+                            obj_ptr,
+                            &field_name,
+                            value.get_type(),
                         );
+                        ctx.builder.build_store(field_ptr, value);
                     }
 
                     Instruction::Ldc(index) => self.load_constant(ctx, state, index as u16),
@@ -1728,27 +2897,14 @@ impl<'ctx> ClassFileCompiler {
                             field_ref.name_and_type_index as usize,
                         );
 
-                        let typ = match parse_field_type_descriptor(&descriptor) {
-                            FieldType::ObjectType(_) => ctx.void_ptr.into(),
-                            FieldType::BaseType(BaseType::Boolean)
-                            | FieldType::BaseType(BaseType::Byte)
-                            | FieldType::BaseType(BaseType::Short)
-                            | FieldType::BaseType(BaseType::Char)
-                            | FieldType::BaseType(BaseType::Int) => ctx.i32_type.into(),
-                            FieldType::BaseType(BaseType::Long) => ctx.i64_type.into(),
-                            FieldType::BaseType(BaseType::Float) => ctx.f32_type.into(),
-                            FieldType::BaseType(BaseType::Double) => ctx.f64_type.into(),
-                            field_type => unreachable!("{:?}", field_type),
-                        };
-
-                        let field_ptr = load_class_obj_static_field_ptr(
+                        self.initialize_class(ctx, state, addr, &class_name, Some(&field_name));
+                        let loaded = build_static_field_load(
                             ctx,
                             &class_name,
                             state.isolate_ptr(),
                             &field_name,
-                            typ,
+                            &parse_field_type_descriptor(&descriptor),
                         );
-                        let loaded = ctx.builder.build_load(typ, field_ptr, &field_name);
                         state.push_value(loaded.into());
                     }
 
@@ -1757,38 +2913,63 @@ impl<'ctx> ClassFileCompiler {
                             ConstantInfo::FieldRef(field_ref) => field_ref,
                             v => unreachable!("{:?}", v),
                         };
-                        let (class_name, field_name, _) = self.resolve_class_field(
+                        let (class_name, field_name, descriptor) = self.resolve_class_field(
                             field_ref.class_index as usize,
                             field_ref.name_and_type_index as usize,
                         );
 
                         let value = state.pop_value();
 
-                        let field_ptr = load_class_obj_static_field_ptr(
+                        self.initialize_class(ctx, state, addr, &class_name, Some(&field_name));
+                        build_static_field_store(
                             ctx,
                             &class_name,
                             state.isolate_ptr(),
                             &field_name,
-                            value.get_type(),
+                            &parse_field_type_descriptor(&descriptor),
+                            value,
                         );
-                        ctx.builder.build_store(field_ptr, value);
                     }
 
-                    Instruction::Arraylength => {
-                        let array_ref = state.pop_value().into_pointer_value();
-                        let length_ptr = ctx
+                    Instruction::Instanceof(index) => {
+                        let class_name = self.get_class_name_const(index as usize);
+                        let class_id = ctx.get_class_id_value(&class_name);
+                        let obj_ref = state.pop_value();
+                        let is_instance = ctx
                             .builder
-                            .build_struct_gep(
-                                ctx.java_array_struct_type,
-                                array_ref,
-                                2,
-                                "length_ptr",
+                            .build_call(
+                                ctx.is_instance_of_fn,
+                                &[state.isolate_ptr().into(), obj_ref.into(), class_id.into()],
+                                "is_instance_of",
                             )
-                            .unwrap();
+                            .try_as_basic_value()
+                            .left()
+                            .unwrap()
+                            .into_int_value();
+                        let result =
+                            ctx.builder
+                                .build_int_z_extend(is_instance, ctx.i32_type, "result");
+                        state.push_value(result.into());
+                    }
+
+                    Instruction::Checkcast(index) => {
+                        let class_name = self.get_class_name_const(index as usize);
+                        let class_id = ctx.get_class_id_value(&class_name);
+                        // The object stays on the stack as is.
+                        let obj_ref = *state.value_stack.last().unwrap();
+                        ctx.builder.build_call(
+                            ctx.check_cast_fn,
+                            &[state.isolate_ptr().into(), obj_ref.into(), class_id.into()],
+                            "check_cast",
+                        );
+                        self.check_pending_exception(ctx, state, addr);
+                    }
 
-                        let length_value =
-                            ctx.builder.build_load(ctx.i32_type, length_ptr, "length");
-                        state.push_value(length_value);
+                    Instruction::Arraylength => {
+                        let array_ref = state.pop_value().into_pointer_value();
+                        self.build_null_check(ctx, state, addr, array_ref);
+                        let length_value = load_array_length(ctx, array_ref);
+                        state.push_value(length_value.into());
                     }
                     Instruction::Iadd | Instruction::Ladd => {
                         let v2 = state.pop_value();
@@ -1811,16 +2992,99 @@ impl<'ctx> ClassFileCompiler {
                         state.push_value(result.into());
                     }
                     Instruction::Idiv | Instruction::Ldiv => {
-                        // TODO: exception!
                         let v2 = state.pop_value();
                         let v1 = state.pop_value();
-                        let result = ctx.builder.build_int_signed_div(
+                        let result = self.build_int_signed_div_or_rem(
+                            ctx,
+                            state,
+                            addr,
+                            v1.into_int_value(),
+                            v2.into_int_value(),
+                            false,
+                        );
+                        state.push_value(result.into());
+                    }
+                    Instruction::Irem | Instruction::Lrem => {
+                        let v2 = state.pop_value();
+                        let v1 = state.pop_value();
+                        let result = self.build_int_signed_div_or_rem(
+                            ctx,
+                            state,
+                            addr,
+                            v1.into_int_value(),
+                            v2.into_int_value(),
+                            true,
+                        );
+                        state.push_value(result.into());
+                    }
+                    Instruction::Ineg | Instruction::Lneg => {
+                        let v = state.pop_value();
+                        let result = ctx.builder.build_int_neg(v.into_int_value(), "result");
+                        state.push_value(result.into());
+                    }
+                    Instruction::Iand | Instruction::Land => {
+                        let v2 = state.pop_value();
+                        let v1 = state.pop_value();
+                        let result = ctx.builder.build_and(
+                            v1.into_int_value(),
+                            v2.into_int_value(),
+                            "result",
+                        );
+                        state.push_value(result.into());
+                    }
+                    Instruction::Ior | Instruction::Lor => {
+                        let v2 = state.pop_value();
+                        let v1 = state.pop_value();
+                        let result = ctx.builder.build_or(
+                            v1.into_int_value(),
+                            v2.into_int_value(),
+                            "result",
+                        );
+                        state.push_value(result.into());
+                    }
+                    Instruction::Ixor | Instruction::Lxor => {
+                        let v2 = state.pop_value();
+                        let v1 = state.pop_value();
+                        let result = ctx.builder.build_xor(
                             v1.into_int_value(),
                             v2.into_int_value(),
                             "result",
                         );
                         state.push_value(result.into());
                     }
+                    Instruction::Ishl
+                    | Instruction::Ishr
+                    | Instruction::Iushr
+                    | Instruction::Lshl
+                    | Instruction::Lshr
+                    | Instruction::Lushr => {
+                        // The shift count is always int, even for long shifts.
+                        let count = state.pop_value().into_int_value();
+                        let v = state.pop_value().into_int_value();
+                        let typ = v.get_type();
+                        // Only the lowest 5 bits (int) or 6 bits (long) of the count are used.
+                        let count = ctx
+                            .builder
+                            .build_int_z_extend_or_bit_cast(count, typ, "count");
+                        let count = ctx.builder.build_and(
+                            count,
+                            typ.const_int((typ.get_bit_width() - 1) as u64, false),
+                            "masked_count",
+                        );
+                        let result = match instr {
+                            Instruction::Ishl | Instruction::Lshl => {
+                                ctx.builder.build_left_shift(v, count, "result")
+                            }
+                            Instruction::Ishr | Instruction::Lshr => {
+                                ctx.builder.build_right_shift(v, count, true, "result")
+                            }
+                            Instruction::Iushr | Instruction::Lushr => {
+                                ctx.builder.build_right_shift(v, count, false, "result")
+                            }
+                            _ => unreachable!(),
+                        };
+                        state.push_value(result.into());
+                    }
                     Instruction::Imul | Instruction::Lmul => {
                         let v2 = state.pop_value();
                         let v1 = state.pop_value();
@@ -1876,6 +3140,24 @@ impl<'ctx> ClassFileCompiler {
                         state.push_value(result.into());
                     }
 
+                    Instruction::Frem | Instruction::Drem => {
+                        // LLVM's frem has the same semantics as fmod in C, which is what Java specifies.
+                        let v2 = state.pop_value();
+                        let v1 = state.pop_value();
+                        let result = ctx.builder.build_float_rem(
+                            v1.into_float_value(),
+                            v2.into_float_value(),
+                            "result",
+                        );
+                        state.push_value(result.into());
+                    }
+
+                    Instruction::Fneg | Instruction::Dneg => {
+                        let v = state.pop_value();
+                        let result = ctx.builder.build_float_neg(v.into_float_value(), "result");
+                        state.push_value(result.into());
+                    }
+
                     Instruction::I2b => {
                         let v = state.pop_value();
                         let result_8 = ctx.builder.build_int_truncate(
@@ -1912,9 +3194,58 @@ impl<'ctx> ClassFileCompiler {
                                 .build_int_z_extend(result_c, ctx.i32_type, "result");
                         state.push_value(result.into());
                     }
+                    Instruction::I2l => {
+                        let v = state.pop_value().into_int_value();
+                        let result = ctx.builder.build_int_s_extend(v, ctx.i64_type, "result");
+                        state.push_value(result.into());
+                    }
+                    Instruction::L2i => {
+                        let v = state.pop_value().into_int_value();
+                        let result = ctx.builder.build_int_truncate(v, ctx.i32_type, "result");
+                        state.push_value(result.into());
+                    }
+                    Instruction::I2f | Instruction::L2f => {
+                        let v = state.pop_value().into_int_value();
+                        let result =
+                            ctx.builder
+                                .build_signed_int_to_float(v, ctx.f32_type, "result");
+                        state.push_value(result.into());
+                    }
+                    Instruction::I2d | Instruction::L2d => {
+                        let v = state.pop_value().into_int_value();
+                        let result =
+                            ctx.builder
+                                .build_signed_int_to_float(v, ctx.f64_type, "result");
+                        state.push_value(result.into());
+                    }
+                    Instruction::F2d => {
+                        let v = state.pop_value().into_float_value();
+                        let result = ctx.builder.build_float_ext(v, ctx.f64_type, "result");
+                        state.push_value(result.into());
+                    }
+                    Instruction::D2f => {
+                        let v = state.pop_value().into_float_value();
+                        let result = ctx.builder.build_float_trunc(v, ctx.f32_type, "result");
+                        state.push_value(result.into());
+                    }
+                    Instruction::F2i | Instruction::D2i => {
+                        let v = state.pop_value().into_float_value();
+                        let result = build_float_to_int_saturating(ctx, v, ctx.i32_type);
+                        state.push_value(result.into());
+                    }
+                    Instruction::F2l | Instruction::D2l => {
+                        let v = state.pop_value().into_float_value();
+                        let result = build_float_to_int_saturating(ctx, v, ctx.i64_type);
+                        state.push_value(result.into());
+                    }
 
-                    Instruction::Iinc { index, value } => {
-                        let value = value as i64; // sign extend
+                    Instruction::Iinc { .. } | Instruction::IincWide { .. } => {
+                        // The increment is sign extended.
+                        let (index, value) = match instr {
+                            Instruction::Iinc { index, value } => (index as u16, value as i64),
+                            Instruction::IincWide { index, value } => (index, value as i64),
+                            _ => unreachable!(),
+                        };
                         let local = state.get_local(index as usize);
                         let new_local = ctx.builder.build_int_add(
                             local.into_int_value(),
@@ -1934,7 +3265,7 @@ impl<'ctx> ClassFileCompiler {
                     | Instruction::IfIcmplt(diff)
                     | Instruction::IfIcmpne(diff)
                     | Instruction::IfIcmpeq(diff) => {
-                        let then_label = self.label_with_offset(state, addr, diff);
+                        let then_label = self.label_with_offset(state, addr, diff.into());
                         state.set_locals_edges(ctx, then_label);
                         let else_label = self.label_with_offset(state, addr, 3);
                         state.set_locals_edges(ctx, else_label);
@@ -1962,12 +3293,53 @@ impl<'ctx> ClassFileCompiler {
                         terminated = true;
                     }
 
+                    Instruction::IfAcmpeq(diff) | Instruction::IfAcmpne(diff) => {
+                        let then_label = self.label_with_offset(state, addr, diff.into());
+                        state.set_locals_edges(ctx, then_label);
+                        let else_label = self.label_with_offset(state, addr, 3);
+                        state.set_locals_edges(ctx, else_label);
+
+                        let v2 = state.pop_value().into_pointer_value();
+                        let v1 = state.pop_value().into_pointer_value();
+                        let cond = ctx.builder.build_int_compare(
+                            match instr {
+                                Instruction::IfAcmpeq(_) => IntPredicate::EQ,
+                                Instruction::IfAcmpne(_) => IntPredicate::NE,
+                                _ => unreachable!(),
+                            },
+                            v1,
+                            v2,
+                            "cond",
+                        );
+                        ctx.builder
+                            .build_conditional_branch(cond, then_label, else_label);
+                        terminated = true;
+                    }
+
+                    Instruction::Ifnull(diff) | Instruction::Ifnonnull(diff) => {
+                        let then_label = self.label_with_offset(state, addr, diff.into());
+                        state.set_locals_edges(ctx, then_label);
+                        let else_label = self.label_with_offset(state, addr, 3);
+                        state.set_locals_edges(ctx, else_label);
+
+                        let v = state.pop_value().into_pointer_value();
+                        let cond = match instr {
+                            Instruction::Ifnull(_) => ctx.builder.build_is_null(v, "cond"),
+                            Instruction::Ifnonnull(_) => ctx.builder.build_is_not_null(v, "cond"),
+                            _ => unreachable!(),
+                        };
+                        ctx.builder
+                            .build_conditional_branch(cond, then_label, else_label);
+                        terminated = true;
+                    }
+
                     Instruction::Ifne(diff)
                     | Instruction::Ifeq(diff)
                     | Instruction::Ifgt(diff)
                     | Instruction::Ifge(diff)
+                    | Instruction::Iflt(diff)
                     | Instruction::Ifle(diff) => {
-                        let then_label = self.label_with_offset(state, addr, diff);
+                        let then_label = self.label_with_offset(state, addr, diff.into());
                         state.set_locals_edges(ctx, then_label);
                         let else_label = self.label_with_offset(state, addr, 3);
                         state.set_locals_edges(ctx, else_label);
@@ -1980,6 +3352,7 @@ impl<'ctx> ClassFileCompiler {
                                     Instruction::Ifeq(_) => IntPredicate::EQ,
                                     Instruction::Ifgt(_) => IntPredicate::SGT,
                                     Instruction::Ifge(_) => IntPredicate::SGE,
+                                    Instruction::Iflt(_) => IntPredicate::SLT,
                                     Instruction::Ifle(_) => IntPredicate::SLE,
                                     _ => unreachable!(),
                                 }
@@ -2076,23 +3449,40 @@ impl<'ctx> ClassFileCompiler {
                         state.push_value(result);
                     }
 
-                    Instruction::Goto(diff) => {
-                        let label = self.label_with_offset(state, addr, diff);
-                        state.set_locals_edges(ctx, label);
-                        ctx.builder.build_unconditional_branch(label);
+                    Instruction::Tableswitch {
+                        default,
+                        low,
+                        high: _,
+                        offsets,
+                    } => {
+                        let key = state.pop_value().into_int_value();
+                        let cases: Vec<(i32, i32)> = offsets
+                            .iter()
+                            .enumerate()
+                            .map(|(i, offset)| (low + i as i32, *offset))
+                            .collect();
+                        self.build_switch(ctx, state, addr, key, default, &cases);
                         terminated = true;
                     }
 
-                    Instruction::Areturn => {
-                        if self.tracing_enabled {
-                            insert_call_tracing_after(ctx, state);
-                        }
-                        let v = state.pop_value();
-                        ctx.builder.build_return(Some(&v));
+                    Instruction::Lookupswitch { default, pairs } => {
+                        let key = state.pop_value().into_int_value();
+                        self.build_switch(ctx, state, addr, key, default, &pairs);
+                        terminated = true;
+                    }
+
+                    Instruction::Goto(diff) => {
+                        let label = self.label_with_offset(state, addr, diff.into());
+                        state.set_locals_edges(ctx, label);
+                        ctx.builder.build_unconditional_branch(label);
                         terminated = true;
                     }
 
-                    Instruction::Ireturn => {
+                    Instruction::Ireturn
+                    | Instruction::Lreturn
+                    | Instruction::Freturn
+                    | Instruction::Dreturn
+                    | Instruction::Areturn => {
                         if self.tracing_enabled {
                             insert_call_tracing_after(ctx, state);
                         }
@@ -2113,10 +3503,191 @@ impl<'ctx> ClassFileCompiler {
                         terminated = true;
                     }
                     instr => {
-                        panic!("{:?}", instr);
+                        unreachable!("{:?} is rejected by analyze", instr);
                     }
                 }
             }
         }
     }
 }
+
+/// Loads the length of the Java array, which must not be null.
+fn load_array_length<'ctx>(
+    ctx: &CodegenContext<'ctx>,
+    array_ref: PointerValue<'ctx>,
+) -> IntValue<'ctx> {
+    let length_ptr = ctx
+        .builder
+        .build_struct_gep(ctx.java_array_struct_type, array_ref, 2, "length_ptr")
+        .unwrap();
+    ctx.builder
+        .build_load(ctx.i32_type, length_ptr, "length")
+        .into_int_value()
+}
+
+/// Converts the float to the integer type as per JVM spec: NaN becomes 0, and values out of range
+/// saturate to the minimum or maximum value instead of being poison as with plain fptosi.
+fn build_float_to_int_saturating<'ctx>(
+    ctx: &CodegenContext<'ctx>,
+    value: FloatValue<'ctx>,
+    int_type: IntType<'ctx>,
+) -> IntValue<'ctx> {
+    let float_type = value.get_type();
+    let bits = int_type.get_bit_width();
+    // -2^(bits-1) and 2^(bits-1) are exactly representable in both float and double.
+    let min_float = float_type.const_float(-(2f64.powi(bits as i32 - 1)));
+    let max_float = float_type.const_float(2f64.powi(bits as i32 - 1));
+    let min_int = int_type.const_int(1 << (bits - 1), false);
+    let max_int = int_type.const_int((1 << (bits - 1)) - 1, false);
+
+    let converted = ctx
+        .builder
+        .build_float_to_signed_int(value, int_type, "converted");
+    let is_too_small =
+        ctx.builder
+            .build_float_compare(FloatPredicate::OLE, value, min_float, "is_too_small");
+    let is_too_large =
+        ctx.builder
+            .build_float_compare(FloatPredicate::OGE, value, max_float, "is_too_large");
+    let is_nan = ctx
+        .builder
+        .build_float_compare(FloatPredicate::UNO, value, value, "is_nan");
+    // The poison from fptosi is never selected when the value is out of range.
+    let result = ctx
+        .builder
+        .build_select(is_too_small, min_int, converted, "result")
+        .into_int_value();
+    let result = ctx
+        .builder
+        .build_select(is_too_large, max_int, result, "result")
+        .into_int_value();
+    ctx.builder
+        .build_select(is_nan, int_type.const_zero(), result, "result")
+        .into_int_value()
+}
+
+/// Applies one of the dup, pop and swap instructions to the operand stack, where each entry is a whole value
+/// and `is_category2` tells whether it is a long or double, i.e. takes two slots of the JVM operand stack.
+fn shuffle_operand_stack<T: Clone>(
+    stack: &mut Vec<T>,
+    instr: &Instruction,
+    is_category2: impl Fn(&T) -> bool,
+) {
+    // Counts the entries below the top `skip` entries which make up exactly `slots` slots.
+    let count_entries = |stack: &Vec<T>, skip: usize, slots: usize| -> usize {
+        let mut entries = 0;
+        let mut taken = 0;
+        while taken < slots {
+            let v = &stack[stack.len() - 1 - skip - entries];
+            taken += if is_category2(v) { 2 } else { 1 };
+            entries += 1;
+        }
+        assert_eq!(taken, slots, "{:?} splits a long or double", instr);
+        entries
+    };
+
+    // (slots duplicated from the top, slots skipped under them)
+    let (copy_slots, skip_slots) = match instr {
+        Instruction::Dup => (1, 0),
+        Instruction::Dupx1 => (1, 1),
+        Instruction::Dupx2 => (1, 2),
+        Instruction::Dup2 => (2, 0),
+        Instruction::Dup2x1 => (2, 1),
+        Instruction::Dup2x2 => (2, 2),
+        Instruction::Pop | Instruction::Pop2 => {
+            let slots = if matches!(instr, Instruction::Pop) {
+                1
+            } else {
+                2
+            };
+            let entries = count_entries(stack, 0, slots);
+            stack.truncate(stack.len() - entries);
+            return;
+        }
+        Instruction::Swap => {
+            let entries = count_entries(stack, 0, 2);
+            assert_eq!(entries, 2, "{:?} of a long or double", instr);
+            let len = stack.len();
+            stack.swap(len - 1, len - 2);
+            return;
+        }
+        _ => unreachable!("{:?}", instr),
+    };
+    let copied = count_entries(stack, 0, copy_slots);
+    let skipped = count_entries(stack, copied, skip_slots);
+    let values = stack[stack.len() - copied..].to_vec();
+    let at = stack.len() - copied - skipped;
+    stack.splice(at..at, values);
+}
+
+/// Returns the field descriptor of the class or array class named in the constant pool, e.g. "Ljava/lang/String;"
+/// for "java/lang/String" and "[I" for "[I".
+fn class_name_to_descriptor(class_name: &str) -> String {
+    if class_name.starts_with('[') {
+        class_name.to_string()
+    } else {
+        format!("L{};", class_name)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Values ending with 'L' are category 2.
+    fn shuffle(stack: &[&'static str], instr: Instruction) -> Vec<&'static str> {
+        let mut stack = stack.to_vec();
+        shuffle_operand_stack(&mut stack, &instr, |v| v.ends_with('L'));
+        stack
+    }
+
+    #[test]
+    fn test_shuffle_operand_stack() {
+        assert_eq!(shuffle(&["a", "b"], Instruction::Dup), ["a", "b", "b"]);
+        assert_eq!(shuffle(&["a", "b"], Instruction::Dupx1), ["b", "a", "b"]);
+        assert_eq!(
+            shuffle(&["a", "b", "c"], Instruction::Dupx2),
+            ["c", "a", "b", "c"]
+        );
+        assert_eq!(shuffle(&["aL", "b"], Instruction::Dupx2), ["b", "aL", "b"]);
+        assert_eq!(
+            shuffle(&["a", "b"], Instruction::Dup2),
+            ["a", "b", "a", "b"]
+        );
+        assert_eq!(shuffle(&["aL"], Instruction::Dup2), ["aL", "aL"]);
+        assert_eq!(
+            shuffle(&["a", "b", "c"], Instruction::Dup2x1),
+            ["b", "c", "a", "b", "c"]
+        );
+        assert_eq!(
+            shuffle(&["a", "bL"], Instruction::Dup2x1),
+            ["bL", "a", "bL"]
+        );
+        assert_eq!(
+            shuffle(&["a", "b", "c", "d"], Instruction::Dup2x2),
+            ["c", "d", "a", "b", "c", "d"]
+        );
+        assert_eq!(
+            shuffle(&["a", "b", "cL"], Instruction::Dup2x2),
+            ["cL", "a", "b", "cL"]
+        );
+        assert_eq!(
+            shuffle(&["aL", "b", "c"], Instruction::Dup2x2),
+            ["b", "c", "aL", "b", "c"]
+        );
+        assert_eq!(
+            shuffle(&["aL", "bL"], Instruction::Dup2x2),
+            ["bL", "aL", "bL"]
+        );
+        assert_eq!(shuffle(&["a", "b"], Instruction::Pop), ["a"]);
+        assert_eq!(shuffle(&["a", "b"], Instruction::Pop2), Vec::<&str>::new());
+        assert_eq!(shuffle(&["a", "bL"], Instruction::Pop2), ["a"]);
+        assert_eq!(shuffle(&["a", "b"], Instruction::Swap), ["b", "a"]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_shuffle_operand_stack_splitting_long() {
+        shuffle(&["a", "bL"], Instruction::Pop);
+    }
+}
@@ -0,0 +1,36 @@
+use crate::codegen::CodegenContext;
+use crate::Isolate;
+use inkwell::values::PointerValue;
+
+fn pending_exception_ptr<'ctx>(
+    ctx: &CodegenContext<'ctx>,
+    isolate_ptr: PointerValue<'ctx>,
+) -> PointerValue<'ctx> {
+    let offset = ctx
+        .i32_type
+        .const_int(Isolate::PENDING_EXCEPTION_OFFSET as u64, false);
+    unsafe {
+        ctx.builder
+            .build_gep(ctx.i8_type, isolate_ptr, &[offset], "pending_exception_ptr")
+    }
+}
+
+/// Loads the pending exception of the Isolate, which is null if nothing has been thrown.
+pub fn load_pending_exception<'ctx>(
+    ctx: &CodegenContext<'ctx>,
+    isolate_ptr: PointerValue<'ctx>,
+) -> PointerValue<'ctx> {
+    let ptr = pending_exception_ptr(ctx, isolate_ptr);
+    ctx.builder
+        .build_load(ctx.void_ptr, ptr, "pending_exception")
+        .into_pointer_value()
+}
+
+pub fn store_pending_exception<'ctx>(
+    ctx: &CodegenContext<'ctx>,
+    isolate_ptr: PointerValue<'ctx>,
+    exception: PointerValue<'ctx>,
+) {
+    let ptr = pending_exception_ptr(ctx, isolate_ptr);
+    ctx.builder.build_store(ptr, exception);
+}
@@ -0,0 +1,68 @@
+use crate::codegen::descriptor::{BaseType, FieldType};
+use crate::codegen::CodegenContext;
+use inkwell::types::{BasicType, BasicTypeEnum};
+use inkwell::values::{BasicValueEnum, PointerValue};
+use inkwell::AddressSpace;
+
+pub fn load_instance_field_ptr<'ctx>(
+    ctx: &mut CodegenContext<'ctx>,
+    class_name: &String,
+    obj_ptr: PointerValue<'ctx>,
+    field_name: &String,
+    field_type: BasicTypeEnum<'ctx>,
+) -> PointerValue<'ctx> {
+    let field_offset = ctx.get_instance_field_offset_value(class_name, field_name);
+    let ptr = unsafe {
+        ctx.builder.build_gep(
+            ctx.i8_type,
+            obj_ptr,
+            &[field_offset],
+            "instance_field_ptr_as_byte_ptr",
+        )
+    };
+    ctx.builder.build_pointer_cast(
+        ptr,
+        field_type.ptr_type(AddressSpace::default()),
+        "instance_field_ptr",
+    )
+}
+
+/// Narrows the int stored to the boolean, byte, char or short field, which keeps it as an int like the operand
+/// stack, and returns the other values as they are. Only the lowest bit is stored to booleans as per JVM spec (See
+/// putfield in 6.5).
+pub fn build_instance_field_value_narrowing<'ctx>(
+    ctx: &CodegenContext<'ctx>,
+    field_type: &FieldType,
+    value: BasicValueEnum<'ctx>,
+) -> BasicValueEnum<'ctx> {
+    let narrowed_type = match field_type {
+        FieldType::BaseType(BaseType::Boolean) => {
+            return ctx
+                .builder
+                .build_and(
+                    value.into_int_value(),
+                    ctx.i32_type.const_int(1, false),
+                    "bit",
+                )
+                .into();
+        }
+        FieldType::BaseType(BaseType::Byte) => ctx.i8_type,
+        FieldType::BaseType(BaseType::Char) | FieldType::BaseType(BaseType::Short) => {
+            ctx.context.i16_type()
+        }
+        _ => return value,
+    };
+    let narrowed =
+        ctx.builder
+            .build_int_truncate(value.into_int_value(), narrowed_type, "narrowed");
+    match field_type {
+        FieldType::BaseType(BaseType::Char) => ctx
+            .builder
+            .build_int_z_extend(narrowed, ctx.i32_type, "extended")
+            .into(),
+        _ => ctx
+            .builder
+            .build_int_s_extend(narrowed, ctx.i32_type, "extended")
+            .into(),
+    }
+}
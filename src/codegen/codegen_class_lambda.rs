@@ -0,0 +1,411 @@
+use crate::codegen::codegen_class_exceptions::load_pending_exception;
+use crate::codegen::codegen_class_instance_fields::load_instance_field_ptr;
+use crate::codegen::codegen_class_static_fields::build_class_initialization;
+use crate::codegen::descriptor::{parse_method_descriptor, BaseType, FieldType};
+use crate::codegen::{CodegenContext, INTERFACE_DISPATCH_PREFIX};
+use crate::compiled_class::{CompiledClass, VirtualMethodInfo};
+use inkwell::types::BasicTypeEnum;
+use inkwell::values::{BasicMetadataValueEnum, BasicValueEnum, IntValue, PointerValue};
+use inkwell::AddressSpace;
+
+/// The bootstrap method of the lambdas and the method references compiled by javac.
+pub const LAMBDA_METAFACTORY: &str = "java/lang/invoke/LambdaMetafactory.metafactory";
+
+// The kinds of the method handles as per JVM spec (See 5.4.3.5).
+pub const REF_INVOKE_VIRTUAL: u8 = 5;
+pub const REF_INVOKE_STATIC: u8 = 6;
+pub const REF_INVOKE_SPECIAL: u8 = 7;
+pub const REF_NEW_INVOKE_SPECIAL: u8 = 8;
+pub const REF_INVOKE_INTERFACE: u8 = 9;
+
+/// The method handle implementing the lambda, e.g. REF_invokeStatic "my/Class.lambda$main$0:(I)V".
+pub struct LambdaImplementation {
+    pub kind: u8,
+    pub class_name: String,
+    pub method_name: String,
+    pub descriptor: String,
+}
+
+/// The class synthesized for a call site of LambdaMetafactory.metafactory. It implements the functional
+/// interface, whose method invokes the implementation with the captured arguments followed by its own ones.
+pub struct LambdaClass {
+    pub class_name: String,
+    pub interface_name: String,
+    pub method_name: String,
+    /// The erased descriptor of the interface method, e.g. "(Ljava/lang/Object;Ljava/lang/Object;)I".
+    pub method_descriptor: String,
+    pub captured_types: Vec<FieldType>,
+    pub implementation: LambdaImplementation,
+}
+
+impl LambdaClass {
+    fn captured_field_name(i: usize) -> String {
+        // Named like the fields of the classes spun by the JDK.
+        format!("arg${}", i + 1)
+    }
+
+    pub fn as_class(&self) -> CompiledClass {
+        let mut c = CompiledClass::new(&self.class_name, Some("java/lang/Object".to_string()));
+        c.instance_fields = (0..self.captured_types.len())
+            .map(Self::captured_field_name)
+            .collect();
+        c.virtual_methods.push(VirtualMethodInfo {
+            symbol: self.method_symbol(),
+            ptr: None,
+            overrides: None,
+        });
+        c.interfaces.push(self.interface_name.clone());
+        c
+    }
+
+    /// Checks that the class can be built, returning the reason if not. The values are passed between the
+    /// interface method and the implementation only through the widening conversions, so the boxing and the
+    /// unboxing, e.g. of an erased Object parameter into an int one, are not supported.
+    pub fn check_supported(&self) -> Result<(), String> {
+        let implementation = &self.implementation;
+        if !matches!(
+            implementation.kind,
+            REF_INVOKE_VIRTUAL
+                | REF_INVOKE_STATIC
+                | REF_INVOKE_SPECIAL
+                | REF_NEW_INVOKE_SPECIAL
+                | REF_INVOKE_INTERFACE
+        ) {
+            return Err(format!("method handle kind {}", implementation.kind));
+        }
+        let method_type = parse_method_descriptor(&self.method_descriptor);
+        let implementation_type = parse_method_descriptor(&implementation.descriptor);
+        let mut parameter_types = Vec::new();
+        let mut return_type = implementation_type.return_type;
+        match implementation.kind {
+            REF_INVOKE_STATIC => {}
+            // The instance created for the constructor is returned.
+            REF_NEW_INVOKE_SPECIAL => {
+                return_type = Some(FieldType::ObjectType(implementation.class_name.clone()))
+            }
+            // The receiver is passed as the first argument.
+            _ => parameter_types.push(FieldType::ObjectType(implementation.class_name.clone())),
+        }
+        parameter_types.extend(implementation_type.parameter_types);
+
+        let arg_types = self
+            .captured_types
+            .iter()
+            .chain(method_type.parameter_types.iter());
+        for (from, to) in arg_types.zip(parameter_types.iter()) {
+            check_adaptable(from, to)?;
+        }
+        if let (Some(from), Some(to)) = (&return_type, &method_type.return_type) {
+            check_adaptable(from, to)?;
+        }
+        Ok(())
+    }
+
+    fn method_symbol(&self) -> String {
+        format!(
+            "{}.{}:{}",
+            self.class_name, self.method_name, self.method_descriptor
+        )
+    }
+
+    /// Builds the interface method of the class, which loads the captured arguments from the fields and
+    /// invokes the implementation. The position of the builder is restored afterwards.
+    pub fn build_method(&self, ctx: &mut CodegenContext) {
+        let current_block = ctx.builder.get_insert_block();
+        let method_type = parse_method_descriptor(&self.method_descriptor);
+        let fn_type = ctx.llvm_function_type_from_method_type(&method_type, false);
+        let function = ctx
+            .module
+            .add_function(&self.method_symbol(), fn_type, None);
+        let entry = ctx.context.append_basic_block(function, "entry");
+        ctx.builder.position_at_end(entry);
+
+        let isolate_ptr = function.get_nth_param(0).unwrap().into_pointer_value();
+        let this = function.get_nth_param(1).unwrap().into_pointer_value();
+        let mut args = Vec::new();
+        for (i, field_type) in self.captured_types.iter().enumerate() {
+            let typ: BasicTypeEnum = ctx.llvm_type_from_field_type(field_type);
+            let field_ptr = load_instance_field_ptr(
+                ctx,
+                &self.class_name,
+                this,
+                &Self::captured_field_name(i),
+                typ,
+            );
+            args.push(ctx.builder.build_load(typ, field_ptr, "captured"));
+        }
+        args.extend(function.get_param_iter().skip(2));
+
+        let ret = self.build_implementation_call(ctx, isolate_ptr, args);
+        match (fn_type.get_return_type(), ret) {
+            (Some(typ), Some(ret)) => {
+                let ret = adapt_value(ctx, ret, typ);
+                ctx.builder.build_return(Some(&ret));
+            }
+            _ => {
+                ctx.builder.build_return(None);
+            }
+        }
+
+        if let Some(block) = current_block {
+            ctx.builder.position_at_end(block);
+        }
+    }
+
+    fn build_implementation_call<'ctx>(
+        &self,
+        ctx: &mut CodegenContext<'ctx>,
+        isolate_ptr: PointerValue<'ctx>,
+        mut args: Vec<BasicValueEnum<'ctx>>,
+    ) -> Option<BasicValueEnum<'ctx>> {
+        let implementation = &self.implementation;
+        let symbol = format!(
+            "{}.{}:{}",
+            implementation.class_name, implementation.method_name, implementation.descriptor
+        );
+        let method_type = parse_method_descriptor(&implementation.descriptor);
+        let is_static = implementation.kind == REF_INVOKE_STATIC;
+        let fn_type = ctx.llvm_function_type_from_method_type(&method_type, is_static);
+
+        if matches!(
+            implementation.kind,
+            REF_INVOKE_STATIC | REF_NEW_INVOKE_SPECIAL
+        ) {
+            build_class_initialization_check(ctx, isolate_ptr, &implementation.class_name);
+        }
+
+        let mut new_obj = None;
+        if implementation.kind == REF_NEW_INVOKE_SPECIAL {
+            // Constructor references create the instance to be initialized.
+            let class_id = ctx.get_class_id_value(&implementation.class_name);
+            let obj = ctx
+                .builder
+                .build_call(
+                    ctx.new_instance_fn,
+                    &[isolate_ptr.into(), class_id.into()],
+                    "new_instance",
+                )
+                .try_as_basic_value()
+                .left()
+                .unwrap();
+            args.insert(0, obj);
+            new_obj = Some(obj);
+        }
+
+        let mut call_args: Vec<BasicMetadataValueEnum> = vec![isolate_ptr.into()];
+        for (arg, typ) in args.iter().zip(fn_type.get_param_types().iter().skip(1)) {
+            call_args.push(adapt_value(ctx, *arg, *typ).into());
+        }
+
+        let call = match implementation.kind {
+            REF_INVOKE_STATIC | REF_INVOKE_SPECIAL | REF_NEW_INVOKE_SPECIAL => {
+                let func = ctx
+                    .module
+                    .get_function(&symbol)
+                    .unwrap_or_else(|| ctx.module.add_function(&symbol, fn_type, None));
+                if is_static {
+                    ctx.invoked_static_method_symbols.insert(symbol);
+                } else {
+                    ctx.invoked_special_method_symbols.insert(symbol);
+                }
+                ctx.builder.build_call(func, &call_args, "call")
+            }
+            REF_INVOKE_VIRTUAL => {
+                let receiver = call_args[1].into_pointer_value();
+                build_receiver_null_check(ctx, isolate_ptr, receiver);
+                let vtable_ptr = ctx
+                    .builder
+                    .build_load(ctx.void_ptr, receiver, "vtable_ptr")
+                    .into_pointer_value();
+                let vtable_offset = ctx.get_virtual_method_offset_value(&symbol);
+                let func_ptr_ptr = unsafe {
+                    ctx.builder.build_gep(
+                        fn_type.ptr_type(AddressSpace::default()),
+                        vtable_ptr,
+                        &[vtable_offset],
+                        "func_ptr_ptr",
+                    )
+                };
+                let func_ptr = ctx
+                    .builder
+                    .build_load(ctx.void_ptr, func_ptr_ptr, &symbol)
+                    .into_pointer_value();
+                ctx.builder
+                    .build_indirect_call(fn_type, func_ptr, &call_args, "call")
+            }
+            REF_INVOKE_INTERFACE => {
+                let receiver = call_args[1].into_pointer_value();
+                build_receiver_null_check(ctx, isolate_ptr, receiver);
+                let dispatcher_symbol = format!("{}{}", INTERFACE_DISPATCH_PREFIX, symbol);
+                let dispatcher = ctx
+                    .module
+                    .get_function(&dispatcher_symbol)
+                    .unwrap_or_else(|| ctx.module.add_function(&dispatcher_symbol, fn_type, None));
+                ctx.invoked_interface_method_symbols.insert(symbol);
+                ctx.builder.build_call(dispatcher, &call_args, "call")
+            }
+            kind => unreachable!("method handle kind {}", kind),
+        };
+        new_obj.or(call.try_as_basic_value().left())
+    }
+
+    /// Builds the instance of the class capturing the arguments of the call site.
+    pub fn build_new_instance<'ctx>(
+        &self,
+        ctx: &mut CodegenContext<'ctx>,
+        isolate_ptr: PointerValue<'ctx>,
+        captured_args: &[BasicValueEnum<'ctx>],
+    ) -> PointerValue<'ctx> {
+        let class_id = ctx.get_class_id_value(&self.class_name);
+        let obj_ptr = ctx
+            .builder
+            .build_call(
+                ctx.new_instance_fn,
+                &[isolate_ptr.into(), class_id.into()],
+                "new_lambda",
+            )
+            .try_as_basic_value()
+            .left()
+            .unwrap()
+            .into_pointer_value();
+        for (i, arg) in captured_args.iter().enumerate() {
+            let field_ptr = load_instance_field_ptr(
+                ctx,
+                &self.class_name,
+                obj_ptr,
+                &Self::captured_field_name(i),
+                arg.get_type(),
+            );
+            ctx.builder.build_store(field_ptr, *arg);
+        }
+        obj_ptr
+    }
+}
+
+/// Throws NullPointerException if the receiver of the method reference is null. The exception is dispatched
+/// by the caller of the interface method once it returns.
+fn build_receiver_null_check<'ctx>(
+    ctx: &mut CodegenContext<'ctx>,
+    isolate_ptr: PointerValue<'ctx>,
+    receiver: PointerValue<'ctx>,
+) {
+    let is_null = ctx.builder.build_is_null(receiver, "is_null");
+    let throw_fn = ctx.throw_null_pointer_exception_fn;
+    build_return_if(ctx, is_null, |ctx| {
+        ctx.builder
+            .build_call(throw_fn, &[isolate_ptr.into()], "throw");
+    });
+}
+
+/// Initializes the class of the static method or the constructor on its first use, and returns if its
+/// static initializer has thrown an exception.
+fn build_class_initialization_check<'ctx>(
+    ctx: &mut CodegenContext<'ctx>,
+    isolate_ptr: PointerValue<'ctx>,
+    class_name: &String,
+) {
+    build_class_initialization(ctx, class_name, isolate_ptr);
+    let exception = load_pending_exception(ctx, isolate_ptr);
+    let thrown = ctx.builder.build_is_not_null(exception, "thrown");
+    build_return_if(ctx, thrown, |_| {});
+}
+
+/// Returns a zero value from the function being built if the condition holds, after building `before_return`.
+fn build_return_if<'ctx>(
+    ctx: &mut CodegenContext<'ctx>,
+    condition: IntValue<'ctx>,
+    before_return: impl FnOnce(&mut CodegenContext<'ctx>),
+) {
+    let function = ctx
+        .builder
+        .get_insert_block()
+        .unwrap()
+        .get_parent()
+        .unwrap();
+    let return_block = ctx.context.append_basic_block(function, "return");
+    let continue_block = ctx.context.append_basic_block(function, "continue");
+    ctx.builder
+        .build_conditional_branch(condition, return_block, continue_block);
+
+    ctx.builder.position_at_end(return_block);
+    before_return(ctx);
+    match function.get_type().get_return_type() {
+        Some(typ) => ctx.builder.build_return(Some(&typ.const_zero())),
+        None => ctx.builder.build_return(None),
+    };
+    ctx.builder.position_at_end(continue_block);
+}
+
+/// Returns an error if the value of the type can't be converted to the other one by adapt_value, i.e. unless
+/// the types are the same, both are references, or the primitive is widened (JLS 5.1.2).
+fn check_adaptable(from: &FieldType, to: &FieldType) -> Result<(), String> {
+    use BaseType::*;
+    let adaptable = match (from, to) {
+        (FieldType::BaseType(from), FieldType::BaseType(to)) => {
+            from == to
+                || matches!(
+                    (from, to),
+                    (Byte, Short | Int | Long | Float | Double)
+                        | (Short | Char, Int | Long | Float | Double)
+                        | (Int, Long | Float | Double)
+                        | (Long, Float | Double)
+                        | (Float, Double)
+                )
+        }
+        (FieldType::BaseType(_), _) | (_, FieldType::BaseType(_)) => false,
+        _ => true,
+    };
+    if !adaptable {
+        return Err(format!("converting {} to {}", from, to));
+    }
+    Ok(())
+}
+
+/// Converts the value between the types of the interface method and the implementation, i.e. widens the
+/// primitives. The references are passed as they are.
+fn adapt_value<'ctx>(
+    ctx: &CodegenContext<'ctx>,
+    value: BasicValueEnum<'ctx>,
+    typ: BasicTypeEnum<'ctx>,
+) -> BasicValueEnum<'ctx> {
+    if value.get_type() == typ {
+        return value;
+    }
+    match (value, typ) {
+        (BasicValueEnum::IntValue(v), BasicTypeEnum::IntType(t)) => ctx
+            .builder
+            .build_int_s_extend_or_bit_cast(v, t, "widened")
+            .into(),
+        (BasicValueEnum::IntValue(v), BasicTypeEnum::FloatType(t)) => ctx
+            .builder
+            .build_signed_int_to_float(v, t, "widened")
+            .into(),
+        (BasicValueEnum::FloatValue(v), BasicTypeEnum::FloatType(t)) => {
+            ctx.builder.build_float_ext(v, t, "widened").into()
+        }
+        // Rejected by LambdaClass::check_supported.
+        (v, t) => unreachable!("{:?} to {:?}", v, t),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_check_adaptable() {
+        let base = FieldType::BaseType;
+        let string = FieldType::ObjectType("java/lang/String".to_string());
+        assert!(check_adaptable(&base(BaseType::Int), &base(BaseType::Int)).is_ok());
+        assert!(check_adaptable(&base(BaseType::Int), &base(BaseType::Long)).is_ok());
+        assert!(check_adaptable(&base(BaseType::Char), &base(BaseType::Double)).is_ok());
+        assert!(check_adaptable(&base(BaseType::Float), &base(BaseType::Double)).is_ok());
+        assert!(check_adaptable(&string, &string).is_ok());
+        assert!(check_adaptable(&base(BaseType::Long), &base(BaseType::Int)).is_err());
+        assert!(check_adaptable(&base(BaseType::Double), &base(BaseType::Float)).is_err());
+        assert!(check_adaptable(&base(BaseType::Float), &base(BaseType::Long)).is_err());
+        assert!(check_adaptable(&base(BaseType::Byte), &base(BaseType::Char)).is_err());
+        assert!(check_adaptable(&base(BaseType::Boolean), &base(BaseType::Int)).is_err());
+        assert!(check_adaptable(&base(BaseType::Int), &string).is_err());
+    }
+}
@@ -0,0 +1,223 @@
+use crate::codegen::descriptor::{BaseType, FieldType};
+use classfile_parser::attribute_info::{StackMapFrame, VerificationTypeInfo};
+
+/// A frame of the StackMapTable attribute (See 4.7.4) expanded with its offset in the code. The locals are listed
+/// as in the frames, where longs and doubles take a single entry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackMapFrameEntry<T> {
+    pub offset: usize,
+    pub locals: Vec<T>,
+    pub stack: Vec<T>,
+}
+
+/// Expands the delta-encoded frames into the full frames. `initial_locals` are the types of the parameters,
+/// including this, which form the implicit initial frame. `resolve` converts the verification types to the types
+/// of the caller, e.g. the verifier's or the compiler's ones.
+pub fn expand_stack_map_frames<T: Clone>(
+    frames: &[StackMapFrame],
+    initial_locals: Vec<T>,
+    mut resolve: impl FnMut(&VerificationTypeInfo) -> Result<T, String>,
+) -> Result<Vec<StackMapFrameEntry<T>>, String> {
+    let mut locals = initial_locals;
+    let mut expanded = Vec::with_capacity(frames.len());
+    let mut offset: Option<usize> = None;
+    for frame in frames {
+        let (offset_delta, stack) = match frame {
+            StackMapFrame::SameFrame { frame_type } => (*frame_type as usize, vec![]),
+            StackMapFrame::SameLocals1StackItemFrame { frame_type, stack } => {
+                (*frame_type as usize - 64, vec![resolve(stack)?])
+            }
+            StackMapFrame::SameLocals1StackItemFrameExtended {
+                offset_delta,
+                stack,
+                ..
+            } => (*offset_delta as usize, vec![resolve(stack)?]),
+            StackMapFrame::ChopFrame {
+                frame_type,
+                offset_delta,
+            } => {
+                let chopped = 251 - *frame_type as usize;
+                if chopped > locals.len() {
+                    return Err("stack map frame chops too many locals".to_string());
+                }
+                locals.truncate(locals.len() - chopped);
+                (*offset_delta as usize, vec![])
+            }
+            StackMapFrame::SameFrameExtended { offset_delta, .. } => {
+                (*offset_delta as usize, vec![])
+            }
+            StackMapFrame::AppendFrame {
+                offset_delta,
+                locals: appended,
+                ..
+            } => {
+                for v in appended {
+                    locals.push(resolve(v)?);
+                }
+                (*offset_delta as usize, vec![])
+            }
+            StackMapFrame::FullFrame {
+                offset_delta,
+                locals: full,
+                stack,
+                ..
+            } => {
+                locals = full.iter().map(&mut resolve).collect::<Result<_, _>>()?;
+                let stack = stack.iter().map(&mut resolve).collect::<Result<_, _>>()?;
+                (*offset_delta as usize, stack)
+            }
+        };
+        // The offset of the first frame is the delta itself, and the others are one past the delta from the
+        // previous frame.
+        let current = match offset {
+            Some(previous) => previous + offset_delta + 1,
+            None => offset_delta,
+        };
+        offset = Some(current);
+        expanded.push(StackMapFrameEntry {
+            offset: current,
+            locals: locals.clone(),
+            stack,
+        });
+    }
+    Ok(expanded)
+}
+
+/// The types of the locals and the operand stack at the beginning of an instruction, as given by a frame of the
+/// StackMapTable attribute. The locals are indexed by slot, where the second slot of longs and doubles and the
+/// unusable ones (top) are None.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameTypes {
+    pub locals: Vec<Option<FieldType>>,
+    pub stack: Vec<FieldType>,
+}
+
+impl FrameTypes {
+    /// Lays out the locals of the frame, whose top is None, by slot.
+    pub fn new(entry: StackMapFrameEntry<Option<FieldType>>) -> Self {
+        let mut slots = Vec::with_capacity(entry.locals.len());
+        for local in entry.locals {
+            let is_wide = matches!(
+                local,
+                Some(FieldType::BaseType(BaseType::Long))
+                    | Some(FieldType::BaseType(BaseType::Double))
+            );
+            slots.push(local);
+            if is_wide {
+                slots.push(None);
+            }
+        }
+        let stack = entry
+            .stack
+            .into_iter()
+            .map(|v| v.expect("top on the operand stack"))
+            .collect();
+        FrameTypes {
+            locals: slots,
+            stack,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const INT: FieldType = FieldType::BaseType(BaseType::Int);
+    const LONG: FieldType = FieldType::BaseType(BaseType::Long);
+
+    fn resolve(v: &VerificationTypeInfo) -> Result<Option<FieldType>, String> {
+        Ok(match v {
+            VerificationTypeInfo::Top => None,
+            VerificationTypeInfo::Integer => Some(INT),
+            VerificationTypeInfo::Long => Some(LONG),
+            VerificationTypeInfo::Object { class } => {
+                Some(FieldType::ObjectType(class.to_string()))
+            }
+            v => unreachable!("{:?}", v),
+        })
+    }
+
+    #[test]
+    fn test_expand_stack_map_frames() {
+        let string = FieldType::ObjectType("7".to_string());
+        let frames = vec![
+            StackMapFrame::AppendFrame {
+                frame_type: 253,
+                offset_delta: 4,
+                locals: vec![VerificationTypeInfo::Long, VerificationTypeInfo::Integer],
+            },
+            StackMapFrame::SameLocals1StackItemFrame {
+                frame_type: 64 + 2,
+                stack: VerificationTypeInfo::Object { class: 7 },
+            },
+            StackMapFrame::ChopFrame {
+                frame_type: 250,
+                offset_delta: 10,
+            },
+            StackMapFrame::FullFrame {
+                frame_type: 255,
+                offset_delta: 0,
+                number_of_locals: 2,
+                locals: vec![VerificationTypeInfo::Top, VerificationTypeInfo::Integer],
+                number_of_stack_items: 1,
+                stack: vec![VerificationTypeInfo::Integer],
+            },
+            StackMapFrame::SameFrame { frame_type: 3 },
+        ];
+        let expanded = expand_stack_map_frames(&frames, vec![Some(INT)], resolve)
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.offset, FrameTypes::new(entry)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            expanded,
+            vec![
+                (
+                    4,
+                    FrameTypes {
+                        locals: vec![Some(INT), Some(LONG), None, Some(INT)],
+                        stack: vec![],
+                    }
+                ),
+                (
+                    7,
+                    FrameTypes {
+                        locals: vec![Some(INT), Some(LONG), None, Some(INT)],
+                        stack: vec![string],
+                    }
+                ),
+                (
+                    18,
+                    FrameTypes {
+                        locals: vec![Some(INT), Some(LONG), None],
+                        stack: vec![],
+                    }
+                ),
+                (
+                    19,
+                    FrameTypes {
+                        locals: vec![None, Some(INT)],
+                        stack: vec![INT],
+                    }
+                ),
+                (
+                    23,
+                    FrameTypes {
+                        locals: vec![None, Some(INT)],
+                        stack: vec![],
+                    }
+                ),
+            ]
+        );
+
+        let chop = [StackMapFrame::ChopFrame {
+            frame_type: 249,
+            offset_delta: 0,
+        }];
+        assert_eq!(
+            expand_stack_map_frames(&chop, vec![Some(INT)], resolve),
+            Err("stack map frame chops too many locals".to_string())
+        );
+    }
+}
@@ -1,8 +1,91 @@
+use crate::codegen::descriptor::{BaseType, FieldType};
 use crate::codegen::CodegenContext;
 use inkwell::types::{BasicType, BasicTypeEnum};
-use inkwell::values::{AnyValue, PointerValue};
+use inkwell::values::{AnyValue, BasicValueEnum, IntValue, PointerValue};
 use inkwell::AddressSpace;
 
+/// Returns the type of the static field in the storage of the class object, where booleans and bytes take 1 byte
+/// and chars and shorts take 2 bytes, unlike on the operand stack.
+pub fn static_field_storage_type<'ctx>(
+    ctx: &CodegenContext<'ctx>,
+    field_type: &FieldType,
+) -> BasicTypeEnum<'ctx> {
+    match field_type {
+        FieldType::ObjectType(_) | FieldType::ArrayType(_) => ctx.void_ptr.into(),
+        FieldType::BaseType(BaseType::Boolean) | FieldType::BaseType(BaseType::Byte) => {
+            ctx.i8_type.into()
+        }
+        FieldType::BaseType(BaseType::Short) | FieldType::BaseType(BaseType::Char) => {
+            ctx.context.i16_type().into()
+        }
+        FieldType::BaseType(BaseType::Int) => ctx.i32_type.into(),
+        FieldType::BaseType(BaseType::Long) => ctx.i64_type.into(),
+        FieldType::BaseType(BaseType::Float) => ctx.f32_type.into(),
+        FieldType::BaseType(BaseType::Double) => ctx.f64_type.into(),
+        field_type => unreachable!("{:?}", field_type),
+    }
+}
+
+/// Loads the static field, extending booleans, bytes, chars and shorts to ints.
+pub fn build_static_field_load<'ctx>(
+    ctx: &mut CodegenContext<'ctx>,
+    class_name: &String,
+    isolate_ptr: PointerValue<'ctx>,
+    field_name: &String,
+    field_type: &FieldType,
+) -> BasicValueEnum<'ctx> {
+    let typ = static_field_storage_type(ctx, field_type);
+    let field_ptr = load_class_obj_static_field_ptr(ctx, class_name, isolate_ptr, field_name, typ);
+    let loaded = ctx.builder.build_load(typ, field_ptr, field_name);
+    match field_type {
+        FieldType::BaseType(BaseType::Boolean) | FieldType::BaseType(BaseType::Char) => ctx
+            .builder
+            .build_int_z_extend(loaded.into_int_value(), ctx.i32_type, field_name)
+            .into(),
+        FieldType::BaseType(BaseType::Byte) | FieldType::BaseType(BaseType::Short) => ctx
+            .builder
+            .build_int_s_extend(loaded.into_int_value(), ctx.i32_type, field_name)
+            .into(),
+        _ => loaded,
+    }
+}
+
+/// Stores the value to the static field, narrowing ints to booleans, bytes, chars and shorts.
+pub fn build_static_field_store<'ctx>(
+    ctx: &mut CodegenContext<'ctx>,
+    class_name: &String,
+    isolate_ptr: PointerValue<'ctx>,
+    field_name: &String,
+    field_type: &FieldType,
+    value: BasicValueEnum<'ctx>,
+) {
+    let typ = static_field_storage_type(ctx, field_type);
+    let value = match field_type {
+        // Only the lowest bit is stored to booleans as per JVM spec (See putstatic in 6.5).
+        FieldType::BaseType(BaseType::Boolean) => {
+            let bit = ctx.builder.build_and(
+                value.into_int_value(),
+                ctx.i32_type.const_int(1, false),
+                "bit",
+            );
+            ctx.builder
+                .build_int_truncate(bit, typ.into_int_type(), "narrowed")
+                .into()
+        }
+        FieldType::BaseType(BaseType::Byte)
+        | FieldType::BaseType(BaseType::Short)
+        | FieldType::BaseType(BaseType::Char) => ctx
+            .builder
+            .build_int_truncate(value.into_int_value(), typ.into_int_type(), "narrowed")
+            .into(),
+        _ => value,
+    };
+    let field_ptr = load_class_obj_static_field_ptr(ctx, class_name, isolate_ptr, field_name, typ);
+    ctx.builder.build_store(field_ptr, value);
+}
+
+/// Returns the pointer to the static field of the class, which must be initialized by
+/// `build_class_initialization` beforehand.
 pub fn load_class_obj_static_field_ptr<'ctx>(
     ctx: &mut CodegenContext<'ctx>,
     class_name: &String,
@@ -10,7 +93,8 @@ pub fn load_class_obj_static_field_ptr<'ctx>(
     field_name: &String,
     field_type: BasicTypeEnum<'ctx>,
 ) -> PointerValue<'ctx> {
-    let class_obj_ptr = load_class_obj_ptr(ctx, class_name, isolate_ptr);
+    let class_id = ctx.get_static_field_class_id_value(class_name, field_name);
+    let class_obj_ptr = load_class_obj_ptr(ctx, class_id, isolate_ptr, false);
 
     let field_offset = ctx.get_static_filed_offset_value(&class_name, &field_name);
     let ptr = unsafe {
@@ -34,12 +118,38 @@ pub fn load_class_obj_static_field_ptr<'ctx>(
     ptr
 }
 
-fn load_class_obj_ptr<'ctx>(
+/// Runs the static initializer of the class if it hasn't been run yet, which may leave an exception pending.
+pub fn build_class_initialization<'ctx>(
     ctx: &mut CodegenContext<'ctx>,
     class_name: &String,
     isolate_ptr: PointerValue<'ctx>,
+) {
+    let class_id = ctx.get_class_id_value(class_name);
+    load_class_obj_ptr(ctx, class_id, isolate_ptr, true);
+}
+
+/// Same as `build_class_initialization`, but for the class declaring the static field, which can be an ancestor
+/// of the class named in getstatic or putstatic.
+pub fn build_static_field_class_initialization<'ctx>(
+    ctx: &mut CodegenContext<'ctx>,
+    class_name: &String,
+    field_name: &String,
+    isolate_ptr: PointerValue<'ctx>,
+) {
+    let class_id = ctx.get_static_field_class_id_value(class_name, field_name);
+    load_class_obj_ptr(ctx, class_id, isolate_ptr, true);
+}
+
+fn load_class_obj_ptr<'ctx>(
+    ctx: &mut CodegenContext<'ctx>,
+    class_id: IntValue<'ctx>,
+    isolate_ptr: PointerValue<'ctx>,
+    need_initialization: bool,
 ) -> PointerValue<'ctx> {
-    let class_id = ctx.get_class_id_value(&class_name);
+    let need_initialization = ctx
+        .context
+        .bool_type()
+        .const_int(need_initialization as u64, false);
     let ptr = ctx
         .builder
         .build_call(
@@ -47,7 +157,7 @@ fn load_class_obj_ptr<'ctx>(
             &[
                 isolate_ptr.into(),
                 class_id.into(),
-                ctx.context.bool_type().const_zero().into(),
+                need_initialization.into(),
             ],
             "get_class_object",
         )
@@ -0,0 +1,115 @@
+use crate::codegen::descriptor::{BaseType, FieldType};
+use crate::codegen::CodegenContext;
+use inkwell::values::{BasicValueEnum, PointerValue};
+
+/// The bootstrap method of the string concatenations compiled by javac 9+.
+pub const MAKE_CONCAT_WITH_CONSTANTS: &str =
+    "java/lang/invoke/StringConcatFactory.makeConcatWithConstants";
+
+/// Builds the call to the native concatenation of the arguments along the recipe of makeConcatWithConstants,
+/// where each \u{1} is replaced with the next argument and each \u{2} with the next constant. The arguments are
+/// passed as 64 bit slots, their types as the characters of their descriptors, e.g. "IJL" for (int, long, Object),
+/// and the constants as the pointers to the string constants.
+pub fn build_string_concat<'ctx>(
+    ctx: &mut CodegenContext<'ctx>,
+    isolate_ptr: PointerValue<'ctx>,
+    recipe: &String,
+    constants: &[String],
+    arg_types: &[FieldType],
+    args: &[BasicValueEnum<'ctx>],
+) -> PointerValue<'ctx> {
+    let slots_type = ctx.i64_type.array_type(args.len().max(1) as u32);
+    let slots_ptr = ctx.build_entry_alloca(slots_type, "concat_args");
+    for (i, arg) in args.iter().enumerate() {
+        let slot = match arg {
+            BasicValueEnum::IntValue(v) if v.get_type() == ctx.i64_type => *v,
+            BasicValueEnum::IntValue(v) => {
+                ctx.builder
+                    .build_int_s_extend(*v, ctx.i64_type, "concat_arg")
+            }
+            BasicValueEnum::FloatValue(v) if v.get_type() == ctx.f64_type => ctx
+                .builder
+                .build_bitcast(*v, ctx.i64_type, "concat_arg")
+                .into_int_value(),
+            BasicValueEnum::FloatValue(v) => {
+                let bits = ctx
+                    .builder
+                    .build_bitcast(*v, ctx.i32_type, "concat_arg_bits")
+                    .into_int_value();
+                ctx.builder
+                    .build_int_z_extend(bits, ctx.i64_type, "concat_arg")
+            }
+            BasicValueEnum::PointerValue(v) => {
+                ctx.builder.build_ptr_to_int(*v, ctx.i64_type, "concat_arg")
+            }
+            v => unreachable!("{:?}", v),
+        };
+        let slot_ptr = unsafe {
+            ctx.builder.build_gep(
+                slots_type,
+                slots_ptr,
+                &[
+                    ctx.i32_type.const_zero(),
+                    ctx.i32_type.const_int(i as u64, false),
+                ],
+                "concat_arg_ptr",
+            )
+        };
+        ctx.builder.build_store(slot_ptr, slot);
+    }
+
+    let constants_type = ctx.void_ptr.array_type(constants.len().max(1) as u32);
+    let constants_ptr = ctx.build_entry_alloca(constants_type, "concat_constants");
+    for (i, constant) in constants.iter().enumerate() {
+        let constant_ptr = unsafe {
+            ctx.builder.build_gep(
+                constants_type,
+                constants_ptr,
+                &[
+                    ctx.i32_type.const_zero(),
+                    ctx.i32_type.const_int(i as u64, false),
+                ],
+                "concat_constant_ptr",
+            )
+        };
+        ctx.builder
+            .build_store(constant_ptr, ctx.get_const_string_global(constant));
+    }
+
+    let types = arg_types.iter().map(type_char).collect::<String>();
+    let recipe_ptr = ctx.get_const_string_global(recipe);
+    let types_ptr = ctx.get_const_string_global(&types);
+    ctx.builder
+        .build_call(
+            ctx.string_concat_fn,
+            &[
+                isolate_ptr.into(),
+                recipe_ptr.into(),
+                types_ptr.into(),
+                slots_ptr.into(),
+                constants_ptr.into(),
+            ],
+            "concat",
+        )
+        .try_as_basic_value()
+        .left()
+        .unwrap()
+        .into_pointer_value()
+}
+
+/// Returns the type of the argument as seen by the native concatenation.
+fn type_char(field_type: &FieldType) -> char {
+    match field_type {
+        FieldType::BaseType(BaseType::Boolean) => 'Z',
+        FieldType::BaseType(BaseType::Byte) => 'B',
+        FieldType::BaseType(BaseType::Char) => 'C',
+        FieldType::BaseType(BaseType::Short) => 'S',
+        FieldType::BaseType(BaseType::Int) => 'I',
+        FieldType::BaseType(BaseType::Long) => 'J',
+        FieldType::BaseType(BaseType::Float) => 'F',
+        FieldType::BaseType(BaseType::Double) => 'D',
+        FieldType::BaseType(BaseType::Void) => unreachable!(),
+        // The references, including the boxed primitives, which are converted by their toString().
+        _ => 'L',
+    }
+}
@@ -1,13 +1,17 @@
 use crate::codegen::descriptor::{BaseType, FieldType, MethodType};
+use crate::compiled_class::CompiledClass;
+use crate::stdlib::array::component_class_name;
 use inkwell::builder::Builder;
 use inkwell::context::Context;
 use inkwell::execution_engine::ExecutionEngine;
 use inkwell::module::Linkage::External;
 use inkwell::module::{Linkage, Module};
-use inkwell::types::{BasicTypeEnum, FloatType, FunctionType, IntType, PointerType, StructType};
+use inkwell::types::{
+    BasicType, BasicTypeEnum, FloatType, FunctionType, IntType, PointerType, StructType,
+};
 use inkwell::values::{BasicValueEnum, FunctionValue, GlobalValue, IntValue, PointerValue};
 use inkwell::{AddressSpace, OptimizationLevel};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 pub struct CodegenContext<'ctx> {
     pub context: &'ctx Context,
@@ -39,6 +43,14 @@ pub struct CodegenContext<'ctx> {
     pub get_class_object_fn: FunctionValue<'ctx>,
     pub new_class_object_fn: FunctionValue<'ctx>,
     pub new_instance_fn: FunctionValue<'ctx>,
+    pub is_instance_of_fn: FunctionValue<'ctx>,
+    pub check_cast_fn: FunctionValue<'ctx>,
+    pub class_id_of_fn: FunctionValue<'ctx>,
+    pub throw_interface_method_error_fn: FunctionValue<'ctx>,
+    pub throw_null_pointer_exception_fn: FunctionValue<'ctx>,
+    pub throw_array_index_out_of_bounds_exception_fn: FunctionValue<'ctx>,
+    pub throw_negative_array_size_exception_fn: FunctionValue<'ctx>,
+    pub throw_arithmetic_exception_fn: FunctionValue<'ctx>,
     pub new_java_array_fn: FunctionValue<'ctx>,
     pub new_boolean_array_fn: FunctionValue<'ctx>,
     pub new_byte_array_fn: FunctionValue<'ctx>,
@@ -48,14 +60,35 @@ pub struct CodegenContext<'ctx> {
     pub new_long_array_fn: FunctionValue<'ctx>,
     pub new_float_array_fn: FunctionValue<'ctx>,
     pub new_double_array_fn: FunctionValue<'ctx>,
+    pub new_multi_array_fn: FunctionValue<'ctx>,
+    pub check_array_store_fn: FunctionValue<'ctx>,
+    pub string_concat_fn: FunctionValue<'ctx>,
 
     /// holds values corresponding to  the class_id of each class, which will be resolved at the very last phase
     /// of compilation.
     pub class_id_values: HashMap<String, Vec<IntValue<'ctx>>>,
     /// holds values corresponding to the static field offset of each class, which will be resolved at the very last phase
     pub static_field_offset_values: HashMap<String, Vec<IntValue<'ctx>>>,
+    /// holds values corresponding to the class_id of the class declaring each static field, which can be an ancestor
+    /// of the class named in the instruction, and will be resolved at the very last phase
+    pub static_field_class_id_values: HashMap<String, Vec<IntValue<'ctx>>>,
+    /// holds values corresponding to the instance field offset of each class, which will be resolved at the very last phase
+    pub instance_field_offset_values: HashMap<String, Vec<IntValue<'ctx>>>,
     /// holds values corresponding to the virtual method offset of each method in a vtable, which will be resolved at the very last phase
     pub virtual_method_offset_values: HashMap<String, Vec<IntValue<'ctx>>>,
+    /// holds the symbols of the invoked static methods, which can be inherited from the ancestors of the class
+    /// and will be resolved at the very last phase
+    pub invoked_static_method_symbols: HashSet<String>,
+    /// holds the symbols of the methods invoked by invokespecial, which can be inherited from the ancestors of the class
+    pub invoked_special_method_symbols: HashSet<String>,
+    /// holds the symbols of the methods invoked by invokeinterface, whose dispatchers are built at the very last phase
+    pub invoked_interface_method_symbols: HashSet<String>,
+    /// holds the names of the reference array classes used by the compiled code, e.g. "[[I", which are defined
+    /// at the last phase
+    pub array_class_names: HashSet<String>,
+    /// holds the classes synthesized for the lambdas of the class being compiled, which are taken by CodeGen
+    /// once the class is compiled
+    pub lambda_classes: Vec<CompiledClass>,
 }
 
 impl<'ctx> CodegenContext<'ctx> {
@@ -184,6 +217,94 @@ impl<'ctx> CodegenContext<'ctx> {
             module.add_function("__yajvm_new_instance", new_instance_fn_type, Some(External))
         };
 
+        let is_instance_of_fn = {
+            let is_instance_of_fn_type = context.bool_type().fn_type(
+                &[
+                    void_ptr.into(), // isolate
+                    void_ptr.into(), // object
+                    i32_type.into(), // class_id
+                ],
+                false,
+            );
+            module.add_function(
+                "__yajvm_is_instance_of",
+                is_instance_of_fn_type,
+                Some(External),
+            )
+        };
+
+        let check_cast_fn = module.add_function(
+            "__yajvm_check_cast",
+            context.void_type().fn_type(
+                &[
+                    void_ptr.into(), // isolate
+                    void_ptr.into(), // object
+                    i32_type.into(), // class_id
+                ],
+                false,
+            ),
+            Some(External),
+        );
+
+        let class_id_of_fn = module.add_function(
+            "__yajvm_class_id_of",
+            i32_type.fn_type(
+                &[
+                    void_ptr.into(), // isolate
+                    void_ptr.into(), // object
+                ],
+                false,
+            ),
+            Some(External),
+        );
+        let throw_interface_method_error_fn = module.add_function(
+            "__yajvm_throw_interface_method_error",
+            context.void_type().fn_type(
+                &[
+                    void_ptr.into(), // isolate
+                    void_ptr.into(), // object
+                    i32_type.into(), // class_id of the interface
+                    void_ptr.into(), // method as a C string, e.g. "abstract void run()"
+                ],
+                false,
+            ),
+            Some(External),
+        );
+
+        let throw_null_pointer_exception_fn = module.add_function(
+            "__yajvm_throw_null_pointer_exception",
+            context.void_type().fn_type(&[void_ptr.into()], false),
+            Some(External),
+        );
+        let throw_array_index_out_of_bounds_exception_fn = module.add_function(
+            "__yajvm_throw_array_index_out_of_bounds_exception",
+            context.void_type().fn_type(
+                &[
+                    void_ptr.into(), // isolate
+                    i32_type.into(), // index
+                    i32_type.into(), // length
+                ],
+                false,
+            ),
+            Some(External),
+        );
+        let throw_negative_array_size_exception_fn = module.add_function(
+            "__yajvm_throw_negative_array_size_exception",
+            context.void_type().fn_type(
+                &[
+                    void_ptr.into(), // isolate
+                    i32_type.into(), // size
+                ],
+                false,
+            ),
+            Some(External),
+        );
+        let throw_arithmetic_exception_fn = module.add_function(
+            "__yajvm_throw_arithmetic_exception",
+            context.void_type().fn_type(&[void_ptr.into()], false),
+            Some(External),
+        );
+
         let new_array_type = void_ptr.fn_type(
             &[
                 void_ptr.into(), // isolate
@@ -192,8 +313,18 @@ impl<'ctx> CodegenContext<'ctx> {
             false,
         );
 
-        let new_java_array_fn =
-            module.add_function("__yajvm_new_java_array", new_array_type, Some(External));
+        let new_java_array_fn = module.add_function(
+            "__yajvm_new_java_array",
+            void_ptr.fn_type(
+                &[
+                    void_ptr.into(), // isolate
+                    i32_type.into(), // class_id
+                    i32_type.into(), // length
+                ],
+                false,
+            ),
+            Some(External),
+        );
         let new_boolean_array_fn =
             module.add_function("__yajvm_new_boolean_array", new_array_type, Some(External));
         let new_byte_array_fn =
@@ -210,6 +341,45 @@ impl<'ctx> CodegenContext<'ctx> {
             module.add_function("__yajvm_new_float_array", new_array_type, Some(External));
         let new_double_array_fn =
             module.add_function("__yajvm_new_double_array", new_array_type, Some(External));
+        let new_multi_array_fn = module.add_function(
+            "__yajvm_new_multi_array",
+            void_ptr.fn_type(
+                &[
+                    void_ptr.into(), // isolate
+                    i32_type.into(), // class_id
+                    void_ptr.into(), // dimensions
+                    i32_type.into(), // dimension count
+                ],
+                false,
+            ),
+            Some(External),
+        );
+        let string_concat_fn = module.add_function(
+            "__yajvm_string_concat",
+            void_ptr.fn_type(
+                &[
+                    void_ptr.into(), // isolate
+                    void_ptr.into(), // recipe
+                    void_ptr.into(), // types
+                    void_ptr.into(), // args
+                    void_ptr.into(), // constants
+                ],
+                false,
+            ),
+            Some(External),
+        );
+        let check_array_store_fn = module.add_function(
+            "__yajvm_check_array_store",
+            context.void_type().fn_type(
+                &[
+                    void_ptr.into(), // isolate
+                    void_ptr.into(), // array
+                    void_ptr.into(), // value
+                ],
+                false,
+            ),
+            Some(External),
+        );
 
         Self {
             context,
@@ -238,6 +408,14 @@ impl<'ctx> CodegenContext<'ctx> {
             new_class_object_fn,
             get_class_object_fn,
             new_instance_fn,
+            is_instance_of_fn,
+            check_cast_fn,
+            class_id_of_fn,
+            throw_interface_method_error_fn,
+            throw_null_pointer_exception_fn,
+            throw_array_index_out_of_bounds_exception_fn,
+            throw_negative_array_size_exception_fn,
+            throw_arithmetic_exception_fn,
             new_java_array_fn,
             new_boolean_array_fn,
             new_byte_array_fn,
@@ -247,14 +425,41 @@ impl<'ctx> CodegenContext<'ctx> {
             new_long_array_fn,
             new_float_array_fn,
             new_double_array_fn,
+            new_multi_array_fn,
+            check_array_store_fn,
+            string_concat_fn,
             class_id_values: HashMap::default(),
             static_field_offset_values: HashMap::default(),
+            static_field_class_id_values: HashMap::default(),
+            instance_field_offset_values: HashMap::default(),
             virtual_method_offset_values: HashMap::default(),
+            invoked_static_method_symbols: HashSet::default(),
+            invoked_special_method_symbols: HashSet::default(),
+            invoked_interface_method_symbols: HashSet::default(),
+            array_class_names: HashSet::default(),
+            lambda_classes: Vec::default(),
         }
     }
 }
 
 impl<'ctx> CodegenContext<'ctx> {
+    /// Allocates the stack slot in the entry block of the function being built, so that it's allocated once per
+    /// call even if the instruction using it runs in a loop.
+    pub fn build_entry_alloca<T: BasicType<'ctx>>(&self, typ: T, name: &str) -> PointerValue<'ctx> {
+        let function = self
+            .builder
+            .get_insert_block()
+            .and_then(|block| block.get_parent())
+            .unwrap();
+        let entry_block = function.get_first_basic_block().unwrap();
+        let builder = self.context.create_builder();
+        match entry_block.get_first_instruction() {
+            Some(instruction) => builder.position_before(&instruction),
+            None => builder.position_at_end(entry_block),
+        }
+        builder.build_alloca(typ, name)
+    }
+
     pub fn get_or_add_global(&self, symbol: &str, ty: BasicTypeEnum<'ctx>) -> GlobalValue<'ctx> {
         if let Some(g) = self.module.get_global(symbol) {
             g
@@ -290,11 +495,17 @@ impl<'ctx> CodegenContext<'ctx> {
                 }
             };
 
+            // The vtable is forward-declared until it is constructed, after which the strings (e.g. the ones of
+            // ConstantValue attributes) refer to it directly.
             let java_lang_string_vtable = self
-                .get_or_add_global(
-                    "forward_declared_vtable###java/lang/String",
-                    self.void_ptr.array_type(0).into(),
-                )
+                .module
+                .get_global("vtable###java/lang/String")
+                .unwrap_or_else(|| {
+                    self.get_or_add_global(
+                        "forward_declared_vtable###java/lang/String",
+                        self.void_ptr.array_type(0).into(),
+                    )
+                })
                 .as_pointer_value();
 
             let const_str_obj = {
@@ -355,16 +566,8 @@ impl<'ctx> CodegenContext<'ctx> {
                     BaseType::Short => self.i32_type.fn_type(&param_types, false),
                     BaseType::Void => self.context.void_type().fn_type(&param_types, false),
                 },
-                FieldType::ObjectType(_) => self.void_ptr.fn_type(&param_types, false),
-                FieldType::ArrayType(_) => self.void_ptr.fn_type(&param_types, false),
-                FieldType::ObjectTypeJavaLangByte => self.i32_type.fn_type(&param_types, false),
-                FieldType::ObjectTypeJavaLangChar => self.i32_type.fn_type(&param_types, false),
-                FieldType::ObjectTypeJavaLangDouble => self.f64_type.fn_type(&param_types, false),
-                FieldType::ObjectTypeJavaLangFloat => self.f32_type.fn_type(&param_types, false),
-                FieldType::ObjectTypeJavaLangInteger => self.i32_type.fn_type(&param_types, false),
-                FieldType::ObjectTypeJavaLangLong => self.i64_type.fn_type(&param_types, false),
-                FieldType::ObjectTypeJavaLangShort => self.i32_type.fn_type(&param_types, false),
-                FieldType::ObjectTypeJavaLangBoolean => self.i32_type.fn_type(&param_types, false),
+                // The references, including the boxed primitives.
+                _ => self.void_ptr.fn_type(&param_types, false),
             }
         } else {
             self.context.void_type().fn_type(&param_types, false)
@@ -396,16 +599,8 @@ impl<'ctx> CodegenContext<'ctx> {
                 BaseType::Short => self.i32_type.into(),
                 _ => unreachable!(),
             },
-            FieldType::ObjectType(_) => self.void_ptr.into(),
-            FieldType::ArrayType(_) => self.void_ptr.into(),
-            FieldType::ObjectTypeJavaLangByte => self.i32_type.into(),
-            FieldType::ObjectTypeJavaLangChar => self.i32_type.into(),
-            FieldType::ObjectTypeJavaLangDouble => self.f64_type.into(),
-            FieldType::ObjectTypeJavaLangFloat => self.f32_type.into(),
-            FieldType::ObjectTypeJavaLangInteger => self.i32_type.into(),
-            FieldType::ObjectTypeJavaLangLong => self.i64_type.into(),
-            FieldType::ObjectTypeJavaLangShort => self.i32_type.into(),
-            FieldType::ObjectTypeJavaLangBoolean => self.i32_type.into(),
+            // The references, including the boxed primitives.
+            _ => self.void_ptr.into(),
         }
     }
 
@@ -451,7 +646,52 @@ impl<'ctx> CodegenContext<'ctx> {
         dummy_value // Returned value will be replaced by the real number at the last phase of compilation.
     }
 
+    pub fn get_static_field_class_id_value(
+        &mut self,
+        class_name: &String,
+        field_name: &String,
+    ) -> IntValue<'ctx> {
+        let dummy_value = self
+            .insert_dummy_value(self.i32_type.into())
+            .into_int_value();
+
+        let symbol = &format!("{}.{}", class_name, field_name);
+        let values = if let Some(val) = self.static_field_class_id_values.get_mut(symbol) {
+            val
+        } else {
+            self.static_field_class_id_values
+                .insert(symbol.clone(), Vec::default());
+            self.static_field_class_id_values.get_mut(symbol).unwrap()
+        };
+
+        values.push(dummy_value);
+        dummy_value // Returned value will be replaced by the real number at the last phase of compilation.
+    }
+
+    pub fn get_instance_field_offset_value(
+        &mut self,
+        class_name: &String,
+        field_name: &String,
+    ) -> IntValue<'ctx> {
+        let dummy_value = self
+            .insert_dummy_value(self.i32_type.into())
+            .into_int_value();
+
+        let symbol = &format!("{}.{}", class_name, field_name);
+        let values = if let Some(val) = self.instance_field_offset_values.get_mut(symbol) {
+            val
+        } else {
+            self.instance_field_offset_values
+                .insert(symbol.clone(), Vec::default());
+            self.instance_field_offset_values.get_mut(symbol).unwrap()
+        };
+
+        values.push(dummy_value);
+        dummy_value // Returned value will be replaced by the real number at the last phase of compilation.
+    }
+
     pub fn get_class_id_value(&mut self, class_name: &String) -> IntValue<'ctx> {
+        self.register_array_class(class_name);
         let dummy_value = self
             .insert_dummy_value(self.i32_type.into())
             .into_int_value()
@@ -469,6 +709,16 @@ impl<'ctx> CodegenContext<'ctx> {
         dummy_value // Returned value will be replaced by the real number at the last phase of compilation.
     }
 
+    /// Records the reference array class and the classes of its nested arrays, e.g. "[[I" for "[[[I", so that
+    /// they are defined along with the other classes. Arrays of primitives are defined by the standard library.
+    fn register_array_class(&mut self, class_name: &str) {
+        if let Some(component) = component_class_name(class_name) {
+            if self.array_class_names.insert(class_name.to_string()) {
+                self.register_array_class(component);
+            }
+        }
+    }
+
     fn insert_dummy_value(&self, typ: BasicTypeEnum<'ctx>) -> BasicValueEnum<'ctx> {
         let dummy_value = self
             .builder
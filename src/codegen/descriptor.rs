@@ -1,7 +1,5 @@
 // 4.3.2 in https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.3.2
 
-use std::str::Chars;
-
 #[derive(PartialEq, Debug, Clone)]
 pub enum FieldType {
     BaseType(BaseType),
@@ -24,7 +22,7 @@ impl std::fmt::Display for FieldType {
             FieldType::ObjectType(object_type) => write!(f, "{}", object_type),
             FieldType::ArrayType(array_type) => write!(f, "{}[]", array_type),
             FieldType::ObjectTypeJavaLangByte => write!(f, "java/lang/Byte"),
-            FieldType::ObjectTypeJavaLangChar => write!(f, "java/lang/Char"),
+            FieldType::ObjectTypeJavaLangChar => write!(f, "java/lang/Character"),
             FieldType::ObjectTypeJavaLangDouble => write!(f, "java/lang/Double"),
             FieldType::ObjectTypeJavaLangFloat => write!(f, "java/lang/Float"),
             FieldType::ObjectTypeJavaLangInteger => write!(f, "java/lang/Integer"),
@@ -35,6 +33,17 @@ impl std::fmt::Display for FieldType {
     }
 }
 
+impl FieldType {
+    /// Returns the descriptor of the type, e.g. "[Ljava/lang/String;".
+    pub fn descriptor(&self) -> String {
+        match self {
+            FieldType::BaseType(base_type) => base_type.descriptor().to_string(),
+            FieldType::ArrayType(component) => format!("[{}", component.descriptor()),
+            object_type => format!("L{};", object_type),
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
 pub enum BaseType {
     Byte,
@@ -48,6 +57,22 @@ pub enum BaseType {
     Void,
 }
 
+impl BaseType {
+    fn descriptor(&self) -> &'static str {
+        match self {
+            BaseType::Byte => "B",
+            BaseType::Char => "C",
+            BaseType::Double => "D",
+            BaseType::Float => "F",
+            BaseType::Int => "I",
+            BaseType::Long => "J",
+            BaseType::Short => "S",
+            BaseType::Boolean => "Z",
+            BaseType::Void => "V",
+        }
+    }
+}
+
 impl std::fmt::Display for BaseType {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
@@ -94,89 +119,90 @@ type ParameterType = FieldType;
 
 type ReturnType = Option<FieldType>;
 
-/// Parse a method descriptor into a MethodType.
+/// Parse a method descriptor into a MethodType. The descriptors of the compiled classes are checked by the
+/// verifier beforehand, so this panics on a malformed one.
 pub fn parse_method_descriptor(descriptor: &String) -> MethodType {
-    let mut parameter_types = Vec::new();
-
-    let mut chars = descriptor.chars();
-    let mut c = chars.next().unwrap();
-    assert_eq!(c, '(');
+    try_parse_method_descriptor(descriptor)
+        .unwrap_or_else(|| panic!("invalid method descriptor {}", descriptor))
+}
 
-    loop {
-        c = chars.next().unwrap();
-        if c == ')' {
-            break;
-        }
+/// Parse a field type descriptor into a FieldType, which panics on a malformed one like parse_method_descriptor.
+pub fn parse_field_type_descriptor(descriptor: &String) -> FieldType {
+    try_parse_field_type_descriptor(descriptor)
+        .unwrap_or_else(|| panic!("invalid field descriptor {}", descriptor))
+}
 
-        let parameter_type = parse_field_type(&mut chars, c);
+/// Parses a method descriptor into a MethodType, or returns None if it's malformed.
+pub fn try_parse_method_descriptor(descriptor: &str) -> Option<MethodType> {
+    let mut rest = descriptor.strip_prefix('(')?;
+    let mut parameter_types = Vec::new();
+    while !rest.starts_with(')') {
+        let (parameter_type, r) = parse_field_type(rest)?;
         parameter_types.push(parameter_type);
+        rest = r;
     }
-
-    c = chars.next().unwrap();
-    let return_type = parse_return_type(&mut chars, c);
-
-    MethodType {
+    let return_type = match &rest[1..] {
+        "V" => None,
+        rest => Some(try_parse_field_type_descriptor(rest)?),
+    };
+    Some(MethodType {
         parameter_types,
         return_type,
-    }
+    })
 }
 
-/// Parse a field type descriptor into a FieldType.
-pub fn parse_field_type_descriptor(descriptor: &String) -> FieldType {
-    let mut chars = descriptor.chars();
-    let c = chars.next().unwrap();
-    parse_field_type(&mut chars, c)
+/// Parses a field type descriptor into a FieldType, or returns None if it's malformed.
+pub fn try_parse_field_type_descriptor(descriptor: &str) -> Option<FieldType> {
+    match parse_field_type(descriptor)? {
+        (field_type, "") => Some(field_type),
+        _ => None,
+    }
 }
 
-fn parse_field_type(chars: &mut Chars, mut current: char) -> FieldType {
-    match current {
-        'B' => ParameterType::BaseType(BaseType::Byte),
-        'C' => ParameterType::BaseType(BaseType::Char),
-        'D' => ParameterType::BaseType(BaseType::Double),
-        'F' => ParameterType::BaseType(BaseType::Float),
-        'I' => ParameterType::BaseType(BaseType::Int),
-        'J' => ParameterType::BaseType(BaseType::Long),
-        'S' => ParameterType::BaseType(BaseType::Short),
-        'Z' => ParameterType::BaseType(BaseType::Boolean),
-        'V' => ParameterType::BaseType(BaseType::Void),
-        'L' => {
-            let mut object_type = String::new();
-            loop {
-                current = chars.next().unwrap();
-                if current == ';' {
-                    break;
-                }
-                object_type.push(current);
-            }
-            match &*object_type {
-                "java/lang/Byte" => ParameterType::ObjectTypeJavaLangByte,
-                "java/lang/Char" => ParameterType::ObjectTypeJavaLangChar,
-                "java/lang/Double" => ParameterType::ObjectTypeJavaLangDouble,
-                "java/lang/Float" => ParameterType::ObjectTypeJavaLangFloat,
-                "java/lang/Integer" => ParameterType::ObjectTypeJavaLangInteger,
-                "java/lang/Long" => ParameterType::ObjectTypeJavaLangLong,
-                "java/lang/Short" => ParameterType::ObjectTypeJavaLangShort,
-                "java/lang/Boolean" => ParameterType::ObjectTypeJavaLangBoolean,
-                _ => ParameterType::ObjectType(object_type),
+/// Parses a field type at the beginning of the descriptor, and returns it with the rest of the descriptor.
+fn parse_field_type(descriptor: &str) -> Option<(FieldType, &str)> {
+    let base_type = |base_type| Some((FieldType::BaseType(base_type), &descriptor[1..]));
+    match descriptor.as_bytes().first()? {
+        b'B' => base_type(BaseType::Byte),
+        b'C' => base_type(BaseType::Char),
+        b'D' => base_type(BaseType::Double),
+        b'F' => base_type(BaseType::Float),
+        b'I' => base_type(BaseType::Int),
+        b'J' => base_type(BaseType::Long),
+        b'S' => base_type(BaseType::Short),
+        b'Z' => base_type(BaseType::Boolean),
+        b'L' => {
+            let end = descriptor.find(';')?;
+            let object_type = &descriptor[1..end];
+            if object_type.is_empty() || object_type.contains(['.', '[']) {
+                return None;
             }
+            let field_type = match object_type {
+                "java/lang/Byte" => FieldType::ObjectTypeJavaLangByte,
+                "java/lang/Character" => FieldType::ObjectTypeJavaLangChar,
+                "java/lang/Double" => FieldType::ObjectTypeJavaLangDouble,
+                "java/lang/Float" => FieldType::ObjectTypeJavaLangFloat,
+                "java/lang/Integer" => FieldType::ObjectTypeJavaLangInteger,
+                "java/lang/Long" => FieldType::ObjectTypeJavaLangLong,
+                "java/lang/Short" => FieldType::ObjectTypeJavaLangShort,
+                "java/lang/Boolean" => FieldType::ObjectTypeJavaLangBoolean,
+                _ => FieldType::ObjectType(object_type.to_string()),
+            };
+            Some((field_type, &descriptor[end + 1..]))
         }
-        '[' => {
-            // Array
-            current = chars.next().unwrap();
-            let inner = parse_field_type(chars, current);
-            ParameterType::ArrayType(Box::new(inner))
-        }
-        _ => unreachable!("{}", current),
-    }
-}
-
-fn parse_return_type(chars: &mut Chars, current: char) -> ReturnType {
-    match current {
-        'V' => None,
-        _ => {
-            let field_type = parse_field_type(chars, current);
-            Some(field_type)
+        b'[' => {
+            // An array type has at most 255 dimensions (See 4.4.1).
+            let dimensions = descriptor.bytes().take_while(|c| *c == b'[').count();
+            if dimensions > 255 {
+                return None;
+            }
+            let (mut field_type, rest) = parse_field_type(&descriptor[dimensions..])?;
+            for _ in 0..dimensions {
+                field_type = FieldType::ArrayType(Box::new(field_type));
+            }
+            Some((field_type, rest))
         }
+        _ => None,
     }
 }
 
@@ -232,6 +258,25 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_try_parse_method_descriptor() {
+        let method_type = try_parse_method_descriptor("(IJ[[Ljava/lang/String;)[D").unwrap();
+        assert_eq!(
+            method_type.parameter_types[2].descriptor(),
+            "[[Ljava/lang/String;"
+        );
+        assert_eq!(method_type.return_type.unwrap().descriptor(), "[D");
+        assert_eq!(try_parse_method_descriptor("(V)V"), None);
+        assert_eq!(try_parse_method_descriptor("(Ljava/lang/String)V"), None);
+        assert_eq!(try_parse_method_descriptor("(L;)V"), None);
+        assert_eq!(try_parse_method_descriptor("(I)"), None);
+        assert_eq!(try_parse_method_descriptor("(I)VV"), None);
+        assert_eq!(
+            try_parse_field_type_descriptor(&format!("{}I", "[".repeat(256))),
+            None
+        );
+    }
+
     #[test]
     fn test_parse_field_type_descriptor() {
         let descriptor = String::from("Ljava/lang/String;");
@@ -248,7 +293,7 @@ mod test {
         let field_type = parse_field_type_descriptor(&descriptor);
         assert_eq!(field_type, FieldType::ObjectTypeJavaLangByte);
 
-        let descriptor = String::from("Ljava/lang/Char;");
+        let descriptor = String::from("Ljava/lang/Character;");
         let field_type = parse_field_type_descriptor(&descriptor);
         assert_eq!(field_type, FieldType::ObjectTypeJavaLangChar);
 
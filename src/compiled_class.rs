@@ -1,16 +1,73 @@
+use crate::codegen::descriptor::{parse_field_type_descriptor, FieldType};
+use crate::verifier::AssignabilityConstraint;
 use crate::Isolate;
 use std::string::ToString;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CompiledClass {
     pub class_name: String,
-    pub static_fields: Vec<String>,
+    /// The static fields in the order of the layout once `layout_static_fields` is called.
+    pub static_fields: Vec<StaticFieldInfo>,
+    /// The initial values of the static fields given by their ConstantValue attributes.
+    pub static_field_constants: Vec<(String, StaticFieldConstant)>,
+    pub instance_fields: Vec<String>,
     pub static_methods: Vec<StaticMethodInfo>,
     pub virtual_methods: Vec<VirtualMethodInfo>,
+    pub special_methods: Vec<SpecialMethodInfo>,
+    /// The symbols of the private methods in `virtual_methods`. javac invokes them with invokevirtual,
+    /// so they get their own entries in the vtable, but they never override nor are overridden.
+    pub private_methods: Vec<String>,
     pub instance_size: u32,
     pub clinit: Option<extern "C" fn(_isolate: &mut Isolate)>,
     pub opaque: Vec<u8>,
     pub super_class: Option<String>,
+    /// The interfaces directly implemented by the class, or extended by the interface.
+    pub interfaces: Vec<String>,
+    pub is_interface: bool,
+    /// The classes referred by the class file, which are compiled on demand from the class source.
+    pub referenced_classes: Vec<String>,
+    /// The assignments between the classes found by the verifier, which are checked against the class hierarchy.
+    pub assignability_constraints: Vec<AssignabilityConstraint>,
+}
+
+/// A static field of the class, which is stored at the offset in the static field storage of the class object.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaticFieldInfo {
+    pub name: String,
+    pub descriptor: String,
+    pub offset: u32,
+}
+
+impl StaticFieldInfo {
+    pub fn field_type(&self) -> FieldType {
+        parse_field_type_descriptor(&self.descriptor)
+    }
+
+    pub fn is_reference(&self) -> bool {
+        self.descriptor.starts_with(['L', '['])
+    }
+
+    /// Returns the size of the field in bytes, which is also its alignment.
+    pub fn size(&self) -> u32 {
+        match self.descriptor.as_bytes()[0] {
+            b'Z' | b'B' => 1,
+            b'C' | b'S' => 2,
+            b'I' | b'F' => 4,
+            b'J' | b'D' => 8,
+            b'L' | b'[' => size_of::<usize>() as u32,
+            _ => unreachable!("{}", self.descriptor),
+        }
+    }
+}
+
+/// The value of the ConstantValue attribute of a static field. Floats and doubles are held as their bits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StaticFieldConstant {
+    Int(i32),
+    Long(i64),
+    Float(u32),
+    Double(u64),
+    String(String),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -26,21 +83,113 @@ pub struct StaticMethodInfo {
     pub ptr: Option<*const u8>,
 }
 
+/// Represents a method invoked by invokespecial, e.g. constructors. It takes the this pointer
+/// but is never dispatched via vtables.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecialMethodInfo {
+    pub symbol: String,
+    pub ptr: Option<*const u8>,
+}
+
 impl CompiledClass {
     pub fn new(class_name: &str, super_class: Option<String>) -> Self {
         Self {
             class_name: class_name.to_string(),
             static_fields: Default::default(),
+            static_field_constants: Default::default(),
+            instance_fields: Default::default(),
             static_methods: Default::default(),
             virtual_methods: Default::default(),
+            special_methods: Default::default(),
+            private_methods: Default::default(),
             instance_size: 0,
             clinit: None,
             opaque: Default::default(),
             super_class,
+            interfaces: Default::default(),
+            is_interface: false,
+            referenced_classes: Vec::new(),
+            assignability_constraints: Vec::new(),
         }
     }
 
+    pub fn add_static_field(&mut self, name: &str, descriptor: &str) {
+        self.static_fields.push(StaticFieldInfo {
+            name: name.to_string(),
+            descriptor: descriptor.to_string(),
+            offset: 0,
+        });
+    }
+
+    pub fn static_field(&self, name: &str) -> Option<&StaticFieldInfo> {
+        self.static_fields.iter().find(|f| f.name == name)
+    }
+
+    /// Assigns the offsets of the static fields. The references come first so that they can be scanned as
+    /// a contiguous range, and then the primitives from the largest to the smallest, which keeps each of
+    /// them naturally aligned without padding. The fields of the same kind keep the declaration order.
+    pub fn layout_static_fields(&mut self) {
+        self.static_fields
+            .sort_by_key(|f| (!f.is_reference(), std::cmp::Reverse(f.size())));
+        let mut offset: u32 = 0;
+        for field in self.static_fields.iter_mut() {
+            let size = field.size();
+            offset = offset.next_multiple_of(size);
+            field.offset = offset;
+            offset += size;
+        }
+    }
+
+    /// Returns the size of the static field storage, which is rounded up to 8 bytes.
     pub fn static_field_size(&self) -> u32 {
-        (self.static_fields.len() * 8) as u32
+        self.static_fields
+            .iter()
+            .map(|f| f.offset + f.size())
+            .max()
+            .unwrap_or(0)
+            .next_multiple_of(8)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_layout_static_fields() {
+        let mut class = CompiledClass::new("Main", None);
+        class.add_static_field("b", "B");
+        class.add_static_field("s", "Ljava/lang/String;");
+        class.add_static_field("i", "I");
+        class.add_static_field("z", "Z");
+        class.add_static_field("j", "J");
+        class.add_static_field("a", "[I");
+        class.add_static_field("c", "C");
+        class.add_static_field("d", "D");
+        class.layout_static_fields();
+
+        let layout: Vec<(&str, u32)> = class
+            .static_fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.offset))
+            .collect();
+        assert_eq!(
+            layout,
+            vec![
+                ("s", 0),
+                ("a", 8),
+                ("j", 16),
+                ("d", 24),
+                ("i", 32),
+                ("c", 36),
+                ("b", 38),
+                ("z", 39)
+            ]
+        );
+        assert_eq!(class.static_field_size(), 40);
+        assert_eq!(
+            class.static_field("s").unwrap().field_type(),
+            FieldType::ObjectType("java/lang/String".to_string())
+        );
     }
 }
@@ -0,0 +1,124 @@
+use crate::stdlib::java_lang_throwable::JavaException;
+use crate::verifier::VerifyError;
+use std::fmt;
+
+/// The error returned by JitEnv when a class can't be loaded, compiled, linked or run.
+#[derive(Debug)]
+pub enum YajvmError {
+    /// The class file couldn't be read.
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+    /// The class file couldn't be parsed.
+    ClassFormat {
+        path: String,
+        message: String,
+    },
+    /// The JAR file couldn't be read as a zip archive.
+    InvalidJar {
+        path: String,
+        message: String,
+    },
+    /// The bytecode uses an instruction which the compiler doesn't support yet.
+    UnsupportedOpcode {
+        class_name: String,
+        /// The name and the descriptor of the method, e.g. "main([Ljava/lang/String;)V".
+        method: String,
+        offset: usize,
+        instruction: String,
+    },
+    /// The class isn't found in the class source such as the classpath, like java.lang.NoClassDefFoundError.
+    NoClassDefFound {
+        class_name: String,
+        /// The class referring to the missing class, or None for the main class.
+        referenced_by: Option<String>,
+    },
+    /// A symbolic reference couldn't be resolved against the compiled classes, e.g. a missing class or method.
+    Linkage {
+        message: String,
+    },
+    Verify(VerifyError),
+    /// A class failed to compile after its code was partly generated into the LLVM module, which leaves the JitEnv
+    /// unusable for any further compilation.
+    Poisoned {
+        /// The class which failed to compile.
+        class_name: String,
+    },
+    /// The Java exception which was thrown but not caught by any Java code.
+    Exception(JavaException),
+}
+
+impl YajvmError {
+    pub(crate) fn linkage(message: String) -> Self {
+        YajvmError::Linkage { message }
+    }
+}
+
+impl fmt::Display for YajvmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            YajvmError::Io { path, source } => write!(f, "failed to read {}: {}", path, source),
+            YajvmError::ClassFormat { path, message } => {
+                write!(f, "invalid class file {}: {}", path, message)
+            }
+            YajvmError::InvalidJar { path, message } => {
+                write!(f, "invalid JAR file {}: {}", path, message)
+            }
+            YajvmError::UnsupportedOpcode {
+                class_name,
+                method,
+                offset,
+                instruction,
+            } => write!(
+                f,
+                "unsupported instruction {} (class: {}, method: {}, offset: {})",
+                instruction, class_name, method, offset
+            ),
+            YajvmError::NoClassDefFound {
+                class_name,
+                referenced_by: Some(referenced_by),
+            } => write!(
+                f,
+                "java.lang.NoClassDefFoundError: {} (referenced by {})",
+                class_name, referenced_by
+            ),
+            YajvmError::NoClassDefFound {
+                class_name,
+                referenced_by: None,
+            } => write!(f, "java.lang.NoClassDefFoundError: {}", class_name),
+            YajvmError::Linkage { message } => write!(f, "{}", message),
+            YajvmError::Verify(e) => write!(f, "{}", e),
+            YajvmError::Poisoned { class_name } => write!(
+                f,
+                "the JIT environment is unusable since compiling {} failed",
+                class_name
+            ),
+            // Formatted the same way as Throwable.toString, like the uncaught exceptions of java.
+            YajvmError::Exception(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for YajvmError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            YajvmError::Io { source, .. } => Some(source),
+            YajvmError::Verify(e) => Some(e),
+            YajvmError::Exception(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<VerifyError> for YajvmError {
+    fn from(e: VerifyError) -> Self {
+        YajvmError::Verify(e)
+    }
+}
+
+impl From<JavaException> for YajvmError {
+    fn from(e: JavaException) -> Self {
+        YajvmError::Exception(e)
+    }
+}
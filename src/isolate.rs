@@ -9,20 +9,25 @@ use crate::stdlib::java_lang_object::{
     java_object_destructor_dummy, JavaObjectDestructor, JavaObjectRef,
 };
 use crate::stdlib::java_lang_string::{JavaLangString, JavaLangStringRef};
+use crate::stdlib::java_lang_throwable::{JavaLangThrowable, JavaLangThrowableRef};
 use crate::tracing::Tracer;
-use crate::{CodeGen, Stdout};
+use crate::{CodeGen, Stdout, YajvmError};
 use std::collections::HashMap;
-use std::mem::size_of;
+use std::ffi::{c_char, CStr};
+use std::mem::{offset_of, size_of};
 use std::ptr::{null, null_mut};
 
 #[repr(C)]
 pub struct Isolate {
     tracer_ptr: *mut Tracer,
+    /// The exception thrown but not caught yet. JIT code checks this after each call and
+    /// dispatches it to the exception handlers, or returns to the caller to propagate it.
+    pending_exception: JavaObjectRef,
     stdout: Box<dyn Stdout>,
     static_objects: Vec<JavaObjectRef>,
     allocated_objects: HashMap<JavaObjectRef, JavaObjectDestructor>,
     class_objects: Vec<ClassObject>,
-    java_array_class_id: ClassID,
+    string_java_array_class_id: ClassID,
     bool_java_array_class_id: ClassID,
     byte_java_array_class_id: ClassID,
     char_java_array_class_id: ClassID,
@@ -33,6 +38,8 @@ pub struct Isolate {
     double_java_array_class_id: ClassID,
     java_lang_string_class_id: ClassID,
     class_ids: HashMap<String, ClassID>,
+    class_names: Vec<String>,
+    vtable_class_ids: HashMap<usize, ClassID>,
 }
 
 type Clinit = extern "C" fn(isolate: &Isolate);
@@ -40,24 +47,46 @@ type Clinit = extern "C" fn(isolate: &Isolate);
 pub extern "C" fn clinit_dummy(_: &Isolate) {}
 
 impl Isolate {
-    pub fn new(cc: &CodeGen, stdout: Box<dyn Stdout>) -> Self {
+    /// Creates the isolate running the classes linked by `done_compilation`, or returns a linkage error if they
+    /// aren't linked yet.
+    pub fn new(cc: &CodeGen, stdout: Box<dyn Stdout>) -> Result<Self, YajvmError> {
+        if !cc.is_linked() {
+            return Err(YajvmError::linkage(
+                "the classes are not linked by done_compilation".to_string(),
+            ));
+        }
+        let class_id = |class_name: &str| {
+            cc.class_id(class_name)
+                .ok_or_else(|| YajvmError::linkage(format!("class {} not found", class_name)))
+        };
+        let java_lang_string_class_id = class_id("java/lang/String")?;
+        let string_java_array_class_id = class_id("[Ljava/lang/String;")?;
+        let bool_java_array_class_id = class_id("[Z")?;
+        let byte_java_array_class_id = class_id("[B")?;
+        let char_java_array_class_id = class_id("[C")?;
+        let short_java_array_class_id = class_id("[S")?;
+        let int_java_array_class_id = class_id("[I")?;
+        let long_java_array_class_id = class_id("[J")?;
+        let float_java_array_class_id = class_id("[F")?;
+        let double_java_array_class_id = class_id("[D")?;
         let tracer_ptr = Box::into_raw(Box::new(Tracer::new()));
-        let java_lang_string_class_id = cc.class_id("java/lang/String");
-        let java_array_class_id = cc.class_id("Array");
-        let bool_java_array_class_id = cc.class_id("ArrayBoolean");
-        let byte_java_array_class_id = cc.class_id("ArrayByte");
-        let char_java_array_class_id = cc.class_id("ArrayChar");
-        let short_java_array_class_id = cc.class_id("ArrayShort");
-        let int_java_array_class_id = cc.class_id("ArrayInt");
-        let long_java_array_class_id = cc.class_id("ArrayLong");
-        let float_java_array_class_id = cc.class_id("ArrayFloat");
-        let double_java_array_class_id = cc.class_id("ArrayDouble");
 
         let class_object_count = cc.max_class_id as usize + 100;
-        let mut class_objects = Vec::with_capacity(class_object_count);
+        let mut class_objects: Vec<ClassObject> = Vec::with_capacity(class_object_count);
         class_objects.resize_with(class_object_count, Default::default);
-        Self {
+        let mut class_names = vec![String::default(); cc.max_class_id as usize + 1];
+        for (class_name, class_id) in &cc.class_ids {
+            class_names[*class_id as usize] = class_name.clone();
+        }
+        for class_id in 0..=cc.max_class_id {
+            let class_obj = &mut class_objects[class_id as usize];
+            class_obj.super_class_id = cc.class_parents[class_id as usize];
+            class_obj.interfaces = cc.all_interfaces(class_id);
+            class_obj.component_class_id = cc.component_class_ids[class_id as usize];
+        }
+        Ok(Self {
             tracer_ptr,
+            pending_exception: null_mut(),
             static_objects: Vec::new(),
             stdout,
             allocated_objects: HashMap::new(),
@@ -71,16 +100,113 @@ impl Isolate {
             float_java_array_class_id,
             double_java_array_class_id,
             java_lang_string_class_id,
-            java_array_class_id,
+            string_java_array_class_id,
             // TODO: avoid clone and reuse the same HashMap.
             class_ids: cc.class_ids.clone(),
-        }
+            class_names,
+            vtable_class_ids: HashMap::new(),
+        })
+    }
+
+    /// The offset of the pending exception in Isolate, which is directly accessed by the JIT code.
+    pub const PENDING_EXCEPTION_OFFSET: usize = offset_of!(Isolate, pending_exception);
+
+    /// Returns the ID of the class, or None if it isn't compiled.
+    pub fn class_id(&self, class_name: &str) -> Option<ClassID> {
+        self.class_ids.get(class_name).copied()
     }
 
-    pub fn class_id(&self, class_name: &str) -> ClassID {
+    /// Returns the ID of the class of the standard library, which every JitEnv compiles.
+    pub fn stdlib_class_id(&self, class_name: &str) -> ClassID {
         self.class_ids[class_name]
     }
 
+    /// Returns the class ID of the object, which is looked up by its vtable.
+    ///
+    /// # Safety
+    ///
+    /// `obj` must be a non-null reference to an object allocated by this isolate.
+    pub unsafe fn class_id_of(&self, obj: JavaObjectRef) -> ClassID {
+        let vtable = *(obj as *const usize);
+        self.vtable_class_ids[&vtable]
+    }
+
+    /// # Safety
+    ///
+    /// `obj` must be a non-null reference to an object allocated by this isolate.
+    pub unsafe fn class_name_of(&self, obj: JavaObjectRef) -> &str {
+        &self.class_names[self.class_id_of(obj) as usize]
+    }
+
+    /// Sets the exception to be dispatched to the handlers once the control returns to the JIT code.
+    pub fn throw(&mut self, exception: JavaObjectRef) {
+        self.pending_exception = exception;
+    }
+
+    /// Creates the exception of the class with the message, and throws it.
+    pub fn throw_new(&mut self, class_name: &str, message: Option<String>) {
+        let exception = Self::new_instance(self, self.stdlib_class_id(class_name));
+        let message = match message {
+            Some(message) => self.new_java_string(&message) as JavaLangStringRef,
+            None => null_mut(),
+        };
+        unsafe {
+            JavaLangThrowable::init_with_message(self, exception as JavaLangThrowableRef, message);
+        }
+        self.throw(exception);
+    }
+
+    #[no_mangle]
+    pub extern "C" fn throw_null_pointer_exception(isolate: &mut Isolate) {
+        isolate.throw_new("java/lang/NullPointerException", None);
+    }
+
+    #[no_mangle]
+    pub extern "C" fn throw_array_index_out_of_bounds_exception(
+        isolate: &mut Isolate,
+        index: i32,
+        length: i32,
+    ) {
+        isolate.throw_new(
+            "java/lang/ArrayIndexOutOfBoundsException",
+            Some(format!(
+                "Index {} out of bounds for length {}",
+                index, length
+            )),
+        );
+    }
+
+    #[no_mangle]
+    pub extern "C" fn throw_negative_array_size_exception(isolate: &mut Isolate, size: i32) {
+        isolate.throw_new(
+            "java/lang/NegativeArraySizeException",
+            Some(size.to_string()),
+        );
+    }
+
+    #[no_mangle]
+    pub extern "C" fn throw_arithmetic_exception(isolate: &mut Isolate) {
+        isolate.throw_new(
+            "java/lang/ArithmeticException",
+            Some("/ by zero".to_string()),
+        );
+    }
+
+    /// Whether an exception has been thrown but not caught yet.
+    pub fn has_pending_exception(&self) -> bool {
+        !self.pending_exception.is_null()
+    }
+
+    /// Takes the exception which has been thrown but not caught, if any.
+    pub fn take_pending_exception(&mut self) -> Option<JavaObjectRef> {
+        let exception = std::mem::replace(&mut self.pending_exception, null_mut());
+        if exception.is_null() {
+            None
+        } else {
+            Some(exception)
+        }
+    }
+
     pub fn tracer(&mut self) -> &mut Tracer {
         unsafe { &mut *self.tracer_ptr }
     }
@@ -105,12 +231,54 @@ impl Isolate {
         ptr as JavaObjectRef
     }
 
-    pub extern "C" fn new_java_array(&mut self, length: usize) -> JavaArrayRef {
-        let array = Self::new_instance(self, self.java_array_class_id);
-        JavaArray::init(array as JavaArrayRef, length, null_mut());
+    /// Creates the array of references of the array class, filled with nulls.
+    pub extern "C" fn new_java_array(&mut self, class_id: ClassID, length: i32) -> JavaArrayRef {
+        let array = Self::new_instance(self, class_id);
+        JavaArray::init(array as JavaArrayRef, length as usize, null_mut());
         array as JavaArrayRef
     }
 
+    /// Creates the multi-dimensional array of the array class, e.g. "[[I", where the first `count` dimensions
+    /// are allocated and the rest are left null. Throws NegativeArraySizeException if any dimension is negative.
+    pub unsafe extern "C" fn new_multi_java_array(
+        &mut self,
+        class_id: ClassID,
+        dimensions: *const i32,
+        count: i32,
+    ) -> JavaArrayRef {
+        let dimensions = std::slice::from_raw_parts(dimensions, count as usize);
+        if let Some(size) = dimensions.iter().find(|size| **size < 0) {
+            Self::throw_negative_array_size_exception(self, *size);
+            return null_mut();
+        }
+        self.new_array_of_class(class_id, dimensions) as JavaArrayRef
+    }
+
+    fn new_array_of_class(&mut self, class_id: ClassID, dimensions: &[i32]) -> JavaObjectRef {
+        let length = dimensions[0] as usize;
+        let array = match class_id {
+            id if id == self.bool_java_array_class_id => self.new_bool_java_array(length),
+            id if id == self.byte_java_array_class_id => self.new_byte_java_array(length),
+            id if id == self.char_java_array_class_id => self.new_char_java_array(length),
+            id if id == self.short_java_array_class_id => self.new_short_java_array(length),
+            id if id == self.int_java_array_class_id => self.new_int_java_array(length),
+            id if id == self.long_java_array_class_id => self.new_long_java_array(length),
+            id if id == self.float_java_array_class_id => self.new_float_java_array(length),
+            id if id == self.double_java_array_class_id => self.new_double_java_array(length),
+            _ => self.new_java_array(class_id, dimensions[0]),
+        };
+        if dimensions.len() > 1 {
+            let component_class_id = self.class_objects[class_id as usize]
+                .component_class_id
+                .unwrap();
+            for i in 0..length {
+                let element = self.new_array_of_class(component_class_id, &dimensions[1..]);
+                unsafe { (*array).set(i as isize, element) };
+            }
+        }
+        array as JavaObjectRef
+    }
+
     pub extern "C" fn new_bool_java_array(&mut self, length: usize) -> JavaArrayRef {
         let array = Self::new_instance(self, self.bool_java_array_class_id);
         JavaArrayBoolean::init(array as JavaArrayBooleanRef, length, 0);
@@ -171,7 +339,8 @@ impl Isolate {
     }
 
     pub extern "C" fn allocate_args(isolate: &mut Isolate, args: &Vec<String>) -> JavaArrayRef {
-        let args_array = isolate.new_java_array(args.len());
+        let args_array =
+            isolate.new_java_array(isolate.string_java_array_class_id, args.len() as i32);
         for (i, arg) in args.iter().enumerate() {
             let arg = isolate.new_java_string(arg);
             unsafe { (*args_array).set(i as isize, arg as JavaObjectRef) };
@@ -185,21 +354,69 @@ impl Isolate {
         class_id: ClassID,
         need_initialization: bool,
     ) -> *mut ClassObject {
-        if !need_initialization {
-            return &mut isolate.class_objects[class_id as usize];
+        if need_initialization {
+            isolate.initialize_class(class_id);
+        }
+        &mut isolate.class_objects[class_id as usize]
+    }
+
+    /// Initializes the class on its first active use as per JVM spec (See 5.5): the super class is
+    /// initialized first, then the static initializer of the class is run. If the initializer throws
+    /// an exception other than Error, it is wrapped in ExceptionInInitializerError, and the class can't
+    /// be initialized anymore.
+    fn initialize_class(&mut self, class_id: ClassID) {
+        match self.class_objects[class_id as usize].init_state {
+            // The recursive requests, e.g. cyclic references between static initializers, see the class
+            // as being initialized.
+            InitState::Initialized | InitState::BeingInitialized => return,
+            InitState::Erroneous => {
+                let message = format!(
+                    "Could not initialize class {}",
+                    self.class_names[class_id as usize].replace('/', ".")
+                );
+                self.throw_new("java/lang/NoClassDefFoundError", Some(message));
+                return;
+            }
+            InitState::Uninitialized => {}
         }
-        let class_obj = &isolate.class_objects[class_id as usize];
-        if !class_obj.initialized {
-            let clinit = class_obj.clinit;
-            if clinit as usize != 0 {
-                clinit(isolate);
+        self.class_objects[class_id as usize].init_state = InitState::BeingInitialized;
+
+        let super_class_id = self.class_objects[class_id as usize].super_class_id;
+        if super_class_id != class_id {
+            self.initialize_class(super_class_id);
+            if !self.pending_exception.is_null() {
+                self.class_objects[class_id as usize].init_state = InitState::Erroneous;
+                return;
             }
         }
-        let class_obj = &mut isolate.class_objects[class_id as usize];
-        if !class_obj.initialized {
-            class_obj.initialized = true;
+
+        let clinit = self.class_objects[class_id as usize].clinit;
+        if clinit as usize != 0 {
+            clinit(self);
+        }
+        if self.pending_exception.is_null() {
+            self.class_objects[class_id as usize].init_state = InitState::Initialized;
+            return;
+        }
+
+        self.class_objects[class_id as usize].init_state = InitState::Erroneous;
+        let exception = self.pending_exception;
+        if !self.is_assignable(
+            unsafe { self.class_id_of(exception) },
+            self.stdlib_class_id("java/lang/Error"),
+        ) {
+            let error = Self::new_instance(
+                self,
+                self.stdlib_class_id("java/lang/ExceptionInInitializerError"),
+            );
+            unsafe {
+                JavaLangThrowable::init_with_cause(
+                    error as JavaLangThrowableRef,
+                    exception as JavaLangThrowableRef,
+                );
+            }
+            self.throw(error);
         }
-        class_obj
     }
 
     #[no_mangle]
@@ -211,6 +428,7 @@ impl Isolate {
         vtable: *const u8,
         clinit: Clinit,
     ) -> &mut ClassObject {
+        isolate.vtable_class_ids.insert(vtable as usize, class_id);
         isolate.class_objects[class_id as usize].init(
             static_fields_size,
             instance_size,
@@ -220,6 +438,143 @@ impl Isolate {
         &mut isolate.class_objects[class_id as usize]
     }
 
+    /// Returns true if the object is an instance of the class or any of its subclasses, or of a class
+    /// implementing the interface. Null is an instance of nothing.
+    ///
+    /// # Safety
+    ///
+    /// `obj` must be null or a reference to an object allocated by the isolate.
+    #[no_mangle]
+    pub unsafe extern "C" fn is_instance_of(
+        isolate: &mut Isolate,
+        obj: JavaObjectRef,
+        class_id: ClassID,
+    ) -> bool {
+        if obj.is_null() {
+            return false;
+        }
+        isolate.is_assignable(isolate.class_id_of(obj), class_id)
+    }
+
+    /// Returns true if the instances of the class `from` are the instances of the class `to`. Arrays of
+    /// references are covariant, e.g. "[Ljava/lang/String;" is assignable to "[Ljava/lang/Object;".
+    fn is_assignable(&self, from: ClassID, to: ClassID) -> bool {
+        let from_class = &self.class_objects[from as usize];
+        if from_class.interfaces.contains(&to) {
+            return true;
+        }
+        if let (Some(from_component), Some(to_component)) = (
+            from_class.component_class_id,
+            self.class_objects[to as usize].component_class_id,
+        ) {
+            return self.is_assignable(from_component, to_component);
+        }
+        let mut current = from;
+        loop {
+            if current == to {
+                return true;
+            }
+            let parent = self.class_objects[current as usize].super_class_id;
+            if parent == current {
+                // Reached java/lang/Object.
+                return false;
+            }
+            current = parent;
+        }
+    }
+
+    /// Throws ClassCastException unless the object is null or an instance of the class.
+    ///
+    /// # Safety
+    ///
+    /// `obj` must be null or a reference to an object allocated by the isolate.
+    #[no_mangle]
+    pub unsafe extern "C" fn check_cast(
+        isolate: &mut Isolate,
+        obj: JavaObjectRef,
+        class_id: ClassID,
+    ) {
+        if obj.is_null() || Self::is_instance_of(isolate, obj, class_id) {
+            return;
+        }
+        let message = format!(
+            "class {} cannot be cast to class {}",
+            isolate.class_name_of(obj).replace('/', "."),
+            isolate.class_names[class_id as usize].replace('/', ".")
+        );
+        isolate.throw_new("java/lang/ClassCastException", Some(message));
+    }
+
+    /// Returns the class ID of the receiver of invokeinterface, which indexes the itables.
+    ///
+    /// # Safety
+    ///
+    /// `obj` must be a non-null reference to an object allocated by the isolate.
+    #[no_mangle]
+    pub unsafe extern "C" fn get_class_id(isolate: &mut Isolate, obj: JavaObjectRef) -> ClassID {
+        isolate.class_id_of(obj)
+    }
+
+    /// Throws IncompatibleClassChangeError if the class of the receiver doesn't implement the interface, or
+    /// AbstractMethodError if it does but has no implementation of the method.
+    ///
+    /// # Safety
+    ///
+    /// `obj` must be a non-null reference to an object allocated by the isolate, and `method` must be a
+    /// NUL-terminated string such as "abstract void run()".
+    #[no_mangle]
+    pub unsafe extern "C" fn throw_interface_method_error(
+        isolate: &mut Isolate,
+        obj: JavaObjectRef,
+        interface_id: ClassID,
+        method: *const c_char,
+    ) {
+        let class_id = isolate.class_id_of(obj);
+        let class_name = isolate.class_names[class_id as usize].replace('/', ".");
+        let interface_name = isolate.class_names[interface_id as usize].replace('/', ".");
+        if !isolate.is_assignable(class_id, interface_id) {
+            let message = format!(
+                "Class {} does not implement the requested interface {}",
+                class_name, interface_name
+            );
+            isolate.throw_new("java/lang/IncompatibleClassChangeError", Some(message));
+            return;
+        }
+        let message = format!(
+            "Receiver class {} does not define or inherit an implementation of the resolved method '{}' of interface {}.",
+            class_name,
+            CStr::from_ptr(method).to_string_lossy(),
+            interface_name
+        );
+        isolate.throw_new("java/lang/AbstractMethodError", Some(message));
+    }
+
+    /// Throws ArrayStoreException unless the value is null or an instance of the element class of the array.
+    ///
+    /// # Safety
+    ///
+    /// `array` must be a reference array allocated by the isolate, and `value` must be null or a reference to an
+    /// object allocated by the isolate.
+    #[no_mangle]
+    pub unsafe extern "C" fn check_array_store(
+        isolate: &mut Isolate,
+        array: JavaArrayRef,
+        value: JavaObjectRef,
+    ) {
+        if value.is_null() {
+            return;
+        }
+        let array_class_id = isolate.class_id_of(array as JavaObjectRef);
+        let component_class_id = isolate.class_objects[array_class_id as usize]
+            .component_class_id
+            .unwrap();
+        if isolate.is_assignable(isolate.class_id_of(value), component_class_id) {
+            return;
+        }
+        let message = isolate.class_name_of(value).replace('/', ".");
+        isolate.throw_new("java/lang/ArrayStoreException", Some(message));
+    }
+
     #[no_mangle]
     pub extern "C" fn new_instance(isolate: &mut Isolate, class_id: ClassID) -> JavaObjectRef {
         let class_object = &isolate.class_objects[class_id as usize];
@@ -245,18 +600,36 @@ impl Drop for Isolate {
     }
 }
 
+/// The initialization state of a class as per JVM spec (See 5.5).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InitState {
+    Uninitialized,
+    BeingInitialized,
+    Initialized,
+    /// The initialization has failed, so the class is unusable.
+    Erroneous,
+}
+
 #[repr(C)]
 #[derive(Debug)]
 pub struct ClassObject {
     static_fields_ptr: *mut u8,
     vtable: *const u8,
     clinit: Clinit,
-    initialized: bool,
+    init_state: InitState,
     instance_size: u32,
     destructor: JavaObjectDestructor,
     // This will only be used by the Rust code.
     opaque: *const u8,
-    static_fields: Vec<u8>,
+    /// The storage of the static fields, which is held as u64s to align the longs, doubles and references.
+    static_fields: Vec<u64>,
+    /// The class ID of the super class, which is itself for java/lang/Object.
+    super_class_id: ClassID,
+    /// All the interfaces implemented by the class, including the ones inherited from the ancestors
+    /// and extended by the other interfaces.
+    interfaces: Vec<ClassID>,
+    /// The class ID of the elements if the class is an array of references.
+    component_class_id: Option<ClassID>,
 }
 
 impl Default for ClassObject {
@@ -265,11 +638,14 @@ impl Default for ClassObject {
             static_fields_ptr: null_mut(),
             vtable: null(),
             clinit: clinit_dummy,
-            initialized: false,
+            init_state: InitState::Uninitialized,
             instance_size: 0,
             destructor: java_object_destructor_dummy,
             opaque: null(),
             static_fields: Vec::new(),
+            super_class_id: 0,
+            interfaces: Vec::new(),
+            component_class_id: None,
         }
     }
 }
@@ -282,12 +658,11 @@ impl ClassObject {
         vtable: *const u8,
         clinit: Clinit,
     ) {
-        self.static_fields = Vec::with_capacity(static_fields_size as usize);
-        self.static_fields.resize(static_fields_size as usize, 0);
-        self.static_fields_ptr = self.static_fields.as_mut_ptr();
+        self.static_fields = vec![0; (static_fields_size as usize).div_ceil(8)];
+        self.static_fields_ptr = self.static_fields.as_mut_ptr() as *mut u8;
         self.vtable = vtable;
         self.destructor = java_object_destructor_dummy;
-        self.initialized = false;
+        self.init_state = InitState::Uninitialized;
         self.instance_size = instance_size;
         self.clinit = clinit;
         self.opaque = null();
@@ -301,10 +676,7 @@ impl ClassObject {
         // Set the opaque pointer of the Java object to the offset of the class object.
         let ptr = obj as *mut u8;
         unsafe {
-            std::ptr::write(
-                self.static_fields.as_mut_ptr().add(offset) as *mut *mut u8,
-                ptr,
-            );
+            std::ptr::write(self.static_fields_ptr.add(offset) as *mut *mut u8, ptr);
         }
     }
 }
@@ -0,0 +1,148 @@
+use crate::error::YajvmError;
+use std::fs::File;
+use std::io::Read;
+use zip::result::ZipError;
+use zip::ZipArchive;
+
+const MANIFEST_PATH: &str = "META-INF/MANIFEST.MF";
+
+/// Which classes of a JAR file are compiled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JarClasses {
+    /// Every class in the JAR file.
+    All,
+    /// The classes reachable from the main class through the class constants of the constant pools.
+    Reachable,
+}
+
+/// The JAR file opened to read its classes.
+pub struct Jar {
+    path: String,
+    archive: ZipArchive<File>,
+    main_class: Option<String>,
+}
+
+impl Jar {
+    pub fn open(path: &str) -> Result<Self, YajvmError> {
+        let file = File::open(path).map_err(|source| YajvmError::Io {
+            path: path.to_string(),
+            source,
+        })?;
+        let archive = ZipArchive::new(file).map_err(|e| zip_error(path, e))?;
+        let mut jar = Self {
+            path: path.to_string(),
+            archive,
+            main_class: None,
+        };
+        jar.main_class = match jar.read_entry(MANIFEST_PATH)? {
+            Some(manifest) => parse_main_class(&String::from_utf8_lossy(&manifest)),
+            None => None,
+        };
+        Ok(jar)
+    }
+
+    /// Returns the Main-Class attribute of the manifest as the internal name, e.g. "com/example/Main".
+    pub fn main_class(&self) -> Option<&str> {
+        self.main_class.as_deref()
+    }
+
+    /// Returns the internal names of the classes in the JAR file, sorted.
+    pub fn class_names(&self) -> Vec<String> {
+        let mut class_names: Vec<String> = self
+            .archive
+            .file_names()
+            .filter(|name| !name.starts_with("META-INF/"))
+            .filter_map(|name| name.strip_suffix(".class"))
+            // They declare the module and the package annotations instead of classes.
+            .filter(|name| !name.ends_with("module-info") && !name.ends_with("package-info"))
+            .map(String::from)
+            .collect();
+        class_names.sort();
+        class_names
+    }
+
+    /// Reads the class file of the class, or returns None if the JAR file doesn't contain it.
+    pub fn read_class(&mut self, class_name: &str) -> Result<Option<Vec<u8>>, YajvmError> {
+        self.read_entry(&format!("{}.class", class_name))
+    }
+
+    /// Returns the path of the class file in the error messages, e.g. "app.jar!/com/example/Main.class".
+    pub fn class_path(&self, class_name: &str) -> String {
+        format!("{}!/{}.class", self.path, class_name)
+    }
+
+    fn read_entry(&mut self, name: &str) -> Result<Option<Vec<u8>>, YajvmError> {
+        let mut entry = match self.archive.by_name(name) {
+            Ok(entry) => entry,
+            Err(ZipError::FileNotFound) => return Ok(None),
+            Err(e) => return Err(zip_error(&self.path, e)),
+        };
+        let mut bytes = Vec::new();
+        entry
+            .read_to_end(&mut bytes)
+            .map_err(|source| YajvmError::Io {
+                path: format!("{}!/{}", self.path, name),
+                source,
+            })?;
+        Ok(Some(bytes))
+    }
+}
+
+fn zip_error(path: &str, e: ZipError) -> YajvmError {
+    match e {
+        ZipError::Io(source) => YajvmError::Io {
+            path: path.to_string(),
+            source,
+        },
+        e => YajvmError::InvalidJar {
+            path: path.to_string(),
+            message: e.to_string(),
+        },
+    }
+}
+
+/// Parses the Main-Class attribute in the main section of the manifest.
+fn parse_main_class(manifest: &str) -> Option<String> {
+    let mut attributes: Vec<String> = Vec::new();
+    for line in manifest.lines() {
+        if line.is_empty() {
+            // The main section ends with an empty line, followed by the sections of the entries.
+            break;
+        }
+        // The lines longer than 72 bytes continue on the next lines starting with a space.
+        match (line.strip_prefix(' '), attributes.last_mut()) {
+            (Some(continuation), Some(attribute)) => attribute.push_str(continuation),
+            _ => attributes.push(line.to_string()),
+        }
+    }
+    attributes.iter().find_map(|attribute| {
+        let (name, value) = attribute.split_once(':')?;
+        // The attribute names are case-insensitive.
+        name.eq_ignore_ascii_case("Main-Class")
+            .then(|| value.trim().replace('.', "/"))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_main_class() {
+        assert_eq!(
+            parse_main_class("Manifest-Version: 1.0\r\nMain-Class: com.example.Main\r\n\r\n"),
+            Some("com/example/Main".to_string())
+        );
+        assert_eq!(
+            parse_main_class(
+                "Manifest-Version: 1.0\nmain-class: com.example.app.Ver\n ylongMain\n"
+            ),
+            Some("com/example/app/VerylongMain".to_string())
+        );
+        // The attributes of the entries are not the main class.
+        assert_eq!(
+            parse_main_class("Manifest-Version: 1.0\n\nName: a/B.class\nMain-Class: a.B\n"),
+            None
+        );
+    }
+}
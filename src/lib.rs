@@ -2,15 +2,27 @@ pub mod codegen;
 pub mod stdlib;
 
 use inkwell::execution_engine::JitFunction;
+use std::collections::HashSet;
 use std::io::Write;
 
+mod classpath;
 mod compiled_class;
+mod error;
 pub mod isolate;
+mod jar;
 pub mod tracing;
+mod verifier;
 
+pub use crate::classpath::{ClassSource, Classpath};
 pub use crate::codegen::CodeGen;
+pub use crate::compiled_class::StaticFieldInfo;
+pub use crate::error::YajvmError;
 pub use crate::isolate::Isolate;
+pub use crate::jar::{Jar, JarClasses};
 use crate::stdlib::add_stdlib;
+use crate::stdlib::java_lang_string::JavaLangString;
+pub use crate::stdlib::java_lang_throwable::JavaException;
+pub use crate::verifier::VerifyError;
 
 pub enum StdoutOption {
     Stdout(Box<dyn Stdout>),
@@ -58,27 +70,94 @@ impl Stdout for VecOutputStream {
 
 pub struct JitEnv<'ctx> {
     codegen: CodeGen<'ctx>,
+    class_source: Option<Box<dyn ClassSource>>,
 }
 
 impl<'ctx> JitEnv<'ctx> {
     pub fn new(class_name: &str) -> Self {
         let mut codegen = CodeGen::new(class_name);
         add_stdlib(&mut codegen);
-        Self { codegen }
+        Self {
+            codegen,
+            class_source: None,
+        }
     }
 
-    pub fn new_isolate(&self, stdout: StdoutOption) -> Isolate {
+    /// Creates the isolate to run the classes, which fails unless `done_compilation` has linked them.
+    pub fn new_isolate(&self, stdout: StdoutOption) -> Result<Isolate, YajvmError> {
         let stdout = match stdout {
             StdoutOption::Stdout(stdout) => stdout,
             StdoutOption::VecOutputStream => Box::new(VecOutputStream::new()),
             StdoutOption::HostStdout => Box::new(std::io::stdout()),
         };
-        let isolate = Isolate::new(&self.codegen, stdout);
-        isolate
+        Isolate::new(&self.codegen, stdout)
+    }
+
+    /// Compiles the class file after verifying it. If the class fails to compile after it passes the verification,
+    /// e.g. with an unsupported instruction, the JitEnv is poisoned and any further compilation or linking fails
+    /// with `YajvmError::Poisoned`, as the code generated so far can't be removed.
+    pub fn compile(&mut self, path: &str) -> Result<(), YajvmError> {
+        self.codegen.compile(path)
     }
 
-    pub fn compile(&mut self, path: &str) {
-        self.codegen.compile(path);
+    /// Compiles the class file given as bytes after verifying it, e.g. the one generated or downloaded by the
+    /// embedder.
+    pub fn compile_bytes(&mut self, classfile_bytes: &[u8]) -> Result<(), YajvmError> {
+        self.codegen.compile_bytes("<memory>", classfile_bytes)
+    }
+
+    /// Compiles the classes in the JAR file after verifying them. The classes the JAR file doesn't contain, e.g.
+    /// java/lang/Object, are expected to be provided by the stdlib or the class source.
+    pub fn compile_jar(&mut self, jar: &mut Jar, classes: JarClasses) -> Result<(), YajvmError> {
+        match classes {
+            JarClasses::All => {
+                for class_name in jar.class_names() {
+                    let path = jar.class_path(&class_name);
+                    let classfile_bytes =
+                        jar.read_class(&class_name)?
+                            .ok_or_else(|| YajvmError::InvalidJar {
+                                path: path.clone(),
+                                message: "class file listed but not found".to_string(),
+                            })?;
+                    self.codegen.compile_bytes(&path, &classfile_bytes)?;
+                }
+            }
+            // The classes missing in the JAR file are left to the stdlib and the class source.
+            JarClasses::Reachable => {
+                compile_reachable_classes(&mut self.codegen, jar)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets the source which `done_compilation` asks for the classes referred but not compiled, starting from the
+    /// main class.
+    pub fn set_class_source(&mut self, class_source: impl ClassSource + 'static) {
+        self.class_source = Some(Box::new(class_source));
+    }
+
+    /// Sets the classpath as the source of the classes referred but not compiled.
+    pub fn set_classpath(&mut self, classpath: Classpath) {
+        self.set_class_source(classpath);
+    }
+
+    /// Compiles the classes found in the class source until no compiled class refers to a class not compiled.
+    fn compile_missing_classes(&mut self) -> Result<(), YajvmError> {
+        let Some(class_source) = self.class_source.as_mut() else {
+            return Ok(());
+        };
+        match compile_reachable_classes(&mut self.codegen, class_source.as_mut())?.first() {
+            Some((class_name, referenced_by)) => Err(YajvmError::NoClassDefFound {
+                class_name: class_name.clone(),
+                referenced_by: referenced_by.clone(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the static fields of the class with their types and offsets, or None if the class isn't loaded.
+    pub fn static_fields(&self, class_name: &str) -> Option<&[StaticFieldInfo]> {
+        self.codegen.static_fields(class_name)
     }
 
     pub fn dump_llvm_module(&mut self, path: &str) {
@@ -90,8 +169,11 @@ impl<'ctx> JitEnv<'ctx> {
         self.codegen.enable_tracing();
     }
 
-    pub fn done_compilation(&mut self) {
-        self.codegen.done_compilation();
+    /// Links the compiled classes, which fails if they refer to any class, field or method not compiled. The classes
+    /// not compiled are compiled from the class source first if it's set.
+    pub fn done_compilation(&mut self) -> Result<(), YajvmError> {
+        self.compile_missing_classes()?;
+        self.codegen.done_compilation()?;
         if let Some(f) = self
             .codegen
             .cc
@@ -127,10 +209,54 @@ impl<'ctx> JitEnv<'ctx> {
             &self.codegen.cc.new_instance_fn,
             Isolate::new_instance as usize,
         );
+        self.codegen.cc.execution_engine.add_global_mapping(
+            &self.codegen.cc.is_instance_of_fn,
+            Isolate::is_instance_of as usize,
+        );
+        self.codegen
+            .cc
+            .execution_engine
+            .add_global_mapping(&self.codegen.cc.check_cast_fn, Isolate::check_cast as usize);
+        self.codegen.cc.execution_engine.add_global_mapping(
+            &self.codegen.cc.class_id_of_fn,
+            Isolate::get_class_id as *const () as usize,
+        );
+        self.codegen.cc.execution_engine.add_global_mapping(
+            &self.codegen.cc.throw_interface_method_error_fn,
+            Isolate::throw_interface_method_error as *const () as usize,
+        );
+        self.codegen.cc.execution_engine.add_global_mapping(
+            &self.codegen.cc.throw_null_pointer_exception_fn,
+            Isolate::throw_null_pointer_exception as usize,
+        );
+        self.codegen.cc.execution_engine.add_global_mapping(
+            &self.codegen.cc.throw_array_index_out_of_bounds_exception_fn,
+            Isolate::throw_array_index_out_of_bounds_exception as usize,
+        );
+        self.codegen.cc.execution_engine.add_global_mapping(
+            &self.codegen.cc.throw_negative_array_size_exception_fn,
+            Isolate::throw_negative_array_size_exception as usize,
+        );
+        self.codegen.cc.execution_engine.add_global_mapping(
+            &self.codegen.cc.throw_arithmetic_exception_fn,
+            Isolate::throw_arithmetic_exception as usize,
+        );
         self.codegen.cc.execution_engine.add_global_mapping(
             &self.codegen.cc.new_java_array_fn,
             Isolate::new_java_array as usize,
         );
+        self.codegen.cc.execution_engine.add_global_mapping(
+            &self.codegen.cc.new_multi_array_fn,
+            Isolate::new_multi_java_array as usize,
+        );
+        self.codegen.cc.execution_engine.add_global_mapping(
+            &self.codegen.cc.check_array_store_fn,
+            Isolate::check_array_store as usize,
+        );
+        self.codegen.cc.execution_engine.add_global_mapping(
+            &self.codegen.cc.string_concat_fn,
+            JavaLangString::concat_with_constants as usize,
+        );
         self.codegen.cc.execution_engine.add_global_mapping(
             &self.codegen.cc.new_boolean_array_fn,
             Isolate::new_bool_java_array as usize,
@@ -163,19 +289,55 @@ impl<'ctx> JitEnv<'ctx> {
             &self.codegen.cc.new_double_array_fn,
             Isolate::new_double_java_array as usize,
         );
+        Ok(())
     }
 
-    pub fn call(&mut self, isolate: &mut Isolate, args: &Vec<String>) {
+    /// Calls the main method. Returns YajvmError::Exception if an exception is thrown and not caught by any Java
+    /// code.
+    pub fn call(&mut self, isolate: &mut Isolate, args: &Vec<String>) -> Result<(), YajvmError> {
         let f: JitFunction<'ctx, unsafe extern "C" fn(*mut Isolate, *const Vec<String>)> = unsafe {
             self.codegen
                 .cc
                 .execution_engine
                 .get_function("main")
-                .unwrap()
+                .map_err(|e| YajvmError::linkage(format!("main function not found: {}", e)))?
         };
 
         unsafe {
             f.call(isolate, args);
         }
+        match isolate.take_pending_exception() {
+            // The pending exception is always a Throwable thrown by the JIT code.
+            Some(exception) => Err(unsafe { JavaException::new(isolate, exception) }.into()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Compiles the classes found in the class source, following the references from the main class until every class
+/// referred is compiled or missing in the source. Returns the missing classes with the classes referring them.
+fn compile_reachable_classes(
+    codegen: &mut CodeGen,
+    class_source: &mut dyn ClassSource,
+) -> Result<Vec<(String, Option<String>)>, YajvmError> {
+    let mut missing = Vec::new();
+    let mut skipped = HashSet::new();
+    while let Some((class_name, referenced_by)) = codegen.missing_class(&skipped) {
+        match class_source.find_class(&class_name)? {
+            Some((path, classfile_bytes)) => {
+                codegen.compile_bytes(&path, &classfile_bytes)?;
+                if !codegen.has_class(&class_name) {
+                    return Err(YajvmError::ClassFormat {
+                        path,
+                        message: format!("wrong name, expected {}", class_name),
+                    });
+                }
+            }
+            None => {
+                skipped.insert(class_name.clone());
+                missing.push((class_name, referenced_by));
+            }
+        }
     }
+    Ok(missing)
 }
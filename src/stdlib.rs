@@ -4,10 +4,13 @@ pub mod array;
 pub mod java_io;
 pub mod java_lang_boolean;
 pub mod java_lang_char;
+pub mod java_lang_interfaces;
 pub mod java_lang_number;
 pub mod java_lang_object;
 pub mod java_lang_string;
 pub mod java_lang_system;
+pub mod java_lang_throwable;
+pub mod java_util;
 
 pub fn add_stdlib(cc: &mut CodeGen) {
     cc.add_class(java_lang_object::new_compiled_class());
@@ -25,4 +28,13 @@ pub fn add_stdlib(cc: &mut CodeGen) {
     for c in array::new_compiled_classes() {
         cc.add_class(c);
     }
+    for c in java_lang_throwable::new_compiled_classes() {
+        cc.add_class(c);
+    }
+    for c in java_lang_interfaces::new_compiled_classes() {
+        cc.add_class(c);
+    }
+    for c in java_util::new_compiled_classes() {
+        cc.add_class(c);
+    }
 }
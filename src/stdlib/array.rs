@@ -5,81 +5,75 @@ use crate::Isolate;
 
 pub fn new_compiled_classes() -> Vec<CompiledClass> {
     let mut ret = Vec::new();
-    let mut c = CompiledClass::new("Array", None);
-    c.virtual_methods.push(VirtualMethodInfo {
-        symbol: "java/lang/Object.toString:()Ljava/lang/String;@Array".to_string(),
-        ptr: Some(java_array_java_lang_object_to_string as *const u8),
-        overrides: Some("java/lang/Object.toString:()Ljava/lang/String;".to_string()),
-    });
-    c.instance_size = std::mem::size_of::<JavaArray>() as u32;
-    ret.push(c);
+    // The arguments of the main method.
+    ret.push(new_compiled_reference_array_class("[Ljava/lang/String;"));
 
-    let mut c = CompiledClass::new("ArrayBoolean", None);
+    let mut c = CompiledClass::new("[Z", None);
     c.virtual_methods.push(VirtualMethodInfo {
-        symbol: "java/lang/Object.toString:()Ljava/lang/String;@ArrayBoolean".to_string(),
+        symbol: "java/lang/Object.toString:()Ljava/lang/String;@[Z".to_string(),
         ptr: Some(boolean_java_array_java_lang_object_to_string as *const u8),
         overrides: Some("java/lang/Object.toString:()Ljava/lang/String;".to_string()),
     });
     c.instance_size = std::mem::size_of::<JavaArrayBoolean>() as u32;
     ret.push(c);
 
-    let mut c = CompiledClass::new("ArrayByte", None);
+    let mut c = CompiledClass::new("[B", None);
     c.virtual_methods.push(VirtualMethodInfo {
-        symbol: "java/lang/Object.toString:()Ljava/lang/String;@ArrayByte".to_string(),
+        symbol: "java/lang/Object.toString:()Ljava/lang/String;@[B".to_string(),
         ptr: Some(byte_java_array_java_lang_object_to_string as *const u8),
         overrides: Some("java/lang/Object.toString:()Ljava/lang/String;".to_string()),
     });
     c.instance_size = std::mem::size_of::<JavaArrayByte>() as u32;
     ret.push(c);
 
-    let mut c = CompiledClass::new("ArrayChar", None);
+    let mut c = CompiledClass::new("[C", None);
     c.virtual_methods.push(VirtualMethodInfo {
-        symbol: "java/lang/Object.toString:()Ljava/lang/String;@ArrayChar".to_string(),
+        symbol: "java/lang/Object.toString:()Ljava/lang/String;@[C".to_string(),
         ptr: Some(char_java_array_java_lang_object_to_string as *const u8),
         overrides: Some("java/lang/Object.toString:()Ljava/lang/String;".to_string()),
     });
     c.instance_size = std::mem::size_of::<JavaArrayChar>() as u32;
     ret.push(c);
 
-    let mut c = CompiledClass::new("ArrayShort", None);
+    let mut c = CompiledClass::new("[S", None);
     c.virtual_methods.push(VirtualMethodInfo {
-        symbol: "java/lang/Object.toString:()Ljava/lang/String;@ArrayShort".to_string(),
+        symbol: "java/lang/Object.toString:()Ljava/lang/String;@[S".to_string(),
         ptr: Some(short_java_array_java_lang_object_to_string as *const u8),
         overrides: Some("java/lang/Object.toString:()Ljava/lang/String;".to_string()),
     });
     c.instance_size = std::mem::size_of::<JavaArrayShort>() as u32;
     ret.push(c);
 
-    let mut c = CompiledClass::new("ArrayInt", None);
+    let mut c = CompiledClass::new("[I", None);
     c.virtual_methods.push(VirtualMethodInfo {
-        symbol: "java/lang/Object.toString:()Ljava/lang/String;@ArrayInt".to_string(),
+        symbol: "java/lang/Object.toString:()Ljava/lang/String;@[I".to_string(),
         ptr: Some(int_java_array_java_lang_object_to_string as *const u8),
         overrides: Some("java/lang/Object.toString:()Ljava/lang/String;".to_string()),
     });
     c.instance_size = std::mem::size_of::<JavaArrayInt>() as u32;
     ret.push(c);
 
-    let mut c = CompiledClass::new("ArrayLong", None);
+    let mut c = CompiledClass::new("[J", None);
     c.virtual_methods.push(VirtualMethodInfo {
-        symbol: "java/lang/Object.toString:()Ljava/lang/String;@ArrayLong".to_string(),
+        symbol: "java/lang/Object.toString:()Ljava/lang/String;@[J".to_string(),
         ptr: Some(long_java_array_java_lang_object_to_string as *const u8),
         overrides: Some("java/lang/Object.toString:()Ljava/lang/String;".to_string()),
     });
     c.instance_size = std::mem::size_of::<JavaArrayLong>() as u32;
     ret.push(c);
 
-    let mut c = CompiledClass::new("ArrayFloat", None);
+    let mut c = CompiledClass::new("[F", None);
     c.virtual_methods.push(VirtualMethodInfo {
-        symbol: "java/lang/Object.toString:()Ljava/lang/String;@ArrayFloat".to_string(),
+        symbol: "java/lang/Object.toString:()Ljava/lang/String;@[F".to_string(),
         ptr: Some(float_java_array_java_lang_object_to_string as *const u8),
         overrides: Some("java/lang/Object.toString:()Ljava/lang/String;".to_string()),
     });
     c.instance_size = std::mem::size_of::<JavaArrayFloat>() as u32;
     ret.push(c);
 
-    let mut c = CompiledClass::new("ArrayDouble", None);
+    let mut c = CompiledClass::new("[D", None);
     c.virtual_methods.push(VirtualMethodInfo {
-        symbol: "java/lang/Object.toString:()Ljava/lang/String;@ArrayDouble".to_string(),
+        symbol: "java/lang/Object.toString:()Ljava/lang/String;@[D".to_string(),
         ptr: Some(double_java_array_java_lang_object_to_string as *const u8),
         overrides: Some("java/lang/Object.toString:()Ljava/lang/String;".to_string()),
     });
@@ -89,6 +83,33 @@ pub fn new_compiled_classes() -> Vec<CompiledClass> {
     ret
 }
 
+/// Creates the class of the arrays of references, e.g. "[Ljava/lang/String;" or "[[I".
+pub fn new_compiled_reference_array_class(class_name: &str) -> CompiledClass {
+    let mut c = CompiledClass::new(class_name, None);
+    c.virtual_methods.push(VirtualMethodInfo {
+        symbol: format!(
+            "java/lang/Object.toString:()Ljava/lang/String;@{}",
+            class_name
+        ),
+        ptr: Some(java_array_java_lang_object_to_string as *const u8),
+        overrides: Some("java/lang/Object.toString:()Ljava/lang/String;".to_string()),
+    });
+    c.instance_size = std::mem::size_of::<JavaArray>() as u32;
+    c
+}
+
+/// Returns the class name of the elements of the array class if they are references, e.g. "java/lang/String"
+/// for "[Ljava/lang/String;" and "[I" for "[[I".
+pub fn component_class_name(class_name: &str) -> Option<&str> {
+    if class_name.starts_with("[L") {
+        Some(&class_name[2..class_name.len() - 1])
+    } else if class_name.starts_with("[[") {
+        Some(&class_name[1..])
+    } else {
+        None
+    }
+}
+
 pub type JavaArray = JavaArrayT<JavaObjectRef>;
 pub type JavaArrayRef = *mut JavaArray;
 
@@ -161,6 +182,10 @@ pub unsafe extern "C" fn java_array_java_lang_object_to_string(
 ) -> JavaLangStringRef {
     let mut ss = Vec::<String>::new();
     for item in java_array_ref_into_iterator(array) {
+        if item.is_null() {
+            ss.push("null".to_string());
+            continue;
+        }
         let s = (*to_java_string_ref(isolate, item)).as_str();
         ss.push(s.to_string());
     }
@@ -193,7 +193,7 @@ pub trait OutputStream {
 
 pub type OutPutStreamRef = *mut dyn OutputStream;
 
-fn format_float_java_style(f: f32) -> String {
+pub fn format_float_java_style(f: f32) -> String {
     if f.is_nan() {
         return "NaN".to_string();
     }
@@ -219,7 +219,7 @@ fn format_float_java_style(f: f32) -> String {
     };
 }
 
-fn format_double_java_style(d: f64) -> String {
+pub fn format_double_java_style(d: f64) -> String {
     if d.is_nan() {
         return "NaN".to_string();
     }
@@ -14,6 +14,15 @@ pub fn new_compiled_class() -> CompiledClass {
         symbol: "java/lang/Boolean.init:(Z)V".to_string(),
         ptr: Some(JavaLangBoolean::init as *const u8),
     });
+    c.static_methods.push(StaticMethodInfo {
+        symbol: "java/lang/Boolean.valueOf:(Z)Ljava/lang/Boolean;".to_string(),
+        ptr: Some(JavaLangBoolean::value_of as *const u8),
+    });
+    c.virtual_methods.push(VirtualMethodInfo {
+        symbol: "java/lang/Boolean.booleanValue:()Z".to_string(),
+        ptr: Some(JavaLangBoolean::boolean_value as *const u8),
+        overrides: None,
+    });
     c.instance_size = std::mem::size_of::<JavaLangBoolean>() as u32;
     c
 }
@@ -29,6 +38,25 @@ impl JavaLangBoolean {
         self.value = v == 1;
     }
 
+    /// Boxes the boolean, which javac emits as Boolean.valueOf(boolean).
+    pub extern "C" fn value_of(isolate: &mut Isolate, v: u32) -> JavaObjectRef {
+        let class_id = isolate.stdlib_class_id("java/lang/Boolean");
+        let obj = Isolate::new_instance(isolate, class_id);
+        unsafe {
+            (*(obj as *mut JavaLangBoolean)).init(v);
+        }
+        obj
+    }
+
+    /// Unboxes the boolean, which javac emits as Boolean.booleanValue().
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be a non-null reference to a Boolean.
+    pub unsafe extern "C" fn boolean_value(_: &mut Isolate, ptr: JavaObjectRef) -> bool {
+        (*(ptr as *const JavaLangBoolean)).value
+    }
+
     pub unsafe extern "C" fn java_lang_object_to_string(
         isolate: &mut Isolate,
         ptr: JavaObjectRef,
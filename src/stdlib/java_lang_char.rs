@@ -4,16 +4,25 @@ use crate::stdlib::java_lang_string::JavaLangStringRef;
 use crate::Isolate;
 
 pub fn new_compiled_class() -> CompiledClass {
-    let mut c = CompiledClass::new("java/lang/Char", None);
+    let mut c = CompiledClass::new("java/lang/Character", None);
     c.virtual_methods.push(VirtualMethodInfo {
-        symbol: "java/lang/Object.toString:()Ljava/lang/String;@java/lang/Char".to_string(),
+        symbol: "java/lang/Object.toString:()Ljava/lang/String;@java/lang/Character".to_string(),
         ptr: Some(JavaLangChar::java_lang_object_to_string as *const u8),
         overrides: Some("java/lang/Object.toString:()Ljava/lang/String;".to_string()),
     });
     c.static_methods.push(StaticMethodInfo {
-        symbol: "java/lang/Char.init:(C)V".to_string(),
+        symbol: "java/lang/Character.init:(C)V".to_string(),
         ptr: Some(JavaLangChar::init as *const u8),
     });
+    c.static_methods.push(StaticMethodInfo {
+        symbol: "java/lang/Character.valueOf:(C)Ljava/lang/Character;".to_string(),
+        ptr: Some(JavaLangChar::value_of as *const u8),
+    });
+    c.virtual_methods.push(VirtualMethodInfo {
+        symbol: "java/lang/Character.charValue:()C".to_string(),
+        ptr: Some(JavaLangChar::char_value as *const u8),
+        overrides: None,
+    });
     c.instance_size = std::mem::size_of::<JavaLangChar>() as u32;
     c
 }
@@ -21,13 +30,32 @@ pub fn new_compiled_class() -> CompiledClass {
 #[repr(C)]
 pub struct JavaLangChar {
     vtable: *const u8,
-    value: char,
+    /// The UTF-16 code unit, which may be a half of a surrogate pair.
+    value: u32,
 }
 
 impl JavaLangChar {
     pub unsafe extern "C" fn init(&mut self, v: u32) {
-        let c = char::from_u32(v).unwrap();
-        self.value = c;
+        self.value = v;
+    }
+
+    /// Boxes the char, which javac emits as Character.valueOf(char).
+    pub extern "C" fn value_of(isolate: &mut Isolate, v: u32) -> JavaObjectRef {
+        let class_id = isolate.stdlib_class_id("java/lang/Character");
+        let obj = Isolate::new_instance(isolate, class_id);
+        unsafe {
+            (*(obj as *mut JavaLangChar)).init(v);
+        }
+        obj
+    }
+
+    /// Unboxes the char, which javac emits as Character.charValue().
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be a non-null reference to a Character.
+    pub unsafe extern "C" fn char_value(_: &mut Isolate, ptr: JavaObjectRef) -> u32 {
+        (*(ptr as *const JavaLangChar)).value
     }
 
     pub unsafe extern "C" fn java_lang_object_to_string(
@@ -35,7 +63,7 @@ impl JavaLangChar {
         ptr: JavaObjectRef,
     ) -> JavaLangStringRef {
         let byte = &*(ptr as *const JavaLangChar);
-        let s = byte.value.to_string();
+        let s = String::from_utf16_lossy(&[byte.value as u16]);
         isolate.new_java_string(&s) as JavaLangStringRef
     }
 }
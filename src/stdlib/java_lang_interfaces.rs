@@ -0,0 +1,23 @@
+use crate::compiled_class::{CompiledClass, VirtualMethodInfo};
+
+pub fn new_compiled_classes() -> Vec<CompiledClass> {
+    vec![
+        new_interface("java/lang/Runnable", &["run:()V"]),
+        new_interface("java/lang/Comparable", &["compareTo:(Ljava/lang/Object;)I"]),
+    ]
+}
+
+pub fn new_interface(name: &str, methods: &[&str]) -> CompiledClass {
+    let mut c = CompiledClass::new(name, None);
+    c.is_interface = true;
+    for method in methods {
+        // Abstract methods have no implementation.
+        c.virtual_methods.push(VirtualMethodInfo {
+            symbol: format!("{}.{}", name, method),
+            ptr: None,
+            overrides: None,
+        });
+    }
+    c.instance_size = 0;
+    c
+}
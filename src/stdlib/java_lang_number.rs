@@ -1,4 +1,5 @@
 use crate::compiled_class::{CompiledClass, StaticMethodInfo, VirtualMethodInfo};
+use crate::stdlib::java_io::{format_double_java_style, format_float_java_style};
 use crate::stdlib::java_lang_object::JavaObjectRef;
 use crate::stdlib::java_lang_string::JavaLangStringRef;
 use crate::Isolate;
@@ -14,6 +15,15 @@ pub fn new_compiled_class_java_lang_byte() -> CompiledClass {
         symbol: "java/lang/Byte.init:(B)V".to_string(),
         ptr: Some(JavaLangByte::init as *const u8),
     });
+    c.static_methods.push(StaticMethodInfo {
+        symbol: "java/lang/Byte.valueOf:(B)Ljava/lang/Byte;".to_string(),
+        ptr: Some(JavaLangByte::value_of as *const u8),
+    });
+    c.virtual_methods.push(VirtualMethodInfo {
+        symbol: "java/lang/Byte.byteValue:()B".to_string(),
+        ptr: Some(JavaLangByte::byte_value as *const u8),
+        overrides: None,
+    });
     c.instance_size = std::mem::size_of::<JavaLangByte>() as u32;
     c
 }
@@ -29,6 +39,15 @@ pub fn new_compiled_class_java_lang_short() -> CompiledClass {
         symbol: "java/lang/Short.init:(S)V".to_string(),
         ptr: Some(JavaLangShort::init as *const u8),
     });
+    c.static_methods.push(StaticMethodInfo {
+        symbol: "java/lang/Short.valueOf:(S)Ljava/lang/Short;".to_string(),
+        ptr: Some(JavaLangShort::value_of as *const u8),
+    });
+    c.virtual_methods.push(VirtualMethodInfo {
+        symbol: "java/lang/Short.shortValue:()S".to_string(),
+        ptr: Some(JavaLangShort::short_value as *const u8),
+        overrides: None,
+    });
     c.instance_size = std::mem::size_of::<JavaLangShort>() as u32;
     c
 }
@@ -44,6 +63,15 @@ pub fn new_compiled_class_java_lang_integer() -> CompiledClass {
         symbol: "java/lang/Integer.init:(I)V".to_string(),
         ptr: Some(JavaLangInteger::init as *const u8),
     });
+    c.static_methods.push(StaticMethodInfo {
+        symbol: "java/lang/Integer.valueOf:(I)Ljava/lang/Integer;".to_string(),
+        ptr: Some(JavaLangInteger::value_of as *const u8),
+    });
+    c.virtual_methods.push(VirtualMethodInfo {
+        symbol: "java/lang/Integer.intValue:()I".to_string(),
+        ptr: Some(JavaLangInteger::int_value as *const u8),
+        overrides: None,
+    });
     c.instance_size = std::mem::size_of::<JavaLangInteger>() as u32;
     c
 }
@@ -59,6 +87,15 @@ pub fn new_compiled_class_java_lang_long() -> CompiledClass {
         symbol: "java/lang/Long.init:(J)V".to_string(),
         ptr: Some(JavaLangLong::init as *const u8),
     });
+    c.static_methods.push(StaticMethodInfo {
+        symbol: "java/lang/Long.valueOf:(J)Ljava/lang/Long;".to_string(),
+        ptr: Some(JavaLangLong::value_of as *const u8),
+    });
+    c.virtual_methods.push(VirtualMethodInfo {
+        symbol: "java/lang/Long.longValue:()J".to_string(),
+        ptr: Some(JavaLangLong::long_value as *const u8),
+        overrides: None,
+    });
     c.instance_size = std::mem::size_of::<JavaLangLong>() as u32;
     c
 }
@@ -74,6 +111,15 @@ pub fn new_compiled_class_java_lang_float() -> CompiledClass {
         symbol: "java/lang/Float.init:(F)V".to_string(),
         ptr: Some(JavaLangFloat::init as *const u8),
     });
+    c.static_methods.push(StaticMethodInfo {
+        symbol: "java/lang/Float.valueOf:(F)Ljava/lang/Float;".to_string(),
+        ptr: Some(JavaLangFloat::value_of as *const u8),
+    });
+    c.virtual_methods.push(VirtualMethodInfo {
+        symbol: "java/lang/Float.floatValue:()F".to_string(),
+        ptr: Some(JavaLangFloat::float_value as *const u8),
+        overrides: None,
+    });
     c.instance_size = std::mem::size_of::<JavaLangFloat>() as u32;
     c
 }
@@ -89,6 +135,15 @@ pub fn new_compiled_class_java_lang_double() -> CompiledClass {
         symbol: "java/lang/Double.init:(D)V".to_string(),
         ptr: Some(JavaLangDouble::init as *const u8),
     });
+    c.static_methods.push(StaticMethodInfo {
+        symbol: "java/lang/Double.valueOf:(D)Ljava/lang/Double;".to_string(),
+        ptr: Some(JavaLangDouble::value_of as *const u8),
+    });
+    c.virtual_methods.push(VirtualMethodInfo {
+        symbol: "java/lang/Double.doubleValue:()D".to_string(),
+        ptr: Some(JavaLangDouble::double_value as *const u8),
+        overrides: None,
+    });
     c.instance_size = std::mem::size_of::<JavaLangDouble>() as u32;
     c
 }
@@ -104,6 +159,25 @@ impl JavaLangByte {
         self.value = v;
     }
 
+    /// Boxes the byte, which javac emits as Byte.valueOf(byte).
+    pub extern "C" fn value_of(isolate: &mut Isolate, v: i32) -> JavaObjectRef {
+        let class_id = isolate.stdlib_class_id("java/lang/Byte");
+        let obj = Isolate::new_instance(isolate, class_id);
+        unsafe {
+            (*(obj as *mut JavaLangByte)).init(v as i8);
+        }
+        obj
+    }
+
+    /// Unboxes the byte, which javac emits as Byte.byteValue().
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be a non-null reference to a Byte.
+    pub unsafe extern "C" fn byte_value(_: &mut Isolate, ptr: JavaObjectRef) -> i32 {
+        (*(ptr as *const JavaLangByte)).value as i32
+    }
+
     pub unsafe extern "C" fn java_lang_object_to_string(
         isolate: &mut Isolate,
         ptr: JavaObjectRef,
@@ -121,8 +195,27 @@ pub struct JavaLangShort {
 }
 
 impl JavaLangShort {
-    pub unsafe extern "C" fn init(&mut self, v: i8) {
-        self.value = v as i16;
+    pub unsafe extern "C" fn init(&mut self, v: i16) {
+        self.value = v;
+    }
+
+    /// Boxes the short, which javac emits as Short.valueOf(short).
+    pub extern "C" fn value_of(isolate: &mut Isolate, v: i32) -> JavaObjectRef {
+        let class_id = isolate.stdlib_class_id("java/lang/Short");
+        let obj = Isolate::new_instance(isolate, class_id);
+        unsafe {
+            (*(obj as *mut JavaLangShort)).init(v as i16);
+        }
+        obj
+    }
+
+    /// Unboxes the short, which javac emits as Short.shortValue().
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be a non-null reference to a Short.
+    pub unsafe extern "C" fn short_value(_: &mut Isolate, ptr: JavaObjectRef) -> i32 {
+        (*(ptr as *const JavaLangShort)).value as i32
     }
 
     pub unsafe extern "C" fn java_lang_object_to_string(
@@ -142,8 +235,27 @@ pub struct JavaLangInteger {
 }
 
 impl JavaLangInteger {
-    pub unsafe extern "C" fn init(&mut self, v: i8) {
-        self.value = v as i32;
+    pub unsafe extern "C" fn init(&mut self, v: i32) {
+        self.value = v;
+    }
+
+    /// Boxes the int, which javac emits as Integer.valueOf(int).
+    pub extern "C" fn value_of(isolate: &mut Isolate, v: i32) -> JavaObjectRef {
+        let class_id = isolate.stdlib_class_id("java/lang/Integer");
+        let obj = Isolate::new_instance(isolate, class_id);
+        unsafe {
+            (*(obj as *mut JavaLangInteger)).init(v);
+        }
+        obj
+    }
+
+    /// Unboxes the int, which javac emits as Integer.intValue().
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be a non-null reference to a Integer.
+    pub unsafe extern "C" fn int_value(_: &mut Isolate, ptr: JavaObjectRef) -> i32 {
+        (*(ptr as *const JavaLangInteger)).value
     }
 
     pub unsafe extern "C" fn java_lang_object_to_string(
@@ -163,8 +275,27 @@ pub struct JavaLangLong {
 }
 
 impl JavaLangLong {
-    pub unsafe extern "C" fn init(&mut self, v: i8) {
-        self.value = v as i64;
+    pub unsafe extern "C" fn init(&mut self, v: i64) {
+        self.value = v;
+    }
+
+    /// Boxes the long, which javac emits as Long.valueOf(long).
+    pub extern "C" fn value_of(isolate: &mut Isolate, v: i64) -> JavaObjectRef {
+        let class_id = isolate.stdlib_class_id("java/lang/Long");
+        let obj = Isolate::new_instance(isolate, class_id);
+        unsafe {
+            (*(obj as *mut JavaLangLong)).init(v);
+        }
+        obj
+    }
+
+    /// Unboxes the long, which javac emits as Long.longValue().
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be a non-null reference to a Long.
+    pub unsafe extern "C" fn long_value(_: &mut Isolate, ptr: JavaObjectRef) -> i64 {
+        (*(ptr as *const JavaLangLong)).value
     }
 
     pub unsafe extern "C" fn java_lang_object_to_string(
@@ -188,12 +319,31 @@ impl JavaLangFloat {
         self.value = v;
     }
 
+    /// Boxes the float, which javac emits as Float.valueOf(float).
+    pub extern "C" fn value_of(isolate: &mut Isolate, v: f32) -> JavaObjectRef {
+        let class_id = isolate.stdlib_class_id("java/lang/Float");
+        let obj = Isolate::new_instance(isolate, class_id);
+        unsafe {
+            (*(obj as *mut JavaLangFloat)).init(v);
+        }
+        obj
+    }
+
+    /// Unboxes the float, which javac emits as Float.floatValue().
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be a non-null reference to a Float.
+    pub unsafe extern "C" fn float_value(_: &mut Isolate, ptr: JavaObjectRef) -> f32 {
+        (*(ptr as *const JavaLangFloat)).value
+    }
+
     pub unsafe extern "C" fn java_lang_object_to_string(
         isolate: &mut Isolate,
         ptr: JavaObjectRef,
     ) -> JavaLangStringRef {
         let float = &*(ptr as *const JavaLangFloat);
-        let s = float.value.to_string();
+        let s = format_float_java_style(float.value);
         isolate.new_java_string(&s) as JavaLangStringRef
     }
 }
@@ -209,12 +359,31 @@ impl JavaLangDouble {
         self.value = v;
     }
 
+    /// Boxes the double, which javac emits as Double.valueOf(double).
+    pub extern "C" fn value_of(isolate: &mut Isolate, v: f64) -> JavaObjectRef {
+        let class_id = isolate.stdlib_class_id("java/lang/Double");
+        let obj = Isolate::new_instance(isolate, class_id);
+        unsafe {
+            (*(obj as *mut JavaLangDouble)).init(v);
+        }
+        obj
+    }
+
+    /// Unboxes the double, which javac emits as Double.doubleValue().
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be a non-null reference to a Double.
+    pub unsafe extern "C" fn double_value(_: &mut Isolate, ptr: JavaObjectRef) -> f64 {
+        (*(ptr as *const JavaLangDouble)).value
+    }
+
     pub unsafe extern "C" fn java_lang_object_to_string(
         isolate: &mut Isolate,
         ptr: JavaObjectRef,
     ) -> JavaLangStringRef {
         let double = &*(ptr as *const JavaLangDouble);
-        let s = double.value.to_string();
+        let s = format_double_java_style(double.value);
         isolate.new_java_string(&s) as JavaLangStringRef
     }
 }
@@ -1,4 +1,5 @@
-use crate::compiled_class::{CompiledClass, VirtualMethodInfo};
+use crate::compiled_class::{CompiledClass, StaticMethodInfo, VirtualMethodInfo};
+use crate::stdlib::java_io::{format_double_java_style, format_float_java_style};
 use crate::stdlib::java_lang_object::*;
 use crate::Isolate;
 
@@ -9,6 +10,10 @@ pub fn new_compiled_class() -> CompiledClass {
         ptr: Some(JavaLangString::java_lang_object_to_string as *const u8),
         overrides: Some("java/lang/Object.toString:()Ljava/lang/String;".to_string()),
     });
+    c.static_methods.push(StaticMethodInfo {
+        symbol: "java/lang/String.valueOf:(Ljava/lang/Object;)Ljava/lang/String;".to_string(),
+        ptr: Some(JavaLangString::value_of_object as *const u8),
+    });
     c.instance_size = std::mem::size_of::<JavaLangString>() as u32;
     c
 }
@@ -51,6 +56,75 @@ impl JavaLangString {
         }
     }
 
+    /// Implements the string concatenations bootstrapped by StringConcatFactory.makeConcatWithConstants.
+    /// Each \u{1} in the recipe is replaced with the next argument in `args`, whose type is given by the
+    /// character of `types` as in the field descriptors, where 'L' stands for any reference, and each \u{2}
+    /// with the next string in `constants`.
+    pub unsafe extern "C" fn concat_with_constants(
+        isolate: &mut Isolate,
+        recipe: JavaLangStringRef,
+        types: JavaLangStringRef,
+        args: *const u64,
+        constants: *const JavaLangStringRef,
+    ) -> JavaLangStringRef {
+        let recipe = (*recipe).as_str();
+        let types = (*types).as_bytes();
+        let args = std::slice::from_raw_parts(args, types.len());
+        let constants = std::slice::from_raw_parts(constants, recipe.matches('\u{2}').count());
+        // The chars are UTF-16 code units, which may be the halves of a surrogate pair.
+        let mut ret: Vec<u16> = Vec::new();
+        let mut args = types.iter().zip(args);
+        let mut constants = constants.iter();
+        for c in recipe.chars() {
+            if c == '\u{2}' {
+                ret.extend((**constants.next().unwrap()).as_str().encode_utf16());
+                continue;
+            }
+            if c != '\u{1}' {
+                ret.extend(c.encode_utf16(&mut [0; 2]).iter());
+                continue;
+            }
+            let (t, &arg) = args.next().unwrap();
+            match t {
+                b'Z' => ret.extend(if arg as i32 != 0 { "true" } else { "false" }.encode_utf16()),
+                b'C' => ret.push(arg as u16),
+                b'B' | b'S' | b'I' => ret.extend((arg as i32).to_string().encode_utf16()),
+                b'J' => ret.extend((arg as i64).to_string().encode_utf16()),
+                b'F' => {
+                    ret.extend(format_float_java_style(f32::from_bits(arg as u32)).encode_utf16())
+                }
+                b'D' => ret.extend(format_double_java_style(f64::from_bits(arg)).encode_utf16()),
+                b'L' if arg == 0 => ret.extend("null".encode_utf16()),
+                b'L' => {
+                    let s = to_java_string_ref(isolate, arg as JavaObjectRef);
+                    // The exception thrown by toString() is propagated by the caller.
+                    if isolate.has_pending_exception() {
+                        return std::ptr::null_mut();
+                    }
+                    if s.is_null() {
+                        ret.extend("null".encode_utf16());
+                    } else {
+                        ret.extend((*s).as_str().encode_utf16());
+                    }
+                }
+                t => unreachable!("{}", *t as char),
+            }
+        }
+        isolate.new_java_string(&String::from_utf16_lossy(&ret)) as JavaLangStringRef
+    }
+
+    /// Implements String.valueOf(Object), which javac emits before the string concatenations of non-String
+    /// references.
+    pub unsafe extern "C" fn value_of_object(
+        isolate: &mut Isolate,
+        obj: JavaObjectRef,
+    ) -> JavaLangStringRef {
+        if obj.is_null() {
+            return isolate.new_java_string(&"null".to_string()) as JavaLangStringRef;
+        }
+        to_java_string_ref(isolate, obj)
+    }
+
     pub unsafe extern "C" fn java_lang_object_to_string(
         _: &mut Isolate,
         ptr: JavaObjectRef,
@@ -5,14 +5,14 @@ use crate::Isolate;
 pub fn new_compiled_class() -> CompiledClass {
     let mut c = CompiledClass::new("java/lang/System", None);
     c.instance_size = 0;
-    c.static_fields.push("out".to_string());
+    c.add_static_field("out", "Ljava/io/PrintStream;");
     c.clinit = Some(clinit);
     c
 }
 
 pub extern "C" fn clinit(isolate: &mut Isolate) {
-    let self_class_id = isolate.class_id("java/lang/System");
-    let print_stream_class_id = isolate.class_id("java/io/PrintStream");
+    let self_class_id = isolate.stdlib_class_id("java/lang/System");
+    let print_stream_class_id = isolate.stdlib_class_id("java/io/PrintStream");
     let class_object = Isolate::get_class_object(isolate, self_class_id, false);
     let obj = Isolate::new_instance(isolate, print_stream_class_id);
     let print_stream = unsafe { &mut *(obj as *mut PrintStream) };
@@ -21,6 +21,7 @@ pub extern "C" fn clinit(isolate: &mut Isolate) {
         print_stream,
         Box::into_raw(Box::new(IsolateStdoutAsJavaIoOutStream)),
     );
+    // "out" is the only static field, which is at the beginning of the storage.
     unsafe { (*class_object).set_object(0, obj) };
 }
 
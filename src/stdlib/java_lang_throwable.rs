@@ -0,0 +1,196 @@
+use crate::compiled_class::{CompiledClass, SpecialMethodInfo, VirtualMethodInfo};
+use crate::stdlib::java_lang_object::JavaObjectRef;
+use crate::stdlib::java_lang_string::JavaLangStringRef;
+use crate::Isolate;
+use std::fmt;
+use std::ptr::null_mut;
+
+/// The Throwable hierarchy provided by the standard library: (class name, super class name).
+const THROWABLE_CLASSES: [(&str, &str); 18] = [
+    ("java/lang/Throwable", "java/lang/Object"),
+    ("java/lang/Exception", "java/lang/Throwable"),
+    ("java/lang/Error", "java/lang/Throwable"),
+    ("java/lang/LinkageError", "java/lang/Error"),
+    (
+        "java/lang/ExceptionInInitializerError",
+        "java/lang/LinkageError",
+    ),
+    ("java/lang/NoClassDefFoundError", "java/lang/LinkageError"),
+    (
+        "java/lang/IncompatibleClassChangeError",
+        "java/lang/LinkageError",
+    ),
+    (
+        "java/lang/AbstractMethodError",
+        "java/lang/IncompatibleClassChangeError",
+    ),
+    ("java/lang/RuntimeException", "java/lang/Exception"),
+    (
+        "java/lang/IllegalArgumentException",
+        "java/lang/RuntimeException",
+    ),
+    (
+        "java/lang/IllegalStateException",
+        "java/lang/RuntimeException",
+    ),
+    (
+        "java/lang/ArithmeticException",
+        "java/lang/RuntimeException",
+    ),
+    (
+        "java/lang/ArrayStoreException",
+        "java/lang/RuntimeException",
+    ),
+    ("java/lang/ClassCastException", "java/lang/RuntimeException"),
+    (
+        "java/lang/NullPointerException",
+        "java/lang/RuntimeException",
+    ),
+    (
+        "java/lang/NegativeArraySizeException",
+        "java/lang/RuntimeException",
+    ),
+    (
+        "java/lang/IndexOutOfBoundsException",
+        "java/lang/RuntimeException",
+    ),
+    (
+        "java/lang/ArrayIndexOutOfBoundsException",
+        "java/lang/IndexOutOfBoundsException",
+    ),
+];
+
+pub fn new_compiled_classes() -> Vec<CompiledClass> {
+    let mut ret = Vec::new();
+    for (class_name, super_class) in THROWABLE_CLASSES {
+        let mut c = CompiledClass::new(class_name, Some(super_class.to_string()));
+        // Constructors are resolved by the class named in the invokespecial instruction,
+        // so each class needs its own symbols even though they share the implementation.
+        c.special_methods.push(SpecialMethodInfo {
+            symbol: format!("{}.<init>:()V", class_name),
+            ptr: Some(JavaLangThrowable::init as *const u8),
+        });
+        c.special_methods.push(SpecialMethodInfo {
+            symbol: format!("{}.<init>:(Ljava/lang/String;)V", class_name),
+            ptr: Some(JavaLangThrowable::init_with_message as *const u8),
+        });
+        if class_name == "java/lang/Throwable" {
+            c.virtual_methods.push(VirtualMethodInfo {
+                symbol: "java/lang/Throwable.getMessage:()Ljava/lang/String;".to_string(),
+                ptr: Some(JavaLangThrowable::get_message as *const u8),
+                overrides: None,
+            });
+            c.virtual_methods.push(VirtualMethodInfo {
+                symbol: "java/lang/Throwable.getCause:()Ljava/lang/Throwable;".to_string(),
+                ptr: Some(JavaLangThrowable::get_cause as *const u8),
+                overrides: None,
+            });
+            c.virtual_methods.push(VirtualMethodInfo {
+                symbol: "java/lang/Object.toString:()Ljava/lang/String;@java/lang/Throwable"
+                    .to_string(),
+                ptr: Some(JavaLangThrowable::java_lang_object_to_string as *const u8),
+                overrides: Some("java/lang/Object.toString:()Ljava/lang/String;".to_string()),
+            });
+        }
+        c.instance_size = std::mem::size_of::<JavaLangThrowable>() as u32;
+        ret.push(c);
+    }
+    ret
+}
+
+#[repr(C)]
+/// Corresponds to Ljava/lang/Throwable, and is the common layout of all its subclasses.
+/// The instance fields of user-defined exceptions are placed right after this.
+pub struct JavaLangThrowable {
+    vtable: *const u8,
+    pub message: JavaLangStringRef,
+    pub cause: JavaLangThrowableRef,
+}
+
+pub type JavaLangThrowableRef = *mut JavaLangThrowable;
+
+impl JavaLangThrowable {
+    pub unsafe extern "C" fn init(_isolate: &mut Isolate, ptr: JavaLangThrowableRef) {
+        (*ptr).message = null_mut();
+        (*ptr).cause = null_mut();
+    }
+
+    pub unsafe extern "C" fn init_with_message(
+        _isolate: &mut Isolate,
+        ptr: JavaLangThrowableRef,
+        message: JavaLangStringRef,
+    ) {
+        (*ptr).message = message;
+        (*ptr).cause = null_mut();
+    }
+
+    /// Initializes the exception wrapping the cause with no message, e.g. ExceptionInInitializerError.
+    pub unsafe fn init_with_cause(ptr: JavaLangThrowableRef, cause: JavaLangThrowableRef) {
+        (*ptr).message = null_mut();
+        (*ptr).cause = cause;
+    }
+
+    pub unsafe extern "C" fn get_message(
+        _isolate: &mut Isolate,
+        ptr: JavaLangThrowableRef,
+    ) -> JavaLangStringRef {
+        (*ptr).message
+    }
+
+    pub unsafe extern "C" fn get_cause(
+        _isolate: &mut Isolate,
+        ptr: JavaLangThrowableRef,
+    ) -> JavaLangThrowableRef {
+        (*ptr).cause
+    }
+
+    pub fn message(&self) -> Option<String> {
+        if self.message.is_null() {
+            None
+        } else {
+            Some(unsafe { (*self.message).as_str().to_string() })
+        }
+    }
+
+    pub unsafe extern "C" fn java_lang_object_to_string(
+        isolate: &mut Isolate,
+        ptr: JavaObjectRef,
+    ) -> JavaLangStringRef {
+        let s = JavaException::new(isolate, ptr).to_string();
+        isolate.new_java_string(&s) as JavaLangStringRef
+    }
+}
+
+/// The Java exception which was thrown but not caught by any Java code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JavaException {
+    /// The internal name of the exception class, e.g. "java/lang/RuntimeException".
+    pub class_name: String,
+    pub message: Option<String>,
+}
+
+impl JavaException {
+    /// # Safety
+    ///
+    /// `exception` must be a non-null reference to a Throwable allocated by the isolate.
+    pub unsafe fn new(isolate: &Isolate, exception: JavaObjectRef) -> Self {
+        let throwable = &*(exception as JavaLangThrowableRef);
+        Self {
+            class_name: isolate.class_name_of(exception).to_string(),
+            message: throwable.message(),
+        }
+    }
+}
+
+impl fmt::Display for JavaException {
+    /// Formats the same way as Throwable.toString does.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let class_name = self.class_name.replace('/', ".");
+        match &self.message {
+            Some(message) => write!(f, "{}: {}", class_name, message),
+            None => write!(f, "{}", class_name),
+        }
+    }
+}
+
+impl std::error::Error for JavaException {}
@@ -0,0 +1,36 @@
+use crate::compiled_class::{CompiledClass, StaticMethodInfo};
+use crate::stdlib::java_lang_interfaces::new_interface;
+use crate::stdlib::java_lang_object::JavaObjectRef;
+use crate::Isolate;
+
+pub fn new_compiled_classes() -> Vec<CompiledClass> {
+    vec![
+        new_interface(
+            "java/util/Comparator",
+            &["compare:(Ljava/lang/Object;Ljava/lang/Object;)I"],
+        ),
+        new_compiled_class_java_util_objects(),
+    ]
+}
+
+fn new_compiled_class_java_util_objects() -> CompiledClass {
+    let mut c = CompiledClass::new("java/util/Objects", None);
+    c.static_methods.push(StaticMethodInfo {
+        symbol: "java/util/Objects.requireNonNull:(Ljava/lang/Object;)Ljava/lang/Object;"
+            .to_string(),
+        ptr: Some(JavaUtilObjects::require_non_null as *const u8),
+    });
+    c
+}
+
+pub struct JavaUtilObjects;
+
+impl JavaUtilObjects {
+    /// javac invokes this on the receivers of the bound method references, e.g. `obj::method`.
+    pub extern "C" fn require_non_null(isolate: &mut Isolate, obj: JavaObjectRef) -> JavaObjectRef {
+        if obj.is_null() {
+            isolate.throw_new("java/lang/NullPointerException", None);
+        }
+        obj
+    }
+}
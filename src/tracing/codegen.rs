@@ -165,57 +165,55 @@ fn get_arg_or_return_as_pointer<'ctx>(
     ctx: &mut CodegenContext<'ctx>,
     arg_or_return: BasicValueEnum<'ctx>,
 ) -> PointerValue<'ctx> {
-    match field_type {
-        FieldType::ObjectType(_) | FieldType::ArrayType(_) => {
-            return arg_or_return.into_pointer_value()
-        }
-        _ => {}
-    };
+    if !matches!(field_type, FieldType::BaseType(_)) {
+        // The references, including the boxed primitives.
+        return arg_or_return.into_pointer_value();
+    }
 
     let (class_name, constructor_symbol, llvm_level_type, casted_arg_or_ret) = match field_type {
-        FieldType::BaseType(BaseType::Byte) | FieldType::ObjectTypeJavaLangByte => (
+        FieldType::BaseType(BaseType::Byte) => (
             "java/lang/Byte",
             "java/lang/Byte.init:(B)V",
             ctx.i32_type.as_basic_type_enum(),
             arg_or_return.as_basic_value_enum(),
         ),
-        FieldType::BaseType(BaseType::Char) | FieldType::ObjectTypeJavaLangChar => (
-            "java/lang/Char",
-            "java/lang/Char.init:(C)V",
+        FieldType::BaseType(BaseType::Char) => (
+            "java/lang/Character",
+            "java/lang/Character.init:(C)V",
             ctx.i32_type.as_basic_type_enum(),
             arg_or_return.as_basic_value_enum(),
         ),
-        FieldType::BaseType(BaseType::Double) | FieldType::ObjectTypeJavaLangDouble => (
+        FieldType::BaseType(BaseType::Double) => (
             "java/lang/Double",
             "java/lang/Double.init:(D)V",
             ctx.f64_type.as_basic_type_enum(),
             arg_or_return.as_basic_value_enum(),
         ),
-        FieldType::BaseType(BaseType::Float) | FieldType::ObjectTypeJavaLangFloat => (
+        FieldType::BaseType(BaseType::Float) => (
             "java/lang/Float",
             "java/lang/Float.init:(F)V",
             ctx.f32_type.as_basic_type_enum(),
             arg_or_return.as_basic_value_enum(),
         ),
-        FieldType::BaseType(BaseType::Int) | FieldType::ObjectTypeJavaLangInteger => (
+        FieldType::BaseType(BaseType::Int) => (
             "java/lang/Integer",
             "java/lang/Integer.init:(I)V",
             ctx.i32_type.as_basic_type_enum(),
             arg_or_return.as_basic_value_enum(),
         ),
-        FieldType::BaseType(BaseType::Long) | FieldType::ObjectTypeJavaLangLong => (
+        FieldType::BaseType(BaseType::Long) => (
             "java/lang/Long",
             "java/lang/Long.init:(J)V",
             ctx.i64_type.as_basic_type_enum(),
             arg_or_return.as_basic_value_enum(),
         ),
-        FieldType::BaseType(BaseType::Short) | FieldType::ObjectTypeJavaLangShort => (
+        FieldType::BaseType(BaseType::Short) => (
             "java/lang/Short",
             "java/lang/Short.init:(S)V",
             ctx.i16_type.as_basic_type_enum(),
             arg_or_return.as_basic_value_enum(),
         ),
-        FieldType::BaseType(BaseType::Boolean) | FieldType::ObjectTypeJavaLangBoolean => (
+        FieldType::BaseType(BaseType::Boolean) => (
             "java/lang/Boolean",
             "java/lang/Boolean.init:(Z)V",
             ctx.i32_type.as_basic_type_enum(),
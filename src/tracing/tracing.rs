@@ -6,9 +6,7 @@ use std::io::Write;
 
 #[no_mangle]
 pub unsafe extern "C" fn before(isolate: *mut Isolate, fn_symbol: JavaLangStringRef, arg_num: u32) {
-    let tracing_ctx = unsafe { (&mut *isolate).tracer() };
-    let fn_symbol = &(*fn_symbol);
-    tracing_ctx.before(unsafe { &mut *isolate }, fn_symbol, arg_num);
+    Tracer::before(&mut *isolate, &*fn_symbol, arg_num);
 }
 
 #[no_mangle]
@@ -17,9 +15,7 @@ pub unsafe extern "C" fn after(
     fn_symbol: JavaLangStringRef,
     ret: JavaObjectRef,
 ) {
-    let tracing_ctx = unsafe { (&mut *isolate).tracer() };
-    let fn_symbol = &(*fn_symbol);
-    tracing_ctx.after(unsafe { &mut *isolate }, fn_symbol, ret);
+    Tracer::after(&mut *isolate, &*fn_symbol, ret);
 }
 
 pub const MAX_TRACING_ARGS: usize = 20;
@@ -29,6 +25,8 @@ pub struct Tracer {
     args_result_vec: [JavaObjectRef; MAX_TRACING_ARGS],
     depth: usize,
     buf: Vec<u8>,
+    /// Set while calling toString of the traced values, which can be Java methods traced themselves.
+    rendering: bool,
 }
 
 impl Tracer {
@@ -37,6 +35,7 @@ impl Tracer {
             buf: Vec::new(),
             depth: 0,
             args_result_vec: [std::ptr::null_mut(); 20],
+            rendering: false,
         }
     }
 
@@ -44,57 +43,75 @@ impl Tracer {
         &self.buf
     }
 
-    pub fn before(&mut self, isolate: &mut Isolate, fn_symbol: &JavaLangString, arg_num: u32) {
+    /// Traces the call with its arguments. The tracer is only reached through the isolate rather than `&mut self`,
+    /// since rendering the values calls toString, which can be a Java method reaching the tracer again.
+    pub fn before(isolate: &mut Isolate, fn_symbol: &JavaLangString, arg_num: u32) {
+        let tracer = isolate.tracer();
+        if tracer.rendering {
+            return;
+        }
+        // Copied since rendering the arguments can call other methods which overwrite them.
+        let args = tracer.args_result_vec;
         // Repeat the character "-" for each level of depth.
-        let depth = self.depth;
-        for _ in 0..depth {
-            self.buf.write_all(b"\t").unwrap();
+        for _ in 0..tracer.depth {
+            tracer.buf.write_all(b"\t").unwrap();
         }
-        self.buf.write_all(b"--> \"").unwrap();
+        tracer.buf.write_all(b"--> \"").unwrap();
 
         // Then print the function name.
-        self.buf.write_all(fn_symbol.as_bytes()).unwrap();
-        self.buf.write_all(b"\" (").unwrap();
+        tracer.buf.write_all(fn_symbol.as_bytes()).unwrap();
+        tracer.buf.write_all(b"\" (").unwrap();
 
         let c = min(arg_num, 20);
         for i in 0..c {
-            let obj_ref = self.args_result_vec[i as usize];
-            let java_str = to_java_string_ref(isolate, obj_ref);
-            self.buf
-                .write_all(unsafe { (*java_str).as_bytes() })
-                .unwrap();
+            let obj_ref = args[i as usize];
+            Self::write_object(isolate, obj_ref);
             if i != c - 1 {
-                self.buf.write_all(b", ").unwrap();
+                isolate.tracer().buf.write_all(b", ").unwrap();
             }
         }
+        let tracer = isolate.tracer();
         if arg_num > 21 {
-            self.buf.write_all(b"...").unwrap();
+            tracer.buf.write_all(b"...").unwrap();
         }
-        self.buf.write_all(b")\n").unwrap();
-        self.depth += 1;
+        tracer.buf.write_all(b")\n").unwrap();
+        tracer.depth += 1;
     }
 
-    pub fn after(
-        &mut self,
-        isolate: &mut Isolate,
-        _fn_symbol: &JavaLangString,
-        ret: JavaObjectRef,
-    ) {
-        self.depth -= 1;
+    pub fn after(isolate: &mut Isolate, _fn_symbol: &JavaLangString, ret: JavaObjectRef) {
+        let tracer = isolate.tracer();
+        if tracer.rendering {
+            return;
+        }
+        tracer.depth -= 1;
         // Repeat the character "-" for each level of depth.
-        let depth = self.depth;
-        for _ in 0..depth {
-            self.buf.write_all(b"\t").unwrap();
+        for _ in 0..tracer.depth {
+            tracer.buf.write_all(b"\t").unwrap();
         }
-        self.buf.write_all(b"<-- ").unwrap();
+        tracer.buf.write_all(b"<-- ").unwrap();
         if ret.is_null() {
-            self.buf.write_all(b"void").unwrap();
+            tracer.buf.write_all(b"void").unwrap();
+        } else {
+            Self::write_object(isolate, ret);
+        }
+        isolate.tracer().buf.write_all(b"\n").unwrap();
+    }
+
+    /// Writes the string representation of the object, or "null" if either the object or its toString is null.
+    fn write_object(isolate: &mut Isolate, obj_ref: JavaObjectRef) {
+        let java_str = if obj_ref.is_null() {
+            std::ptr::null_mut()
+        } else {
+            isolate.tracer().rendering = true;
+            let java_str = to_java_string_ref(isolate, obj_ref);
+            isolate.tracer().rendering = false;
+            java_str
+        };
+        let buf = &mut isolate.tracer().buf;
+        if java_str.is_null() {
+            buf.write_all(b"null").unwrap();
         } else {
-            let java_str = to_java_string_ref(isolate, ret);
-            self.buf
-                .write_all(unsafe { (*java_str).as_bytes() })
-                .unwrap();
+            buf.write_all(unsafe { (*java_str).as_bytes() }).unwrap();
         }
-        self.buf.write_all(b"\n").unwrap();
     }
 }
@@ -0,0 +1,2100 @@
+use crate::codegen::codegen_class_stack_map::expand_stack_map_frames;
+use crate::codegen::descriptor::{
+    try_parse_field_type_descriptor, try_parse_method_descriptor, BaseType, FieldType,
+};
+use classfile_parser::attribute_info::{
+    bootstrap_methods_attribute_parser, code_attribute_parser, constant_value_attribute_parser,
+    stack_map_table_attribute_parser, AttributeInfo, CodeAttribute, VerificationTypeInfo,
+};
+use classfile_parser::code_attribute::{code_parser, Instruction};
+use classfile_parser::constant_info::ConstantInfo;
+use classfile_parser::method_info::{MethodAccessFlags, MethodInfo};
+use classfile_parser::ClassFile;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+
+/// The class file version which made the StackMapTable attribute mandatory (Java 6).
+const STACK_MAP_TABLE_VERSION: u16 = 50;
+/// The class file version which allowed invokestatic and invokespecial to refer to interface methods (Java 8).
+const INTERFACE_METHOD_REF_VERSION: u16 = 52;
+
+/// The error raised when a class file is malformed or its bytecode doesn't pass the type checking, like
+/// java.lang.VerifyError.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyError {
+    /// The internal name of the class, or an empty string if the constant pool is too broken to tell.
+    pub class_name: String,
+    /// The name and the descriptor of the method, e.g. "main([Ljava/lang/String;)V".
+    pub method: Option<String>,
+    /// The offset of the offending instruction in the code of the method.
+    pub offset: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        if !self.class_name.is_empty() {
+            write!(f, " (class: {}", self.class_name)?;
+            if let Some(method) = &self.method {
+                write!(f, ", method: {}", method)?;
+            }
+            if let Some(offset) = self.offset {
+                write!(f, ", offset: {}", offset)?;
+            }
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+/// The assignment of a reference type which the verifier can't check without the class hierarchy, i.e. the class
+/// `from` must be a subclass of the class `to` unless `to` is an interface (See 4.10.1.2). It is checked once all
+/// the classes are loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssignabilityConstraint {
+    pub from: String,
+    pub to: String,
+    /// The name and the descriptor of the method, e.g. "main([Ljava/lang/String;)V".
+    pub method: String,
+    /// The offset of the instruction making the assignment.
+    pub offset: usize,
+}
+
+impl AssignabilityConstraint {
+    /// Returns the error for the violation of the constraint in the class.
+    pub fn to_error(&self, class_name: &str) -> VerifyError {
+        VerifyError {
+            class_name: class_name.to_string(),
+            method: Some(self.method.clone()),
+            offset: Some(self.offset),
+            message: format!("{} is not assignable to {}", self.from, self.to),
+        }
+    }
+}
+
+type Result<T> = std::result::Result<T, String>;
+
+/// The pair of classes found by a method whose subclassing decides an assignment, as (offset, from, to).
+type SubclassConstraint = (usize, String, String);
+
+/// Verifies the class file before it is compiled (See 4.8 and 4.10). Besides the type checking of every method,
+/// this checks all the constant pool indices, descriptors and attributes which the compiler relies on.
+///
+/// The class hierarchy isn't loaded at this point, so the assignments between classes are returned as the
+/// constraints to be checked against the hierarchy later, and the access control is left to the linking. The class
+/// files before version 50 are rejected, as the types at their join points can't be inferred without the hierarchy.
+pub fn verify_class(
+    class_file: &ClassFile,
+) -> std::result::Result<Vec<AssignabilityConstraint>, VerifyError> {
+    let cp = ConstantPool(&class_file.const_pool);
+    let class_error = |class_name: &str, message: String| VerifyError {
+        class_name: class_name.to_string(),
+        method: None,
+        offset: None,
+        message,
+    };
+
+    cp.check_entries()
+        .map_err(|message| class_error("", message))?;
+    let class_name = cp
+        .class_name(class_file.this_class)
+        .map_err(|message| class_error("", message))?;
+    if class_file.major_version < STACK_MAP_TABLE_VERSION {
+        return Err(class_error(
+            class_name,
+            format!(
+                "unsupported class file version {} without stack map frames",
+                class_file.major_version
+            ),
+        ));
+    }
+    let context = ClassContext::new(&cp, class_file, class_name)
+        .map_err(|message| class_error(class_name, message))?;
+    let mut constraints = Vec::new();
+    for method in &class_file.methods {
+        let name = cp
+            .utf8(method.name_index)
+            .map_err(|message| class_error(class_name, message))?;
+        let descriptor = cp
+            .utf8(method.descriptor_index)
+            .map_err(|message| class_error(class_name, message))?;
+        let method_name = format!("{}{}", name, descriptor);
+        let method_constraints =
+            context
+                .verify_method(method, name, descriptor)
+                .map_err(|(offset, message)| VerifyError {
+                    class_name: class_name.to_string(),
+                    method: Some(method_name.clone()),
+                    offset,
+                    message,
+                })?;
+        constraints.extend(method_constraints.into_iter().map(|(offset, from, to)| {
+            AssignabilityConstraint {
+                from,
+                to,
+                method: method_name.clone(),
+                offset,
+            }
+        }));
+    }
+    Ok(constraints)
+}
+
+/// The constant pool, which is indexed from 1 as in the class file.
+struct ConstantPool<'a>(&'a [ConstantInfo]);
+
+impl<'a> ConstantPool<'a> {
+    fn get(&self, index: u16) -> Result<&'a ConstantInfo> {
+        match (index as usize).checked_sub(1).and_then(|i| self.0.get(i)) {
+            None | Some(ConstantInfo::Unusable) => {
+                Err(format!("invalid constant pool index {}", index))
+            }
+            Some(constant) => Ok(constant),
+        }
+    }
+
+    fn utf8(&self, index: u16) -> Result<&'a str> {
+        match self.get(index)? {
+            ConstantInfo::Utf8(utf8) => Ok(&utf8.utf8_string),
+            _ => Err(format!("constant pool entry {} isn't a Utf8", index)),
+        }
+    }
+
+    fn attribute_name(&self, attr_info: &AttributeInfo) -> Result<&'a str> {
+        self.utf8(attr_info.attribute_name_index)
+    }
+
+    /// Returns the internal name of the class, or the descriptor if it's an array class.
+    fn class_name(&self, index: u16) -> Result<&'a str> {
+        match self.get(index)? {
+            ConstantInfo::Class(class) => self.utf8(class.name_index),
+            _ => Err(format!("constant pool entry {} isn't a Class", index)),
+        }
+    }
+
+    fn name_and_type(&self, index: u16) -> Result<(&'a str, &'a str)> {
+        match self.get(index)? {
+            ConstantInfo::NameAndType(name_and_type) => Ok((
+                self.utf8(name_and_type.name_index)?,
+                self.utf8(name_and_type.descriptor_index)?,
+            )),
+            _ => Err(format!("constant pool entry {} isn't a NameAndType", index)),
+        }
+    }
+
+    /// Returns the class name, the name and the field type of the FieldRef.
+    fn field_ref(&self, index: u16) -> Result<(&'a str, &'a str, VType)> {
+        match self.get(index)? {
+            ConstantInfo::FieldRef(field_ref) => {
+                let (name, descriptor) = self.name_and_type(field_ref.name_and_type_index)?;
+                let field_type = parse_field_descriptor(descriptor)
+                    .ok_or_else(|| format!("invalid field descriptor {}", descriptor))?;
+                Ok((self.class_name(field_ref.class_index)?, name, field_type))
+            }
+            _ => Err(format!("constant pool entry {} isn't a Fieldref", index)),
+        }
+    }
+
+    /// Returns the class name, the name and the descriptor of the MethodRef or the InterfaceMethodRef, depending
+    /// on which kinds are allowed.
+    fn method_ref(
+        &self,
+        index: u16,
+        allow_class: bool,
+        allow_interface: bool,
+    ) -> Result<(&'a str, &'a str, &'a str)> {
+        let (class_index, name_and_type_index) = match self.get(index)? {
+            ConstantInfo::MethodRef(method_ref) if allow_class => {
+                (method_ref.class_index, method_ref.name_and_type_index)
+            }
+            ConstantInfo::InterfaceMethodRef(method_ref) if allow_interface => {
+                (method_ref.class_index, method_ref.name_and_type_index)
+            }
+            _ => return Err(format!("invalid method reference {}", index)),
+        };
+        let (name, descriptor) = self.name_and_type(name_and_type_index)?;
+        parse_method_descriptor(descriptor)
+            .ok_or_else(|| format!("invalid method descriptor {}", descriptor))?;
+        Ok((self.class_name(class_index)?, name, descriptor))
+    }
+
+    /// Returns the type of the constant pushed by ldc, ldc_w or ldc2_w.
+    fn loadable_constant(&self, index: u16) -> Result<VType> {
+        Ok(match self.get(index)? {
+            ConstantInfo::Integer(_) => VType::Int,
+            ConstantInfo::Float(_) => VType::Float,
+            ConstantInfo::Long(_) => VType::Long,
+            ConstantInfo::Double(_) => VType::Double,
+            ConstantInfo::String(_) => VType::reference("java/lang/String"),
+            ConstantInfo::Class(_) => VType::reference("java/lang/Class"),
+            ConstantInfo::MethodType(_) => VType::reference("java/lang/invoke/MethodType"),
+            ConstantInfo::MethodHandle(_) => VType::reference("java/lang/invoke/MethodHandle"),
+            _ => return Err(format!("constant pool entry {} isn't loadable", index)),
+        })
+    }
+
+    /// Checks that the entries refer to the entries of the right kinds (See 4.4).
+    fn check_entries(&self) -> Result<()> {
+        for constant in self.0 {
+            match constant {
+                ConstantInfo::Class(class) => {
+                    let class_name = self.utf8(class.name_index)?;
+                    let is_valid = if class_name.starts_with('[') {
+                        parse_field_descriptor(class_name).is_some()
+                    } else {
+                        !class_name.is_empty()
+                    };
+                    if !is_valid {
+                        return Err(format!("invalid class name {}", class_name));
+                    }
+                }
+                ConstantInfo::String(string) => {
+                    self.utf8(string.string_index)?;
+                }
+                ConstantInfo::FieldRef(field_ref) => {
+                    self.class_name(field_ref.class_index)?;
+                    let (_, descriptor) = self.name_and_type(field_ref.name_and_type_index)?;
+                    parse_field_descriptor(descriptor)
+                        .ok_or_else(|| format!("invalid field descriptor {}", descriptor))?;
+                }
+                ConstantInfo::MethodRef(method_ref) => {
+                    self.class_name(method_ref.class_index)?;
+                    self.check_method_name_and_type(method_ref.name_and_type_index)?;
+                }
+                ConstantInfo::InterfaceMethodRef(method_ref) => {
+                    self.class_name(method_ref.class_index)?;
+                    self.check_method_name_and_type(method_ref.name_and_type_index)?;
+                }
+                ConstantInfo::NameAndType(name_and_type) => {
+                    self.utf8(name_and_type.name_index)?;
+                    self.utf8(name_and_type.descriptor_index)?;
+                }
+                ConstantInfo::MethodHandle(method_handle) => {
+                    let reference = self.get(method_handle.reference_index)?;
+                    let valid = match method_handle.reference_kind {
+                        1..=4 => matches!(reference, ConstantInfo::FieldRef(_)),
+                        5 | 8 => matches!(reference, ConstantInfo::MethodRef(_)),
+                        6 | 7 => matches!(
+                            reference,
+                            ConstantInfo::MethodRef(_) | ConstantInfo::InterfaceMethodRef(_)
+                        ),
+                        9 => matches!(reference, ConstantInfo::InterfaceMethodRef(_)),
+                        _ => false,
+                    };
+                    if !valid {
+                        return Err(format!(
+                            "invalid method handle of kind {}",
+                            method_handle.reference_kind
+                        ));
+                    }
+                }
+                ConstantInfo::MethodType(method_type) => {
+                    let descriptor = self.utf8(method_type.descriptor_index)?;
+                    parse_method_descriptor(descriptor)
+                        .ok_or_else(|| format!("invalid method descriptor {}", descriptor))?;
+                }
+                ConstantInfo::InvokeDynamic(invoke_dynamic) => {
+                    self.check_method_name_and_type(invoke_dynamic.name_and_type_index)?;
+                }
+                ConstantInfo::Utf8(_)
+                | ConstantInfo::Integer(_)
+                | ConstantInfo::Float(_)
+                | ConstantInfo::Long(_)
+                | ConstantInfo::Double(_)
+                | ConstantInfo::Unusable => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn check_method_name_and_type(&self, index: u16) -> Result<()> {
+        let (name, descriptor) = self.name_and_type(index)?;
+        let (_, return_type) = parse_method_descriptor(descriptor)
+            .ok_or_else(|| format!("invalid method descriptor {}", descriptor))?;
+        if name.starts_with('<') && (name != "<init>" || return_type.is_some()) {
+            return Err(format!("invalid method reference {}{}", name, descriptor));
+        }
+        Ok(())
+    }
+}
+
+/// The verification types (See 4.10.1.2). References are identified by the internal names of the classes, or by
+/// the descriptors for arrays.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum VType {
+    Top,
+    Int,
+    Float,
+    Long,
+    Double,
+    Null,
+    UninitializedThis,
+    /// The object created by the new instruction at the offset, whose constructor isn't called yet.
+    Uninitialized(usize),
+    Reference(String),
+}
+
+impl VType {
+    fn reference(class_name: &str) -> Self {
+        VType::Reference(class_name.to_string())
+    }
+
+    fn from_field_type(field_type: &FieldType) -> Self {
+        match field_type {
+            FieldType::BaseType(BaseType::Float) => VType::Float,
+            FieldType::BaseType(BaseType::Long) => VType::Long,
+            FieldType::BaseType(BaseType::Double) => VType::Double,
+            // The types smaller than int are int on the operand stack.
+            FieldType::BaseType(_) => VType::Int,
+            FieldType::ArrayType(_) => VType::Reference(field_type.descriptor()),
+            object_type => VType::Reference(object_type.to_string()),
+        }
+    }
+
+    /// Returns true for the category 2 types, which take two slots.
+    fn is_wide(&self) -> bool {
+        matches!(self, VType::Long | VType::Double)
+    }
+
+    /// Returns true for the initialized references including null.
+    fn is_reference(&self) -> bool {
+        matches!(self, VType::Null | VType::Reference(_))
+    }
+
+    /// Returns true for any references including the uninitialized ones.
+    fn is_any_reference(&self) -> bool {
+        self.is_reference() || matches!(self, VType::UninitializedThis | VType::Uninitialized(_))
+    }
+
+    /// Returns whether the value is assignable to the type as far as it can be told without the class hierarchy.
+    fn is_assignable_to(&self, other: &VType) -> bool {
+        self == other
+            || match (self, other) {
+                (_, VType::Top) => true,
+                (VType::Null, VType::Reference(_)) => true,
+                (VType::Reference(from), VType::Reference(to)) => is_reference_assignable(from, to),
+                _ => false,
+            }
+    }
+
+    /// Returns the classes whose subclassing decides whether the value is assignable to the type.
+    fn subclass_constraint<'b>(&'b self, other: &'b VType) -> Option<(&'b str, &'b str)> {
+        match (self, other) {
+            (VType::Reference(from), VType::Reference(to)) => subclass_constraint(from, to),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the component type of the array type as a class name or an array descriptor, or None for the arrays of
+/// primitives and the classes.
+fn reference_component(array: &str) -> Option<&str> {
+    let component = array.strip_prefix('[')?;
+    if component.starts_with('[') {
+        Some(component)
+    } else {
+        component.strip_prefix('L')?.strip_suffix(';')
+    }
+}
+
+/// Returns whether the reference type is assignable to the other one unless the class hierarchy tells otherwise,
+/// where the types are class names or array descriptors. The arrays are assignable only to the arrays of assignable
+/// components and to the classes which all the arrays extend or implement.
+fn is_reference_assignable(from: &str, to: &str) -> bool {
+    if from == to || to == "java/lang/Object" {
+        return true;
+    }
+    match (from.starts_with('['), to.starts_with('[')) {
+        (true, true) => match (reference_component(from), reference_component(to)) {
+            (Some(from), Some(to)) => is_reference_assignable(from, to),
+            // The arrays of different primitives.
+            _ => false,
+        },
+        (true, false) => matches!(to, "java/lang/Cloneable" | "java/io/Serializable"),
+        (false, true) => false,
+        (false, false) => true,
+    }
+}
+
+/// Returns the classes whose subclassing decides whether the reference type is assignable to the other one, which
+/// is assumed to pass `is_reference_assignable`.
+fn subclass_constraint<'b>(from: &'b str, to: &'b str) -> Option<(&'b str, &'b str)> {
+    match (reference_component(from), reference_component(to)) {
+        (Some(from), Some(to)) => subclass_constraint(from, to),
+        _ if from == to
+            || to == "java/lang/Object"
+            || from.starts_with('[')
+            || to.starts_with('[') =>
+        {
+            None
+        }
+        _ => Some((from, to)),
+    }
+}
+
+impl fmt::Display for VType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VType::Top => write!(f, "top"),
+            VType::Int => write!(f, "int"),
+            VType::Float => write!(f, "float"),
+            VType::Long => write!(f, "long"),
+            VType::Double => write!(f, "double"),
+            VType::Null => write!(f, "null"),
+            VType::UninitializedThis => write!(f, "uninitializedThis"),
+            VType::Uninitialized(offset) => write!(f, "uninitialized({})", offset),
+            VType::Reference(class_name) => write!(f, "{}", class_name),
+        }
+    }
+}
+
+fn parse_field_descriptor(descriptor: &str) -> Option<VType> {
+    try_parse_field_type_descriptor(descriptor)
+        .map(|field_type| VType::from_field_type(&field_type))
+}
+
+/// Parses the method descriptor into the parameter types and the return type, which is None for void.
+fn parse_method_descriptor(descriptor: &str) -> Option<(Vec<VType>, Option<VType>)> {
+    let method_type = try_parse_method_descriptor(descriptor)?;
+    Some((
+        method_type
+            .parameter_types
+            .iter()
+            .map(VType::from_field_type)
+            .collect(),
+        method_type.return_type.as_ref().map(VType::from_field_type),
+    ))
+}
+
+/// Returns the number of slots taken by the types.
+fn slot_count(types: &[VType]) -> usize {
+    types.iter().map(|t| if t.is_wide() { 2 } else { 1 }).sum()
+}
+
+/// The types of the locals and the operand stack before an instruction. The locals always have max_locals slots,
+/// where the second slots of longs and doubles are top.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Frame {
+    locals: Vec<VType>,
+    stack: Vec<VType>,
+}
+
+impl Frame {
+    fn is_assignable_to(&self, other: &Frame) -> bool {
+        self.stack.len() == other.stack.len()
+            && self
+                .stack
+                .iter()
+                .zip(&other.stack)
+                .all(|(a, b)| a.is_assignable_to(b))
+            && self
+                .locals
+                .iter()
+                .zip(&other.locals)
+                .all(|(a, b)| a.is_assignable_to(b))
+    }
+
+    fn push(&mut self, v: VType) {
+        self.stack.push(v);
+    }
+
+    fn pop(&mut self) -> Result<VType> {
+        self.stack
+            .pop()
+            .ok_or_else(|| "operand stack underflow".to_string())
+    }
+
+    /// Pops a value which is assignable to the type.
+    fn pop_type(&mut self, expected: &VType) -> Result<VType> {
+        let v = self.pop()?;
+        if !v.is_assignable_to(expected) {
+            return Err(format!(
+                "expected {} on the operand stack, found {}",
+                expected, v
+            ));
+        }
+        Ok(v)
+    }
+
+    /// Pops an initialized reference.
+    fn pop_reference(&mut self) -> Result<VType> {
+        let v = self.pop()?;
+        if !v.is_reference() {
+            return Err(format!(
+                "expected a reference on the operand stack, found {}",
+                v
+            ));
+        }
+        Ok(v)
+    }
+
+    /// Pops any reference including the uninitialized ones.
+    fn pop_any_reference(&mut self) -> Result<VType> {
+        let v = self.pop()?;
+        if !v.is_any_reference() {
+            return Err(format!(
+                "expected a reference on the operand stack, found {}",
+                v
+            ));
+        }
+        Ok(v)
+    }
+
+    fn pop_category1(&mut self) -> Result<VType> {
+        let v = self.pop()?;
+        if v.is_wide() {
+            return Err(format!("expected a category 1 value, found {}", v));
+        }
+        Ok(v)
+    }
+
+    /// Pops an array reference, and returns the component type, or None if the array is null.
+    fn pop_array(&mut self) -> Result<Option<VType>> {
+        match self.pop()? {
+            VType::Null => Ok(None),
+            VType::Reference(array) if array.starts_with('[') => {
+                Ok(Some(parse_field_descriptor(&array[1..]).unwrap()))
+            }
+            v => Err(format!(
+                "expected an array on the operand stack, found {}",
+                v
+            )),
+        }
+    }
+
+    /// Pops an array whose component descriptor is one of the given ones.
+    fn pop_array_of(&mut self, components: &[&str]) -> Result<()> {
+        match self.pop()? {
+            VType::Null => Ok(()),
+            VType::Reference(array)
+                if components
+                    .iter()
+                    .any(|c| array.strip_prefix('[') == Some(*c)) =>
+            {
+                Ok(())
+            }
+            v => Err(format!(
+                "expected an array of {} on the operand stack, found {}",
+                components[0], v
+            )),
+        }
+    }
+
+    fn binary(&mut self, t: VType) -> Result<()> {
+        self.pop_type(&t)?;
+        self.pop_type(&t)?;
+        self.push(t);
+        Ok(())
+    }
+
+    fn unary(&mut self, from: VType, to: VType) -> Result<()> {
+        self.pop_type(&from)?;
+        self.push(to);
+        Ok(())
+    }
+
+    fn compare(&mut self, t: VType) -> Result<()> {
+        self.pop_type(&t)?;
+        self.pop_type(&t)?;
+        self.push(VType::Int);
+        Ok(())
+    }
+
+    fn load(&mut self, index: usize, expected: &VType) -> Result<()> {
+        let v = self.local(index, expected.is_wide())?;
+        let matches = match expected {
+            VType::Reference(_) => v.is_any_reference(),
+            _ => v == expected,
+        };
+        if !matches {
+            return Err(format!(
+                "expected {} in local {}, found {}",
+                expected, index, v
+            ));
+        }
+        self.push(v.clone());
+        Ok(())
+    }
+
+    fn store(&mut self, index: usize, expected: &VType) -> Result<()> {
+        let v = match expected {
+            VType::Reference(_) => self.pop_any_reference()?,
+            _ => self.pop_type(expected)?,
+        };
+        self.local(index, v.is_wide())?;
+        self.set_local(index, v);
+        Ok(())
+    }
+
+    fn local(&self, index: usize, is_wide: bool) -> Result<&VType> {
+        let width = if is_wide { 2 } else { 1 };
+        if index + width > self.locals.len() {
+            return Err(format!("invalid local variable index {}", index));
+        }
+        Ok(&self.locals[index])
+    }
+
+    fn set_local(&mut self, index: usize, v: VType) {
+        // Overwriting the second slot invalidates the long or the double before it.
+        if index > 0 && self.locals[index - 1].is_wide() {
+            self.locals[index - 1] = VType::Top;
+        }
+        if v.is_wide() {
+            self.locals[index + 1] = VType::Top;
+        }
+        self.locals[index] = v;
+    }
+
+    /// Replaces the uninitialized object with the initialized one after its constructor is called.
+    fn initialize(&mut self, uninitialized: &VType, initialized: VType) {
+        for v in self.locals.iter_mut().chain(self.stack.iter_mut()) {
+            if v == uninitialized {
+                *v = initialized.clone();
+            }
+        }
+    }
+}
+
+/// The successors of an instruction, other than the exception handlers.
+struct Successors {
+    falls_through: bool,
+    targets: Vec<usize>,
+}
+
+impl Successors {
+    fn next() -> Self {
+        Self {
+            falls_through: true,
+            targets: vec![],
+        }
+    }
+
+    fn branch(target: usize) -> Self {
+        Self {
+            falls_through: true,
+            targets: vec![target],
+        }
+    }
+
+    fn jump(targets: Vec<usize>) -> Self {
+        Self {
+            falls_through: false,
+            targets,
+        }
+    }
+}
+
+struct ClassContext<'a> {
+    cp: &'a ConstantPool<'a>,
+    class_name: &'a str,
+    super_class_name: Option<&'a str>,
+    major_version: u16,
+    bootstrap_method_count: usize,
+}
+
+impl<'a> ClassContext<'a> {
+    /// Checks the class level structures other than the methods.
+    fn new(cp: &'a ConstantPool<'a>, class_file: &ClassFile, class_name: &'a str) -> Result<Self> {
+        let super_class_name = if class_file.super_class == 0 {
+            if class_name != "java/lang/Object" {
+                return Err("missing super class".to_string());
+            }
+            None
+        } else {
+            Some(cp.class_name(class_file.super_class)?)
+        };
+        for interface in &class_file.interfaces {
+            cp.class_name(*interface)?;
+        }
+
+        let mut bootstrap_method_count = 0;
+        for attr_info in &class_file.attributes {
+            if cp.attribute_name(attr_info)? != "BootstrapMethods" {
+                continue;
+            }
+            let (_, attr) = bootstrap_methods_attribute_parser(&attr_info.info)
+                .map_err(|_| "malformed BootstrapMethods attribute".to_string())?;
+            for bootstrap_method in &attr.bootstrap_methods {
+                match cp.get(bootstrap_method.bootstrap_method_ref)? {
+                    ConstantInfo::MethodHandle(_) => {}
+                    _ => return Err("bootstrap method isn't a method handle".to_string()),
+                }
+                for argument in &bootstrap_method.bootstrap_arguments {
+                    cp.loadable_constant(*argument)?;
+                }
+            }
+            bootstrap_method_count = attr.bootstrap_methods.len();
+        }
+
+        for field in &class_file.fields {
+            let name = cp.utf8(field.name_index)?;
+            let descriptor = cp.utf8(field.descriptor_index)?;
+            let field_type = parse_field_descriptor(descriptor)
+                .ok_or_else(|| format!("invalid descriptor {} of field {}", descriptor, name))?;
+            for attr_info in &field.attributes {
+                if cp.attribute_name(attr_info)? != "ConstantValue" {
+                    continue;
+                }
+                let (_, attr) = constant_value_attribute_parser(&attr_info.info)
+                    .map_err(|_| format!("malformed ConstantValue attribute of field {}", name))?;
+                let constant_type = cp.loadable_constant(attr.constant_value_index)?;
+                if !constant_type.is_assignable_to(&field_type) {
+                    return Err(format!(
+                        "ConstantValue of type {} doesn't match field {}",
+                        constant_type, name
+                    ));
+                }
+            }
+        }
+
+        Ok(Self {
+            cp,
+            class_name,
+            super_class_name,
+            major_version: class_file.major_version,
+            bootstrap_method_count,
+        })
+    }
+
+    /// Verifies the method, and returns the subclass constraints, or the error with the offset of the instruction
+    /// if any.
+    fn verify_method(
+        &self,
+        method: &MethodInfo,
+        name: &str,
+        descriptor: &str,
+    ) -> std::result::Result<Vec<SubclassConstraint>, (Option<usize>, String)> {
+        let no_offset = |message| (None, message);
+        let cp = self.cp;
+        let (parameter_types, return_type) = parse_method_descriptor(descriptor)
+            .ok_or_else(|| (None, "invalid method descriptor".to_string()))?;
+        let is_static = method.access_flags.contains(MethodAccessFlags::STATIC);
+        let is_init = name == "<init>";
+        if slot_count(&parameter_types) + usize::from(!is_static) > 255 {
+            return Err((None, "too many parameters".to_string()));
+        }
+        if (is_init && return_type.is_some()) || (name == "<clinit>" && !descriptor.ends_with(")V"))
+        {
+            return Err((None, "initialization method must return void".to_string()));
+        }
+
+        let mut code_attr = None;
+        for attr_info in &method.attributes {
+            if cp.attribute_name(attr_info).map_err(no_offset)? == "Code" {
+                if code_attr.is_some() {
+                    return Err((None, "multiple Code attributes".to_string()));
+                }
+                let (_, attr) = code_attribute_parser(&attr_info.info)
+                    .map_err(|_| (None, "malformed Code attribute".to_string()))?;
+                code_attr = Some(attr);
+            }
+        }
+        let has_code = !method
+            .access_flags
+            .intersects(MethodAccessFlags::ABSTRACT | MethodAccessFlags::NATIVE);
+        let code_attr = match (code_attr, has_code) {
+            (Some(code_attr), true) => code_attr,
+            (None, false) => return Ok(Vec::new()),
+            (Some(_), false) => {
+                return Err((None, "abstract or native method has code".to_string()))
+            }
+            (None, true) => return Err((None, "missing Code attribute".to_string())),
+        };
+
+        let verifier = MethodVerifier::new(
+            self,
+            &code_attr,
+            is_static,
+            is_init,
+            parameter_types,
+            return_type,
+        )
+        .map_err(no_offset)?;
+        verifier.verify()
+    }
+}
+
+struct MethodVerifier<'a> {
+    class: &'a ClassContext<'a>,
+    is_init: bool,
+    return_type: Option<VType>,
+    max_stack: usize,
+    instructions: Vec<(usize, Instruction)>,
+    /// The index into the instructions by the offset.
+    instruction_index: HashMap<usize, usize>,
+    initial_frame: Frame,
+    /// The frames declared in the StackMapTable attribute by the offset.
+    stack_map_frames: HashMap<usize, Frame>,
+    /// The exception handlers as (start, end, handler, catch type).
+    handlers: Vec<(usize, usize, usize, VType)>,
+    /// The subclass constraints found so far.
+    constraints: RefCell<Vec<SubclassConstraint>>,
+}
+
+impl<'a> MethodVerifier<'a> {
+    fn new(
+        class: &'a ClassContext<'a>,
+        code_attr: &CodeAttribute,
+        is_static: bool,
+        is_init: bool,
+        parameter_types: Vec<VType>,
+        return_type: Option<VType>,
+    ) -> Result<Self> {
+        let code = &code_attr.code;
+        if code.is_empty() || code.len() > 65535 {
+            return Err(format!("invalid code length {}", code.len()));
+        }
+        let (rest, instructions) =
+            code_parser(code).map_err(|_| "malformed bytecode".to_string())?;
+        if !rest.is_empty() {
+            return Err(format!(
+                "malformed bytecode at offset {}",
+                code.len() - rest.len()
+            ));
+        }
+        let instruction_index = instructions
+            .iter()
+            .enumerate()
+            .map(|(i, (addr, _))| (*addr, i))
+            .collect();
+
+        // The locals of the implicit initial frame, where longs and doubles take a single entry.
+        let mut initial_locals = Vec::new();
+        if !is_static {
+            if is_init && class.class_name != "java/lang/Object" {
+                initial_locals.push(VType::UninitializedThis);
+            } else {
+                initial_locals.push(VType::reference(class.class_name));
+            }
+        }
+        initial_locals.extend(parameter_types);
+
+        let mut verifier = Self {
+            class,
+            is_init,
+            return_type,
+            max_stack: code_attr.max_stack as usize,
+            instructions,
+            instruction_index,
+            initial_frame: Frame {
+                locals: vec![],
+                stack: vec![],
+            },
+            stack_map_frames: HashMap::new(),
+            handlers: Vec::new(),
+            constraints: RefCell::new(Vec::new()),
+        };
+        verifier.initial_frame =
+            verifier.expand_frame(&initial_locals, vec![], code_attr.max_locals as usize)?;
+
+        for entry in &code_attr.exception_table {
+            let (start, end, handler) = (
+                entry.start_pc as usize,
+                entry.end_pc as usize,
+                entry.handler_pc as usize,
+            );
+            if start >= end
+                || !verifier.instruction_index.contains_key(&start)
+                || !(end == code.len() || verifier.instruction_index.contains_key(&end))
+                || !verifier.instruction_index.contains_key(&handler)
+            {
+                return Err(format!(
+                    "invalid exception handler range {}..{} -> {}",
+                    start, end, handler
+                ));
+            }
+            let catch_type = if entry.catch_type == 0 {
+                "java/lang/Throwable"
+            } else {
+                class.cp.class_name(entry.catch_type)?
+            };
+            let catch_type = VType::reference(catch_type);
+            verifier.require_assignable(
+                handler,
+                &catch_type,
+                &VType::reference("java/lang/Throwable"),
+            )?;
+            verifier.handlers.push((start, end, handler, catch_type));
+        }
+
+        for attr_info in &code_attr.attributes {
+            if class.cp.attribute_name(attr_info)? == "StackMapTable" {
+                verifier.stack_map_frames = verifier.expand_stack_map_frames(
+                    attr_info,
+                    initial_locals.clone(),
+                    code_attr.max_locals as usize,
+                )?;
+            }
+        }
+        Ok(verifier)
+    }
+
+    /// Expands the delta-encoded frames of the StackMapTable attribute (See 4.7.4).
+    fn expand_stack_map_frames(
+        &self,
+        attr_info: &AttributeInfo,
+        locals: Vec<VType>,
+        max_locals: usize,
+    ) -> Result<HashMap<usize, Frame>> {
+        // The parser reads the tags of the verification types without checking the length of the input, so parse
+        // with padding of invalid tags after the attribute, which fails the parser instead when it runs out of the
+        // attribute.
+        const PADDING: usize = 8;
+        let mut info = attr_info.info.clone();
+        info.extend([0xff; PADDING]);
+        let attr = match stack_map_table_attribute_parser(&info) {
+            Ok((rest, attr)) if rest.len() == PADDING => attr,
+            _ => return Err("malformed StackMapTable attribute".to_string()),
+        };
+        let entries = expand_stack_map_frames(&attr.entries, locals, |v| self.resolve(v))?;
+
+        let mut frames = HashMap::new();
+        for entry in entries {
+            if !self.instruction_index.contains_key(&entry.offset) {
+                return Err(format!(
+                    "stack map frame at {} isn't at an instruction",
+                    entry.offset
+                ));
+            }
+            let frame = self.expand_frame(&entry.locals, entry.stack, max_locals)?;
+            frames.insert(entry.offset, frame);
+        }
+        Ok(frames)
+    }
+
+    /// Builds the frame from the locals where longs and doubles take a single entry.
+    fn expand_frame(
+        &self,
+        locals: &[VType],
+        stack: Vec<VType>,
+        max_locals: usize,
+    ) -> Result<Frame> {
+        if slot_count(locals) > max_locals {
+            return Err("locals exceed max_locals".to_string());
+        }
+        if slot_count(&stack) > self.max_stack {
+            return Err("operand stack exceeds max_stack".to_string());
+        }
+        if stack.contains(&VType::Top) {
+            return Err("top on the operand stack".to_string());
+        }
+        let mut slots = Vec::with_capacity(max_locals);
+        for local in locals {
+            slots.push(local.clone());
+            if local.is_wide() {
+                slots.push(VType::Top);
+            }
+        }
+        slots.resize(max_locals, VType::Top);
+        Ok(Frame {
+            locals: slots,
+            stack,
+        })
+    }
+
+    fn resolve(&self, v: &VerificationTypeInfo) -> Result<VType> {
+        Ok(match v {
+            VerificationTypeInfo::Top => VType::Top,
+            VerificationTypeInfo::Integer => VType::Int,
+            VerificationTypeInfo::Float => VType::Float,
+            VerificationTypeInfo::Long => VType::Long,
+            VerificationTypeInfo::Double => VType::Double,
+            VerificationTypeInfo::Null => VType::Null,
+            VerificationTypeInfo::UninitializedThis => VType::UninitializedThis,
+            VerificationTypeInfo::Object { class } => {
+                VType::reference(self.class.cp.class_name(*class)?)
+            }
+            VerificationTypeInfo::Uninitialized { offset } => {
+                self.new_class_name(*offset as usize)?;
+                VType::Uninitialized(*offset as usize)
+            }
+        })
+    }
+
+    /// Returns the class name of the new instruction at the offset.
+    fn new_class_name(&self, offset: usize) -> Result<&'a str> {
+        match self
+            .instruction_index
+            .get(&offset)
+            .map(|i| &self.instructions[*i].1)
+        {
+            Some(Instruction::New(index)) => self.class.cp.class_name(*index),
+            _ => Err(format!("no new instruction at {}", offset)),
+        }
+    }
+
+    /// Runs the type checking over the code, following the control flow from the start and from every frame in
+    /// the StackMapTable attribute.
+    fn verify(&self) -> std::result::Result<Vec<SubclassConstraint>, (Option<usize>, String)> {
+        let mut states: HashMap<usize, Frame> = HashMap::new();
+        let mut worklist = Vec::new();
+        for (addr, frame) in &self.stack_map_frames {
+            states.insert(*addr, frame.clone());
+            worklist.push(*addr);
+        }
+        self.flow(
+            &mut states,
+            &mut worklist,
+            0,
+            0,
+            self.initial_frame.clone(),
+            false,
+        )
+        .map_err(|message| (Some(0), message))?;
+
+        while let Some(addr) = worklist.pop() {
+            let with_offset = |message| (Some(addr), message);
+            let mut frame = states[&addr].clone();
+            let index = self.instruction_index[&addr];
+            let locals_before = frame.locals.clone();
+            let successors = self
+                .execute(addr, &self.instructions[index].1, &mut frame)
+                .map_err(with_offset)?;
+            if slot_count(&frame.stack) > self.max_stack {
+                return Err(with_offset("operand stack overflow".to_string()));
+            }
+
+            for (start, end, handler, catch_type) in &self.handlers {
+                if (*start..*end).contains(&addr) {
+                    for locals in [&locals_before, &frame.locals] {
+                        let handler_frame = Frame {
+                            locals: locals.clone(),
+                            stack: vec![catch_type.clone()],
+                        };
+                        self.flow(
+                            &mut states,
+                            &mut worklist,
+                            addr,
+                            *handler,
+                            handler_frame,
+                            true,
+                        )
+                        .map_err(with_offset)?;
+                    }
+                }
+            }
+            for target in &successors.targets {
+                self.flow(
+                    &mut states,
+                    &mut worklist,
+                    addr,
+                    *target,
+                    frame.clone(),
+                    true,
+                )
+                .map_err(with_offset)?;
+            }
+            if successors.falls_through {
+                let Some((next, _)) = self.instructions.get(index + 1) else {
+                    return Err(with_offset("falling off the end of the code".to_string()));
+                };
+                self.flow(&mut states, &mut worklist, addr, *next, frame, false)
+                    .map_err(with_offset)?;
+            }
+        }
+
+        if let Some((addr, _)) = self
+            .instructions
+            .iter()
+            .find(|(addr, _)| !states.contains_key(addr))
+        {
+            return Err((
+                Some(*addr),
+                "no stack map frame for unreachable code".to_string(),
+            ));
+        }
+        Ok(self.constraints.take())
+    }
+
+    /// Passes the frame from the instruction at the address to the one at the target. The branch targets need to
+    /// have a stack map frame, which the frame must be assignable to.
+    fn flow(
+        &self,
+        states: &mut HashMap<usize, Frame>,
+        worklist: &mut Vec<usize>,
+        addr: usize,
+        target: usize,
+        frame: Frame,
+        is_branch: bool,
+    ) -> Result<()> {
+        if !self.instruction_index.contains_key(&target) {
+            return Err(format!("branch target {} isn't at an instruction", target));
+        }
+        if let Some(declared) = self.stack_map_frames.get(&target) {
+            if !frame.is_assignable_to(declared) {
+                return Err(format!("stack map frame at {} doesn't match", target));
+            }
+            let slots = (frame.stack.iter().zip(&declared.stack))
+                .chain(frame.locals.iter().zip(&declared.locals));
+            for (v, expected) in slots {
+                self.record_subclass_constraint(addr, v, expected);
+            }
+            return Ok(());
+        }
+        if is_branch {
+            return Err(format!(
+                "missing stack map frame at branch target {}",
+                target
+            ));
+        }
+        // Without a stack map frame, the instruction is only reached by falling through from the previous one.
+        states.insert(target, frame);
+        worklist.push(target);
+        Ok(())
+    }
+
+    fn target(&self, addr: usize, offset: i32) -> Result<usize> {
+        let target = addr as i64 + offset as i64;
+        if target < 0 || !self.instruction_index.contains_key(&(target as usize)) {
+            return Err(format!("branch target {} isn't at an instruction", target));
+        }
+        Ok(target as usize)
+    }
+
+    /// Records the constraint if the class hierarchy decides whether the value is assignable to the type.
+    fn record_subclass_constraint(&self, addr: usize, v: &VType, expected: &VType) {
+        if let Some((from, to)) = v.subclass_constraint(expected) {
+            let constraint = (addr, from.to_string(), to.to_string());
+            let mut constraints = self.constraints.borrow_mut();
+            if !constraints.contains(&constraint) {
+                constraints.push(constraint);
+            }
+        }
+    }
+
+    /// Checks that the value of the instruction at the address is assignable to the type, leaving the subclassing
+    /// to be checked against the class hierarchy.
+    fn require_assignable(&self, addr: usize, v: &VType, expected: &VType) -> Result<()> {
+        if !v.is_assignable_to(expected) {
+            return Err(format!(
+                "expected {} on the operand stack, found {}",
+                expected, v
+            ));
+        }
+        self.record_subclass_constraint(addr, v, expected);
+        Ok(())
+    }
+
+    /// Pops a value which is assignable to the type like `Frame::pop_type`, leaving the subclassing to be checked
+    /// against the class hierarchy.
+    fn pop_assignable(&self, addr: usize, frame: &mut Frame, expected: &VType) -> Result<VType> {
+        let v = frame.pop()?;
+        self.require_assignable(addr, &v, expected)?;
+        Ok(v)
+    }
+
+    /// Pops the arguments of the method, and returns the return type.
+    fn pop_arguments(
+        &self,
+        addr: usize,
+        frame: &mut Frame,
+        descriptor: &str,
+    ) -> Result<Option<VType>> {
+        let (parameter_types, return_type) = parse_method_descriptor(descriptor).unwrap();
+        for parameter_type in parameter_types.iter().rev() {
+            self.pop_assignable(addr, frame, parameter_type)?;
+        }
+        Ok(return_type)
+    }
+
+    fn push_return_value(frame: &mut Frame, return_type: Option<VType>) {
+        if let Some(return_type) = return_type {
+            frame.push(return_type);
+        }
+    }
+
+    fn check_not_initialization_method(name: &str) -> Result<()> {
+        if name.starts_with('<') {
+            return Err(format!("invalid invocation of {}", name));
+        }
+        Ok(())
+    }
+
+    fn check_return(
+        &self,
+        addr: usize,
+        frame: &mut Frame,
+        expected: Option<VType>,
+    ) -> Result<Successors> {
+        match (&self.return_type, &expected) {
+            (None, None) => {
+                if self.is_init && frame.locals.contains(&VType::UninitializedThis) {
+                    return Err("constructor returns before calling super() or this()".to_string());
+                }
+            }
+            (Some(return_type), Some(expected))
+                if expected == return_type
+                    || (expected.is_reference() && return_type.is_reference()) =>
+            {
+                self.pop_assignable(addr, frame, return_type)?;
+            }
+            _ => return Err("return type doesn't match the method descriptor".to_string()),
+        }
+        Ok(Successors::jump(vec![]))
+    }
+
+    /// Simulates the instruction on the frame, and returns the successors.
+    fn execute(
+        &self,
+        addr: usize,
+        instruction: &Instruction,
+        frame: &mut Frame,
+    ) -> Result<Successors> {
+        use VType::*;
+        let cp = self.class.cp;
+        let object = || VType::reference("java/lang/Object");
+        match instruction {
+            Instruction::Nop => {}
+            Instruction::Aconstnull => frame.push(Null),
+            Instruction::Iconstm1
+            | Instruction::Iconst0
+            | Instruction::Iconst1
+            | Instruction::Iconst2
+            | Instruction::Iconst3
+            | Instruction::Iconst4
+            | Instruction::Iconst5
+            | Instruction::Bipush(_)
+            | Instruction::Sipush(_) => frame.push(Int),
+            Instruction::Lconst0 | Instruction::Lconst1 => frame.push(Long),
+            Instruction::Fconst0 | Instruction::Fconst1 | Instruction::Fconst2 => frame.push(Float),
+            Instruction::Dconst0 | Instruction::Dconst1 => frame.push(Double),
+            Instruction::Ldc(index) => self.ldc(frame, *index as u16, false)?,
+            Instruction::LdcW(index) => self.ldc(frame, *index, false)?,
+            Instruction::Ldc2W(index) => self.ldc(frame, *index, true)?,
+
+            Instruction::Iload(index) => frame.load(*index as usize, &Int)?,
+            Instruction::IloadWide(index) => frame.load(*index as usize, &Int)?,
+            Instruction::Iload0 => frame.load(0, &Int)?,
+            Instruction::Iload1 => frame.load(1, &Int)?,
+            Instruction::Iload2 => frame.load(2, &Int)?,
+            Instruction::Iload3 => frame.load(3, &Int)?,
+            Instruction::Lload(index) => frame.load(*index as usize, &Long)?,
+            Instruction::LloadWide(index) => frame.load(*index as usize, &Long)?,
+            Instruction::Lload0 => frame.load(0, &Long)?,
+            Instruction::Lload1 => frame.load(1, &Long)?,
+            Instruction::Lload2 => frame.load(2, &Long)?,
+            Instruction::Lload3 => frame.load(3, &Long)?,
+            Instruction::Fload(index) => frame.load(*index as usize, &Float)?,
+            Instruction::FloadWide(index) => frame.load(*index as usize, &Float)?,
+            Instruction::Fload0 => frame.load(0, &Float)?,
+            Instruction::Fload1 => frame.load(1, &Float)?,
+            Instruction::Fload2 => frame.load(2, &Float)?,
+            Instruction::Fload3 => frame.load(3, &Float)?,
+            Instruction::Dload(index) => frame.load(*index as usize, &Double)?,
+            Instruction::DloadWide(index) => frame.load(*index as usize, &Double)?,
+            Instruction::Dload0 => frame.load(0, &Double)?,
+            Instruction::Dload1 => frame.load(1, &Double)?,
+            Instruction::Dload2 => frame.load(2, &Double)?,
+            Instruction::Dload3 => frame.load(3, &Double)?,
+            Instruction::Aload(index) => frame.load(*index as usize, &object())?,
+            Instruction::AloadWide(index) => frame.load(*index as usize, &object())?,
+            Instruction::Aload0 => frame.load(0, &object())?,
+            Instruction::Aload1 => frame.load(1, &object())?,
+            Instruction::Aload2 => frame.load(2, &object())?,
+            Instruction::Aload3 => frame.load(3, &object())?,
+
+            Instruction::Istore(index) => frame.store(*index as usize, &Int)?,
+            Instruction::IstoreWide(index) => frame.store(*index as usize, &Int)?,
+            Instruction::Istore0 => frame.store(0, &Int)?,
+            Instruction::Istore1 => frame.store(1, &Int)?,
+            Instruction::Istore2 => frame.store(2, &Int)?,
+            Instruction::Istore3 => frame.store(3, &Int)?,
+            Instruction::Lstore(index) => frame.store(*index as usize, &Long)?,
+            Instruction::LstoreWide(index) => frame.store(*index as usize, &Long)?,
+            Instruction::Lstore0 => frame.store(0, &Long)?,
+            Instruction::Lstore1 => frame.store(1, &Long)?,
+            Instruction::Lstore2 => frame.store(2, &Long)?,
+            Instruction::Lstore3 => frame.store(3, &Long)?,
+            Instruction::Fstore(index) => frame.store(*index as usize, &Float)?,
+            Instruction::FstoreWide(index) => frame.store(*index as usize, &Float)?,
+            Instruction::Fstore0 => frame.store(0, &Float)?,
+            Instruction::Fstore1 => frame.store(1, &Float)?,
+            Instruction::Fstore2 => frame.store(2, &Float)?,
+            Instruction::Fstore3 => frame.store(3, &Float)?,
+            Instruction::Dstore(index) => frame.store(*index as usize, &Double)?,
+            Instruction::DstoreWide(index) => frame.store(*index as usize, &Double)?,
+            Instruction::Dstore0 => frame.store(0, &Double)?,
+            Instruction::Dstore1 => frame.store(1, &Double)?,
+            Instruction::Dstore2 => frame.store(2, &Double)?,
+            Instruction::Dstore3 => frame.store(3, &Double)?,
+            Instruction::Astore(index) => frame.store(*index as usize, &object())?,
+            Instruction::AstoreWide(index) => frame.store(*index as usize, &object())?,
+            Instruction::Astore0 => frame.store(0, &object())?,
+            Instruction::Astore1 => frame.store(1, &object())?,
+            Instruction::Astore2 => frame.store(2, &object())?,
+            Instruction::Astore3 => frame.store(3, &object())?,
+            Instruction::Iinc { index, .. } => self.iinc(frame, *index as usize)?,
+            Instruction::IincWide { index, .. } => self.iinc(frame, *index as usize)?,
+
+            Instruction::Iaload
+            | Instruction::Baload
+            | Instruction::Caload
+            | Instruction::Saload => {
+                frame.pop_type(&Int)?;
+                frame.pop_array_of(match instruction {
+                    Instruction::Iaload => &["I"],
+                    Instruction::Baload => &["B", "Z"],
+                    Instruction::Caload => &["C"],
+                    _ => &["S"],
+                })?;
+                frame.push(Int);
+            }
+            Instruction::Laload => {
+                frame.pop_type(&Int)?;
+                frame.pop_array_of(&["J"])?;
+                frame.push(Long);
+            }
+            Instruction::Faload => {
+                frame.pop_type(&Int)?;
+                frame.pop_array_of(&["F"])?;
+                frame.push(Float);
+            }
+            Instruction::Daload => {
+                frame.pop_type(&Int)?;
+                frame.pop_array_of(&["D"])?;
+                frame.push(Double);
+            }
+            Instruction::Aaload => {
+                frame.pop_type(&Int)?;
+                match frame.pop_array()? {
+                    None => frame.push(Null),
+                    Some(component) if component.is_reference() => frame.push(component),
+                    Some(component) => {
+                        return Err(format!("aaload from an array of {}", component))
+                    }
+                }
+            }
+            Instruction::Iastore
+            | Instruction::Bastore
+            | Instruction::Castore
+            | Instruction::Sastore => {
+                frame.pop_type(&Int)?;
+                frame.pop_type(&Int)?;
+                frame.pop_array_of(match instruction {
+                    Instruction::Iastore => &["I"],
+                    Instruction::Bastore => &["B", "Z"],
+                    Instruction::Castore => &["C"],
+                    _ => &["S"],
+                })?;
+            }
+            Instruction::Lastore => {
+                frame.pop_type(&Long)?;
+                frame.pop_type(&Int)?;
+                frame.pop_array_of(&["J"])?;
+            }
+            Instruction::Fastore => {
+                frame.pop_type(&Float)?;
+                frame.pop_type(&Int)?;
+                frame.pop_array_of(&["F"])?;
+            }
+            Instruction::Dastore => {
+                frame.pop_type(&Double)?;
+                frame.pop_type(&Int)?;
+                frame.pop_array_of(&["D"])?;
+            }
+            Instruction::Aastore => {
+                frame.pop_reference()?;
+                frame.pop_type(&Int)?;
+                match frame.pop_array()? {
+                    Some(component) if !component.is_reference() => {
+                        return Err(format!("aastore into an array of {}", component))
+                    }
+                    _ => {}
+                }
+            }
+
+            Instruction::Pop => {
+                frame.pop_category1()?;
+            }
+            Instruction::Pop2 => {
+                if !frame.pop()?.is_wide() {
+                    frame.pop_category1()?;
+                }
+            }
+            Instruction::Dup => {
+                let v1 = frame.pop_category1()?;
+                frame.push(v1.clone());
+                frame.push(v1);
+            }
+            Instruction::Dupx1 => {
+                let v1 = frame.pop_category1()?;
+                let v2 = frame.pop_category1()?;
+                frame.stack.extend([v1.clone(), v2, v1]);
+            }
+            Instruction::Dupx2 => {
+                let v1 = frame.pop_category1()?;
+                let v2 = frame.pop()?;
+                if v2.is_wide() {
+                    frame.stack.extend([v1.clone(), v2, v1]);
+                } else {
+                    let v3 = frame.pop_category1()?;
+                    frame.stack.extend([v1.clone(), v3, v2, v1]);
+                }
+            }
+            Instruction::Dup2 => {
+                let v1 = frame.pop()?;
+                if v1.is_wide() {
+                    frame.stack.extend([v1.clone(), v1]);
+                } else {
+                    let v2 = frame.pop_category1()?;
+                    frame.stack.extend([v2.clone(), v1.clone(), v2, v1]);
+                }
+            }
+            Instruction::Dup2x1 => {
+                let v1 = frame.pop()?;
+                if v1.is_wide() {
+                    let v2 = frame.pop_category1()?;
+                    frame.stack.extend([v1.clone(), v2, v1]);
+                } else {
+                    let v2 = frame.pop_category1()?;
+                    let v3 = frame.pop_category1()?;
+                    frame.stack.extend([v2.clone(), v1.clone(), v3, v2, v1]);
+                }
+            }
+            Instruction::Dup2x2 => {
+                let v1 = frame.pop()?;
+                if v1.is_wide() {
+                    let v2 = frame.pop()?;
+                    if v2.is_wide() {
+                        frame.stack.extend([v1.clone(), v2, v1]);
+                    } else {
+                        let v3 = frame.pop_category1()?;
+                        frame.stack.extend([v1.clone(), v3, v2, v1]);
+                    }
+                } else {
+                    let v2 = frame.pop_category1()?;
+                    let v3 = frame.pop()?;
+                    if v3.is_wide() {
+                        frame.stack.extend([v2.clone(), v1.clone(), v3, v2, v1]);
+                    } else {
+                        let v4 = frame.pop_category1()?;
+                        frame.stack.extend([v2.clone(), v1.clone(), v4, v3, v2, v1]);
+                    }
+                }
+            }
+            Instruction::Swap => {
+                let v1 = frame.pop_category1()?;
+                let v2 = frame.pop_category1()?;
+                frame.stack.extend([v1, v2]);
+            }
+
+            Instruction::Iadd
+            | Instruction::Isub
+            | Instruction::Imul
+            | Instruction::Idiv
+            | Instruction::Irem
+            | Instruction::Iand
+            | Instruction::Ior
+            | Instruction::Ixor
+            | Instruction::Ishl
+            | Instruction::Ishr
+            | Instruction::Iushr => frame.binary(Int)?,
+            Instruction::Ladd
+            | Instruction::Lsub
+            | Instruction::Lmul
+            | Instruction::Ldiv
+            | Instruction::Lrem
+            | Instruction::Land
+            | Instruction::Lor
+            | Instruction::Lxor => frame.binary(Long)?,
+            Instruction::Lshl | Instruction::Lshr | Instruction::Lushr => {
+                frame.pop_type(&Int)?;
+                frame.unary(Long, Long)?;
+            }
+            Instruction::Fadd
+            | Instruction::Fsub
+            | Instruction::Fmul
+            | Instruction::Fdiv
+            | Instruction::Frem => frame.binary(Float)?,
+            Instruction::Dadd
+            | Instruction::Dsub
+            | Instruction::Dmul
+            | Instruction::Ddiv
+            | Instruction::Drem => frame.binary(Double)?,
+            Instruction::Ineg | Instruction::I2b | Instruction::I2c | Instruction::I2s => {
+                frame.unary(Int, Int)?
+            }
+            Instruction::Lneg => frame.unary(Long, Long)?,
+            Instruction::Fneg => frame.unary(Float, Float)?,
+            Instruction::Dneg => frame.unary(Double, Double)?,
+            Instruction::I2l => frame.unary(Int, Long)?,
+            Instruction::I2f => frame.unary(Int, Float)?,
+            Instruction::I2d => frame.unary(Int, Double)?,
+            Instruction::L2i => frame.unary(Long, Int)?,
+            Instruction::L2f => frame.unary(Long, Float)?,
+            Instruction::L2d => frame.unary(Long, Double)?,
+            Instruction::F2i => frame.unary(Float, Int)?,
+            Instruction::F2l => frame.unary(Float, Long)?,
+            Instruction::F2d => frame.unary(Float, Double)?,
+            Instruction::D2i => frame.unary(Double, Int)?,
+            Instruction::D2l => frame.unary(Double, Long)?,
+            Instruction::D2f => frame.unary(Double, Float)?,
+            Instruction::Lcmp => frame.compare(Long)?,
+            Instruction::Fcmpl | Instruction::Fcmpg => frame.compare(Float)?,
+            Instruction::Dcmpl | Instruction::Dcmpg => frame.compare(Double)?,
+
+            Instruction::Ifeq(offset)
+            | Instruction::Ifne(offset)
+            | Instruction::Iflt(offset)
+            | Instruction::Ifge(offset)
+            | Instruction::Ifgt(offset)
+            | Instruction::Ifle(offset) => {
+                frame.pop_type(&Int)?;
+                return Ok(Successors::branch(self.target(addr, *offset as i32)?));
+            }
+            Instruction::IfIcmpeq(offset)
+            | Instruction::IfIcmpne(offset)
+            | Instruction::IfIcmplt(offset)
+            | Instruction::IfIcmpge(offset)
+            | Instruction::IfIcmpgt(offset)
+            | Instruction::IfIcmple(offset) => {
+                frame.pop_type(&Int)?;
+                frame.pop_type(&Int)?;
+                return Ok(Successors::branch(self.target(addr, *offset as i32)?));
+            }
+            Instruction::IfAcmpeq(offset) | Instruction::IfAcmpne(offset) => {
+                frame.pop_any_reference()?;
+                frame.pop_any_reference()?;
+                return Ok(Successors::branch(self.target(addr, *offset as i32)?));
+            }
+            Instruction::Ifnull(offset) | Instruction::Ifnonnull(offset) => {
+                frame.pop_any_reference()?;
+                return Ok(Successors::branch(self.target(addr, *offset as i32)?));
+            }
+            Instruction::Goto(offset) => {
+                return Ok(Successors::jump(vec![self.target(addr, *offset as i32)?]))
+            }
+            Instruction::GotoW(offset) => {
+                return Ok(Successors::jump(vec![self.target(addr, *offset)?]))
+            }
+            Instruction::Tableswitch {
+                default,
+                low,
+                high,
+                offsets,
+            } => {
+                if low > high {
+                    return Err("tableswitch with low greater than high".to_string());
+                }
+                frame.pop_type(&Int)?;
+                let mut targets = vec![self.target(addr, *default)?];
+                for offset in offsets {
+                    targets.push(self.target(addr, *offset)?);
+                }
+                return Ok(Successors::jump(targets));
+            }
+            Instruction::Lookupswitch { default, pairs } => {
+                if pairs.windows(2).any(|w| w[0].0 >= w[1].0) {
+                    return Err("lookupswitch keys aren't sorted".to_string());
+                }
+                frame.pop_type(&Int)?;
+                let mut targets = vec![self.target(addr, *default)?];
+                for (_, offset) in pairs {
+                    targets.push(self.target(addr, *offset)?);
+                }
+                return Ok(Successors::jump(targets));
+            }
+            Instruction::Jsr(_)
+            | Instruction::JsrW(_)
+            | Instruction::Ret(_)
+            | Instruction::RetWide(_) => {
+                return Err("jsr and ret aren't supported".to_string());
+            }
+            Instruction::Ireturn => return self.check_return(addr, frame, Some(Int)),
+            Instruction::Lreturn => return self.check_return(addr, frame, Some(Long)),
+            Instruction::Freturn => return self.check_return(addr, frame, Some(Float)),
+            Instruction::Dreturn => return self.check_return(addr, frame, Some(Double)),
+            Instruction::Areturn => return self.check_return(addr, frame, Some(object())),
+            Instruction::Return => return self.check_return(addr, frame, None),
+            Instruction::Athrow => {
+                self.pop_assignable(addr, frame, &VType::reference("java/lang/Throwable"))?;
+                return Ok(Successors::jump(vec![]));
+            }
+
+            Instruction::Getstatic(index) => {
+                let (_, _, field_type) = cp.field_ref(*index)?;
+                frame.push(field_type);
+            }
+            Instruction::Putstatic(index) => {
+                let (_, _, field_type) = cp.field_ref(*index)?;
+                self.pop_assignable(addr, frame, &field_type)?;
+            }
+            Instruction::Getfield(index) => {
+                let (class_name, _, field_type) = cp.field_ref(*index)?;
+                self.pop_assignable(addr, frame, &VType::reference(class_name))?;
+                frame.push(field_type);
+            }
+            Instruction::Putfield(index) => {
+                let (class_name, _, field_type) = cp.field_ref(*index)?;
+                self.pop_assignable(addr, frame, &field_type)?;
+                // The constructor may set the fields of its own class before calling super().
+                match frame.pop()? {
+                    v if v.is_reference() => {
+                        self.require_assignable(addr, &v, &VType::reference(class_name))?
+                    }
+                    VType::UninitializedThis if class_name == self.class.class_name => {}
+                    v => return Err(format!("putfield on {}", v)),
+                }
+            }
+            Instruction::Invokevirtual(index) => {
+                let (class_name, name, descriptor) = cp.method_ref(*index, true, false)?;
+                Self::check_not_initialization_method(name)?;
+                let return_type = self.pop_arguments(addr, frame, descriptor)?;
+                self.pop_assignable(addr, frame, &VType::reference(class_name))?;
+                Self::push_return_value(frame, return_type);
+            }
+            Instruction::Invokespecial(index) => {
+                let (class_name, name, descriptor) = cp.method_ref(
+                    *index,
+                    true,
+                    self.class.major_version >= INTERFACE_METHOD_REF_VERSION,
+                )?;
+                let return_type = self.pop_arguments(addr, frame, descriptor)?;
+                if name == "<init>" {
+                    self.invoke_init(frame, class_name)?;
+                } else {
+                    Self::check_not_initialization_method(name)?;
+                    self.pop_assignable(addr, frame, &VType::reference(class_name))?;
+                    Self::push_return_value(frame, return_type);
+                }
+            }
+            Instruction::Invokestatic(index) => {
+                let (_, name, descriptor) = cp.method_ref(
+                    *index,
+                    true,
+                    self.class.major_version >= INTERFACE_METHOD_REF_VERSION,
+                )?;
+                Self::check_not_initialization_method(name)?;
+                let return_type = self.pop_arguments(addr, frame, descriptor)?;
+                Self::push_return_value(frame, return_type);
+            }
+            Instruction::Invokeinterface { index, count } => {
+                let (class_name, name, descriptor) = cp.method_ref(*index, false, true)?;
+                Self::check_not_initialization_method(name)?;
+                let (parameter_types, _) = parse_method_descriptor(descriptor).unwrap();
+                if *count as usize != slot_count(&parameter_types) + 1 {
+                    return Err(format!("invalid invokeinterface count {}", count));
+                }
+                let return_type = self.pop_arguments(addr, frame, descriptor)?;
+                self.pop_assignable(addr, frame, &VType::reference(class_name))?;
+                Self::push_return_value(frame, return_type);
+            }
+            Instruction::Invokedynamic(index) => {
+                let ConstantInfo::InvokeDynamic(invoke_dynamic) = cp.get(*index)? else {
+                    return Err(format!(
+                        "constant pool entry {} isn't an InvokeDynamic",
+                        index
+                    ));
+                };
+                if invoke_dynamic.bootstrap_method_attr_index as usize
+                    >= self.class.bootstrap_method_count
+                {
+                    return Err(format!(
+                        "invalid bootstrap method index {}",
+                        invoke_dynamic.bootstrap_method_attr_index
+                    ));
+                }
+                let (_, descriptor) = cp.name_and_type(invoke_dynamic.name_and_type_index)?;
+                let return_type = self.pop_arguments(addr, frame, descriptor)?;
+                Self::push_return_value(frame, return_type);
+            }
+
+            Instruction::New(index) => {
+                let class_name = cp.class_name(*index)?;
+                if class_name.starts_with('[') {
+                    return Err(format!("new of the array class {}", class_name));
+                }
+                let uninitialized = Uninitialized(addr);
+                if frame.stack.contains(&uninitialized) || frame.locals.contains(&uninitialized) {
+                    // The object created by the previous run of the same instruction isn't initialized yet.
+                    return Err("uninitialized object created again".to_string());
+                }
+                frame.push(uninitialized);
+            }
+            Instruction::Newarray(atype) => {
+                let component = match atype {
+                    4 => "Z",
+                    5 => "C",
+                    6 => "F",
+                    7 => "D",
+                    8 => "B",
+                    9 => "S",
+                    10 => "I",
+                    11 => "J",
+                    _ => return Err(format!("invalid newarray type {}", atype)),
+                };
+                frame.unary(Int, VType::Reference(format!("[{}", component)))?;
+            }
+            Instruction::Anewarray(index) => {
+                let class_name = cp.class_name(*index)?;
+                let array = if class_name.starts_with('[') {
+                    format!("[{}", class_name)
+                } else {
+                    format!("[L{};", class_name)
+                };
+                if parse_field_descriptor(&array).is_none() {
+                    return Err(format!("invalid array type {}", array));
+                }
+                frame.unary(Int, VType::Reference(array))?;
+            }
+            Instruction::Multianewarray { index, dimensions } => {
+                let class_name = cp.class_name(*index)?;
+                let array_dimensions = class_name.bytes().take_while(|c| *c == b'[').count();
+                if *dimensions == 0
+                    || array_dimensions < *dimensions as usize
+                    || parse_field_descriptor(class_name).is_none()
+                {
+                    return Err(format!(
+                        "invalid multianewarray of {} with {} dimensions",
+                        class_name, dimensions
+                    ));
+                }
+                for _ in 0..*dimensions {
+                    frame.pop_type(&Int)?;
+                }
+                frame.push(VType::reference(class_name));
+            }
+            Instruction::Arraylength => {
+                frame.pop_array()?;
+                frame.push(Int);
+            }
+            Instruction::Checkcast(index) => {
+                let class_name = cp.class_name(*index)?;
+                frame.pop_reference()?;
+                frame.push(VType::reference(class_name));
+            }
+            Instruction::Instanceof(index) => {
+                cp.class_name(*index)?;
+                frame.pop_reference()?;
+                frame.push(Int);
+            }
+            Instruction::Monitorenter | Instruction::Monitorexit => {
+                frame.pop_reference()?;
+            }
+        }
+        Ok(Successors::next())
+    }
+
+    fn ldc(&self, frame: &mut Frame, index: u16, is_wide: bool) -> Result<()> {
+        let v = self.class.cp.loadable_constant(index)?;
+        if v.is_wide() != is_wide {
+            return Err(format!("invalid constant {} for ldc", index));
+        }
+        frame.push(v);
+        Ok(())
+    }
+
+    fn iinc(&self, frame: &mut Frame, index: usize) -> Result<()> {
+        match frame.local(index, false)? {
+            VType::Int => Ok(()),
+            v => Err(format!("iinc of {} in local {}", v, index)),
+        }
+    }
+
+    /// Checks the receiver of the constructor call, and marks it initialized.
+    fn invoke_init(&self, frame: &mut Frame, class_name: &str) -> Result<()> {
+        let receiver = frame.pop()?;
+        let initialized = match &receiver {
+            VType::UninitializedThis => {
+                if class_name != self.class.class_name
+                    && Some(class_name) != self.class.super_class_name
+                {
+                    return Err(format!("invalid constructor call of {}", class_name));
+                }
+                self.class.class_name
+            }
+            VType::Uninitialized(offset) => {
+                let new_class_name = self.new_class_name(*offset)?;
+                if class_name != new_class_name {
+                    return Err(format!(
+                        "constructor of {} called on an uninitialized {}",
+                        class_name, new_class_name
+                    ));
+                }
+                new_class_name
+            }
+            v => return Err(format!("constructor called on the initialized {}", v)),
+        };
+        frame.initialize(&receiver, VType::reference(initialized));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use classfile_parser::constant_info::{
+        ClassConstant, MethodRefConstant, NameAndTypeConstant, Utf8Constant,
+    };
+    use classfile_parser::ClassAccessFlags;
+
+    const INT_TO_INT: u16 = 7;
+    const TO_OBJECT: u16 = 9;
+
+    fn utf8(s: &str) -> ConstantInfo {
+        ConstantInfo::Utf8(Utf8Constant {
+            utf8_string: s.to_string(),
+            bytes: s.as_bytes().to_vec(),
+        })
+    }
+
+    fn attribute(attribute_name_index: u16, info: Vec<u8>) -> AttributeInfo {
+        AttributeInfo {
+            attribute_name_index,
+            attribute_length: info.len() as u32,
+            info,
+        }
+    }
+
+    /// Builds a class with the single static method f, whose descriptor is at the index.
+    fn class_file(
+        descriptor_index: u16,
+        max_stack: u16,
+        max_locals: u16,
+        code: &[u8],
+        stack_map: Option<&[u8]>,
+    ) -> ClassFile {
+        let const_pool = vec![
+            utf8("Test"),
+            ConstantInfo::Class(ClassConstant { name_index: 1 }),
+            utf8("java/lang/Object"),
+            ConstantInfo::Class(ClassConstant { name_index: 3 }),
+            utf8("Code"),
+            utf8("f"),
+            utf8("(I)I"),
+            utf8("StackMapTable"),
+            utf8("()Ljava/lang/Object;"),
+            utf8("<init>"),
+            utf8("()V"),
+            ConstantInfo::NameAndType(NameAndTypeConstant {
+                name_index: 10,
+                descriptor_index: 11,
+            }),
+            ConstantInfo::MethodRef(MethodRefConstant {
+                class_index: 4,
+                name_and_type_index: 12,
+            }),
+        ];
+
+        let mut info = Vec::new();
+        info.extend(max_stack.to_be_bytes());
+        info.extend(max_locals.to_be_bytes());
+        info.extend((code.len() as u32).to_be_bytes());
+        info.extend(code);
+        info.extend(0u16.to_be_bytes());
+        match stack_map {
+            Some(stack_map) => {
+                info.extend(1u16.to_be_bytes());
+                info.extend(8u16.to_be_bytes());
+                info.extend((stack_map.len() as u32).to_be_bytes());
+                info.extend(stack_map);
+            }
+            None => info.extend(0u16.to_be_bytes()),
+        }
+
+        ClassFile {
+            minor_version: 0,
+            major_version: 61,
+            const_pool_size: const_pool.len() as u16 + 1,
+            const_pool,
+            access_flags: ClassAccessFlags::PUBLIC | ClassAccessFlags::SUPER,
+            this_class: 2,
+            super_class: 4,
+            interfaces_count: 0,
+            interfaces: vec![],
+            fields_count: 0,
+            fields: vec![],
+            methods_count: 1,
+            methods: vec![MethodInfo {
+                access_flags: MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC,
+                name_index: 6,
+                descriptor_index,
+                attributes_count: 1,
+                attributes: vec![attribute(5, info)],
+            }],
+            attributes_count: 0,
+            attributes: vec![],
+        }
+    }
+
+    /// Verifies the method, and returns the offset and the message of the error.
+    fn verify(
+        descriptor_index: u16,
+        max_stack: u16,
+        max_locals: u16,
+        code: &[u8],
+        stack_map: Option<&[u8]>,
+    ) -> std::result::Result<(), (Option<usize>, String)> {
+        let class_file = class_file(descriptor_index, max_stack, max_locals, code, stack_map);
+        verify_class(&class_file)
+            .map(|_| ())
+            .map_err(|e| (e.offset, e.message))
+    }
+
+    #[test]
+    fn test_verify_valid_method() {
+        // iload_0, ireturn
+        assert_eq!(verify(INT_TO_INT, 1, 1, &[0x1a, 0xac], None), Ok(()));
+        // new java/lang/Object, dup, invokespecial java/lang/Object.<init>, areturn
+        let code = [0xbb, 0x00, 0x04, 0x59, 0xb7, 0x00, 0x0d, 0xb0];
+        assert_eq!(verify(TO_OBJECT, 2, 0, &code, None), Ok(()));
+    }
+
+    #[test]
+    fn test_verify_operand_stack() {
+        // iadd, ireturn
+        assert_eq!(
+            verify(INT_TO_INT, 2, 1, &[0x60, 0xac], None),
+            Err((Some(0), "operand stack underflow".to_string()))
+        );
+        // iconst_0, iconst_0, iadd, ireturn
+        assert_eq!(
+            verify(INT_TO_INT, 1, 1, &[0x03, 0x03, 0x60, 0xac], None),
+            Err((Some(1), "operand stack overflow".to_string()))
+        );
+        // fconst_0, ireturn
+        assert_eq!(
+            verify(INT_TO_INT, 1, 1, &[0x0b, 0xac], None),
+            Err((
+                Some(1),
+                "expected int on the operand stack, found float".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_verify_indices() {
+        // iload 5, ireturn
+        assert_eq!(
+            verify(INT_TO_INT, 1, 1, &[0x15, 0x05, 0xac], None),
+            Err((Some(0), "invalid local variable index 5".to_string()))
+        );
+        // ldc #200, ireturn
+        assert_eq!(
+            verify(INT_TO_INT, 1, 1, &[0x12, 0xc8, 0xac], None),
+            Err((Some(0), "invalid constant pool index 200".to_string()))
+        );
+        // ldc #1 (Utf8), ireturn
+        assert_eq!(
+            verify(INT_TO_INT, 1, 1, &[0x12, 0x01, 0xac], None),
+            Err((Some(0), "constant pool entry 1 isn't loadable".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_verify_control_flow() {
+        // iload_0, goto +1 (into the goto itself), ireturn
+        assert_eq!(
+            verify(INT_TO_INT, 1, 1, &[0x1a, 0xa7, 0x00, 0x01, 0xac], None),
+            Err((
+                Some(1),
+                "branch target 2 isn't at an instruction".to_string()
+            ))
+        );
+        // iload_0
+        assert_eq!(
+            verify(INT_TO_INT, 1, 1, &[0x1a], None),
+            Err((Some(0), "falling off the end of the code".to_string()))
+        );
+
+        // iload_0, ifeq 6, iconst_1, ireturn, 6: iconst_0, ireturn
+        let code = [0x1a, 0x99, 0x00, 0x05, 0x04, 0xac, 0x03, 0xac];
+        assert_eq!(
+            verify(INT_TO_INT, 1, 1, &code, None),
+            Err((
+                Some(1),
+                "missing stack map frame at branch target 6".to_string()
+            ))
+        );
+        // One same_frame at 6.
+        assert_eq!(
+            verify(INT_TO_INT, 1, 1, &code, Some(&[0x00, 0x01, 0x06])),
+            Ok(())
+        );
+        // One same_locals_1_stack_item_frame at 6 with an int on the stack.
+        assert_eq!(
+            verify(INT_TO_INT, 1, 1, &code, Some(&[0x00, 0x01, 64 + 6, 0x01])),
+            Err((Some(1), "stack map frame at 6 doesn't match".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_verify_uninitialized_object() {
+        // new java/lang/Object, areturn
+        assert_eq!(
+            verify(TO_OBJECT, 1, 0, &[0xbb, 0x00, 0x04, 0xb0], None),
+            Err((
+                Some(3),
+                "expected java/lang/Object on the operand stack, found uninitialized(0)"
+                    .to_string()
+            ))
+        );
+        // new java/lang/Object, invokespecial java/lang/Object.<init>, aconst_null, areturn
+        assert_eq!(
+            verify(
+                TO_OBJECT,
+                1,
+                0,
+                &[0xbb, 0x00, 0x04, 0xb7, 0x00, 0x0d, 0x01, 0xb0],
+                None
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_verify_constant_pool() {
+        let mut class_file = class_file(INT_TO_INT, 1, 1, &[0x1a, 0xac], None);
+        class_file.this_class = 1;
+        assert_eq!(
+            verify_class(&class_file),
+            Err(VerifyError {
+                class_name: String::new(),
+                method: None,
+                offset: None,
+                message: "constant pool entry 1 isn't a Class".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_reference_assignability() {
+        assert!(is_reference_assignable("[I", "java/lang/Object"));
+        assert!(is_reference_assignable("[I", "java/io/Serializable"));
+        assert!(is_reference_assignable("[[I", "[Ljava/lang/Cloneable;"));
+        assert!(is_reference_assignable("[LA;", "[LB;"));
+        assert!(is_reference_assignable("A", "B"));
+        assert!(!is_reference_assignable("[I", "[J"));
+        assert!(!is_reference_assignable("[I", "[Ljava/lang/Object;"));
+        assert!(!is_reference_assignable("[LA;", "A"));
+        assert!(!is_reference_assignable("A", "[LA;"));
+
+        assert_eq!(subclass_constraint("[[LA;", "[[LB;"), Some(("A", "B")));
+        assert_eq!(subclass_constraint("[LA;", "[Ljava/lang/Object;"), None);
+        assert_eq!(subclass_constraint("[[LA;", "[LB;"), None);
+        assert_eq!(subclass_constraint("A", "A"), None);
+    }
+
+    #[test]
+    fn test_parse_method_descriptor() {
+        assert_eq!(
+            parse_method_descriptor("(IJ[Ljava/lang/String;)[[D"),
+            Some((
+                vec![
+                    VType::Int,
+                    VType::Long,
+                    VType::reference("[Ljava/lang/String;")
+                ],
+                Some(VType::reference("[[D"))
+            ))
+        );
+        assert_eq!(parse_method_descriptor("()V"), Some((vec![], None)));
+        assert_eq!(parse_method_descriptor("(V)V"), None);
+        assert_eq!(parse_method_descriptor("(Ljava/lang/String)V"), None);
+        assert_eq!(parse_method_descriptor("(I)"), None);
+    }
+}
@@ -0,0 +1,11 @@
+// The class file is patched after compiling so that main passes args itself instead of args[0] to greet, i.e.
+// iconst_0 and aaload are replaced by nops.
+public class ArrayTypeMismatch {
+    static String greet(String name) {
+        return "Hello, " + name;
+    }
+
+    public static void main(String[] args) {
+        System.out.println(greet(args[0]));
+    }
+}
@@ -0,0 +1,115 @@
+public class BitTwiddling {
+    static long lastLong;
+
+    static int popCount(int x) {
+        x = x - ((x >>> 1) & 0x55555555);
+        x = (x & 0x33333333) + ((x >>> 2) & 0x33333333);
+        x = (x + (x >>> 4)) & 0x0f0f0f0f;
+        return (x * 0x01010101) >>> 24;
+    }
+
+    static int popCountLong(long x) {
+        int count = 0;
+        while (x != 0) {
+            x &= x - 1;
+            count++;
+        }
+        return count;
+    }
+
+    static int reverse(int x) {
+        x = ((x & 0x55555555) << 1) | ((x >>> 1) & 0x55555555);
+        x = ((x & 0x33333333) << 2) | ((x >>> 2) & 0x33333333);
+        x = ((x & 0x0f0f0f0f) << 4) | ((x >>> 4) & 0x0f0f0f0f);
+        x = (x << 24) | ((x & 0xff00) << 8) | ((x >>> 8) & 0xff00) | (x >>> 24);
+        return x;
+    }
+
+    static boolean isPowerOfTwo(int x) {
+        return x > 0 && (x & (x - 1)) == 0;
+    }
+
+    static int lowestOneBit(int x) {
+        return x & -x;
+    }
+
+    static int abs(int x) {
+        int mask = x >> 31;
+        return (x + mask) ^ mask;
+    }
+
+    static int signExtendByte(int x) {
+        return (x << 24) >> 24;
+    }
+
+    static void shifts(long x, int count) {
+        lastLong = x << count;
+        System.out.println(lastLong);
+        System.out.println(x >> count);
+        System.out.println(x >>> count);
+    }
+
+    public static void main(String[] args) {
+        System.out.println(popCount(0));
+        System.out.println(popCount(-1));
+        System.out.println(popCount(0x12345678));
+        System.out.println(popCountLong(-1L));
+        System.out.println(popCountLong(0x0f0f0f0f0f0f0f0fL));
+        System.out.println(reverse(1));
+        System.out.println(reverse(0x12345678));
+        System.out.println(isPowerOfTwo(64));
+        System.out.println(isPowerOfTwo(96));
+        System.out.println(isPowerOfTwo(Integer.MIN_VALUE));
+        System.out.println(lowestOneBit(96));
+        System.out.println(abs(-12345));
+        System.out.println(abs(Integer.MIN_VALUE));
+        System.out.println(signExtendByte(0xff));
+        System.out.println(signExtendByte(0x7f));
+
+        // Shift counts are masked to 5 bits for int and 6 bits for long.
+        int one = 1;
+        System.out.println(one << 33);
+        System.out.println(-16 >> 34);
+        System.out.println(-16 >>> 60);
+        shifts(-256L, 4);
+        shifts(-256L, 68);
+        shifts(1L, 63);
+
+        // Remainders take the sign of the dividend.
+        int seven = 7;
+        System.out.println(seven % 3);
+        System.out.println(-seven % 3);
+        System.out.println(seven % -3);
+        System.out.println(Integer.MIN_VALUE % -1);
+        long big = 1000000007L * 3 + 5;
+        System.out.println(big % 1000000007L);
+        System.out.println(-big % 1000000007L);
+        System.out.println(Long.MIN_VALUE % -1L);
+        try {
+            System.out.println(seven % 0);
+        } catch (ArithmeticException e) {
+            System.out.println(e.getMessage());
+        }
+
+        // Floating point remainders are fmod-like.
+        double d = 7.5;
+        float f = -7.5f;
+        System.out.println(d % 2.0);
+        System.out.println(-d % 2.0);
+        System.out.println(f % 2.0f);
+        System.out.println(d % 0.0);
+        System.out.println(-d);
+        System.out.println(-f);
+
+        int x = 0x0ff0;
+        long y = 0xff00ff00ff00L;
+        System.out.println(x & 0x00ff);
+        System.out.println(x | 0xf000);
+        System.out.println(x ^ 0xffff);
+        System.out.println(y & 0xffffffffL);
+        System.out.println(y | 0xffL);
+        System.out.println(y ^ -1L);
+        System.out.println(-x);
+        System.out.println(-y);
+    }
+}
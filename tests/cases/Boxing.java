@@ -0,0 +1,39 @@
+public class Boxing {
+    static Integer sum(Integer a, Integer b) {
+        return a + b;
+    }
+
+    static long widen(Long l, Short s, Byte b) {
+        return l + s + b;
+    }
+
+    static Integer countUp(int n) {
+        // The boxed local is merged at the loop header.
+        Integer total = 0;
+        for (int i = 0; i < n; i++) {
+            total = total + i;
+        }
+        return total;
+    }
+
+    static Integer nothing() {
+        return null;
+    }
+
+    public static void main(String[] args) {
+        System.out.println(sum(20, 22).intValue());
+        System.out.println(widen(10000000000L, (short) 300, (byte) -1));
+        System.out.println(countUp(5).intValue());
+
+        Object o = Integer.valueOf(7);
+        System.out.println(String.valueOf(o));
+        Boolean flag = true;
+        Character c = 'j';
+        Float f = 1.5f;
+        Double d = 2.25;
+        System.out.println(flag.booleanValue());
+        System.out.println(c.charValue());
+        System.out.println(f.floatValue() + d.doubleValue());
+        System.out.println("boxed " + flag + " " + c + " " + f + " " + d + " " + nothing());
+    }
+}
@@ -0,0 +1,15 @@
+public class BoxingLambda {
+    interface ObjectSupplier {
+        Object get();
+    }
+
+    static int answer() {
+        return 42;
+    }
+
+    public static void main(String[] args) {
+        // The method reference boxes the int returned by answer().
+        ObjectSupplier supplier = BoxingLambda::answer;
+        System.out.println(supplier.get());
+    }
+}
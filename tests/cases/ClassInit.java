@@ -0,0 +1,111 @@
+public class ClassInit {
+    static {
+        System.out.println("ClassInit");
+    }
+
+    static class Base {
+        static int baseValue = log("Base", 1);
+    }
+
+    static class Derived extends Base {
+        static int derivedValue = log("Derived", 2);
+
+        static int get() {
+            return derivedValue + baseValue;
+        }
+    }
+
+    static class Unused {
+        static {
+            System.out.println("Unused");
+        }
+    }
+
+    static class Counter {
+        static int count;
+
+        static {
+            System.out.println("Counter");
+            count = 10;
+        }
+
+        int next() {
+            return ++count;
+        }
+    }
+
+    static class Constants {
+        static final int ANSWER = 42;
+
+        static {
+            System.out.println("Constants");
+        }
+    }
+
+    static class Ping {
+        static int value = log("Ping", Pong.value + 1);
+    }
+
+    static class Pong {
+        static int value = log("Pong", Ping.value + 10);
+    }
+
+    static class Broken {
+        static int value = fail();
+
+        static int fail() {
+            throw new IllegalStateException("broken");
+        }
+    }
+
+    static class Fatal {
+        static int value = fatal();
+
+        static int fatal() {
+            throw new Error("fatal");
+        }
+    }
+
+    static class Holder {
+        static int value = 7;
+    }
+
+    static int log(String name, int value) {
+        System.out.println(name);
+        return value;
+    }
+
+    public static void main(String[] args) {
+        System.out.println("main");
+        System.out.println(Derived.get());
+        System.out.println(Derived.get());
+        System.out.println(Constants.ANSWER);
+        Counter counter = new Counter();
+        System.out.println(counter.next());
+        System.out.println(new Counter().next());
+        System.out.println(Ping.value);
+        System.out.println(Pong.value);
+
+        try {
+            System.out.println(Broken.value);
+        } catch (ExceptionInInitializerError e) {
+            System.out.println(e.getMessage() == null);
+            System.out.println(e.getCause().getMessage());
+        }
+        try {
+            System.out.println(Broken.value);
+        } catch (NoClassDefFoundError e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            System.out.println(Fatal.value);
+        } catch (ExceptionInInitializerError e) {
+            System.out.println("wrapped");
+        } catch (Error e) {
+            System.out.println(e.getMessage());
+        }
+
+        Holder.value = 8;
+        System.out.println(Holder.value);
+    }
+}
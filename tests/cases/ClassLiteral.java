@@ -0,0 +1,6 @@
+// Class literals are compiled to ldc of the class constant, which is not supported yet.
+public class ClassLiteral {
+    public static void main(String[] args) {
+        Object c = ClassLiteral.class;
+    }
+}
@@ -0,0 +1,46 @@
+// The fields of Constants aren't final here so that javac doesn't inline them into main. The class file
+// ConstantValues$Constants.class is compiled separately from the following source instead, whose static
+// final fields have ConstantValue attributes and aren't assigned in <clinit>:
+//
+// class ConstantValues$Constants {
+//     static final boolean Z = true;
+//     static final byte B = -8;
+//     static final char C = 'c';
+//     static final short S = -1234;
+//     static final int I = 42;
+//     static final long J = 1234567890123L;
+//     static final float F = 1.5f;
+//     static final double D = -2.25;
+//     static final String STR = "constant";
+//     static int counter = 3;
+//     static final int FROM_CLINIT = I + counter;
+// }
+class ConstantValues {
+    static class Constants {
+        static boolean Z;
+        static byte B;
+        static char C;
+        static short S;
+        static int I;
+        static long J;
+        static float F;
+        static double D;
+        static String STR;
+        static int counter;
+        static int FROM_CLINIT;
+    }
+
+    public static void main(String[] args) {
+        System.out.println(Constants.Z);
+        System.out.println(Constants.B);
+        System.out.println(Constants.C);
+        System.out.println(Constants.S);
+        System.out.println(Constants.I);
+        System.out.println(Constants.J);
+        System.out.println(Constants.F);
+        System.out.println(Constants.D);
+        System.out.println(Constants.STR);
+        System.out.println(Constants.counter);
+        System.out.println(Constants.FROM_CLINIT);
+    }
+}
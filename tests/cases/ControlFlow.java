@@ -0,0 +1,143 @@
+class ControlFlow {
+    static class Node {
+        int value;
+        Node next;
+
+        Node(int value, Node next) {
+            this.value = value;
+            this.next = next;
+        }
+    }
+
+    public static void main(String[] args) {
+        slotReuse();
+        linkedList();
+        mergedNull(true);
+        mergedNull(false);
+        System.out.println(ternaryOnStack(3));
+        System.out.println(ternaryOnStack(-3));
+        wideLocals();
+        catchInLoop();
+        nestedLoops();
+        System.out.println(firstNegative(new int[] {4, 2, -7, 1}));
+        System.out.println(firstNegative(new int[] {4, 2}));
+    }
+
+    // The same local slots hold ints, strings and doubles in the different scopes.
+    static void slotReuse() {
+        {
+            int a = 40;
+            int b = 2;
+            System.out.println(a + b);
+        }
+        {
+            String s = "reused";
+            String t = " slots";
+            System.out.println(s + t);
+        }
+        {
+            double d = 1.5;
+            System.out.println(d * 3);
+        }
+        for (int i = 0; i < 2; i++) {
+            String s = "loop" + i;
+            System.out.println(s);
+        }
+        for (long i = 0; i < 2; i++) {
+            System.out.println(i);
+        }
+    }
+
+    static void linkedList() {
+        Node head = null;
+        for (int i = 1; i <= 4; i++) {
+            head = new Node(i, head);
+        }
+        int sum = 0;
+        Node last = null;
+        for (Node n = head; n != null; n = n.next) {
+            sum += n.value;
+            last = n;
+        }
+        System.out.println(sum);
+        System.out.println(last.value);
+    }
+
+    static void mergedNull(boolean flag) {
+        String s = null;
+        if (flag) {
+            s = "set";
+        }
+        if (s == null) {
+            System.out.println("null");
+        } else {
+            System.out.println(s);
+        }
+        Node n = flag ? new Node(7, null) : null;
+        System.out.println(n == null ? -1 : n.value);
+    }
+
+    static String ternaryOnStack(int x) {
+        return "sign: " + (x > 0 ? "positive" : "negative");
+    }
+
+    static void wideLocals() {
+        long total = 0;
+        double scale = 0.5;
+        for (int i = 0; i < 5; i++) {
+            if (i % 2 == 0) {
+                total += i;
+            } else {
+                scale *= 1.5;
+            }
+        }
+        System.out.println(total);
+        System.out.println(scale);
+    }
+
+    static void catchInLoop() {
+        int caught = 0;
+        for (int i = 0; i < 4; i++) {
+            String label = "i=" + i;
+            try {
+                if (i % 2 == 1) {
+                    throw new RuntimeException(label);
+                }
+                System.out.println(label);
+            } catch (RuntimeException e) {
+                caught++;
+                System.out.println("caught " + e.getMessage());
+            }
+        }
+        System.out.println(caught);
+    }
+
+    static void nestedLoops() {
+        int count = 0;
+        outer:
+        for (int i = 0; i < 5; i++) {
+            Node n = new Node(i, null);
+            for (int j = 0; j < 5; j++) {
+                if (j > n.value) {
+                    continue outer;
+                }
+                if (i == 4) {
+                    break outer;
+                }
+                count++;
+            }
+        }
+        System.out.println(count);
+    }
+
+    static int firstNegative(int[] values) {
+        int index = 0;
+        while (index < values.length) {
+            if (values[index] < 0) {
+                return index;
+            }
+            index++;
+        }
+        return -1;
+    }
+}
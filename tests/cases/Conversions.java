@@ -0,0 +1,64 @@
+public class Conversions {
+    static void fromInt(int i) {
+        long l = i;
+        System.out.println(l * 4);
+        float f = i;
+        System.out.println(f + 0.5f);
+        double d = i;
+        System.out.println(d + 0.25);
+    }
+
+    static void fromLong(long l) {
+        int i = (int) l;
+        System.out.println(i);
+        float f = l;
+        System.out.println(f < 0 ? (int) (f / 1e6f) : (int) (f / 1e3f));
+        double d = l;
+        System.out.println((long) (d / 3));
+    }
+
+    static void fromFloat(float f) {
+        int i = (int) f;
+        System.out.println(i);
+        long l = (long) f;
+        System.out.println(l);
+        double d = f;
+        System.out.println(d * 2 == f + f);
+    }
+
+    static void fromDouble(double d) {
+        int i = (int) d;
+        System.out.println(i);
+        long l = (long) d;
+        System.out.println(l);
+        float f = (float) d;
+        System.out.println(f * 0.5f);
+    }
+
+    static void narrowing(int i) {
+        System.out.println((byte) i);
+        System.out.println((short) i);
+        System.out.println((int) (char) i);
+    }
+
+    public static void main(String[] args) {
+        fromInt(-7);
+        fromInt(1000);
+        fromLong(4294967297L);
+        fromLong(-9007199254740993L);
+        fromFloat(-3.75f);
+        fromFloat(1e20f);
+        fromFloat(-1e20f);
+        float zero = 0.0f;
+        fromFloat(zero / zero);
+        fromFloat(1.0f / zero);
+        fromDouble(123456.789);
+        fromDouble(-3e9);
+        fromDouble(-1e300);
+        double dzero = 0.0;
+        fromDouble(dzero / dzero);
+        fromDouble(-1.0 / dzero);
+        narrowing(0x12345678);
+        narrowing(-129);
+    }
+}
@@ -0,0 +1,60 @@
+public class CrossClassStatics {
+    static class MathUtil {
+        static int calls;
+
+        static int square(int x) {
+            calls++;
+            return x * x;
+        }
+
+        static long cube(long x) {
+            calls++;
+            return x * x * x;
+        }
+    }
+
+    static class Config {
+        static int size = MathUtil.square(4);
+        static long volume = MathUtil.cube(size);
+        static int[] table = new int[] {size, size + 1, size + 2};
+    }
+
+    static class Base {
+        static int twice(int x) {
+            return x + x;
+        }
+    }
+
+    static class Derived extends Base {
+        static int quadruple(int x) {
+            return twice(twice(x));
+        }
+    }
+
+    // Static initializers referring to each other.
+    static class A {
+        static int a = B.b + 1;
+    }
+
+    static class B {
+        static int b = A.a + 10;
+    }
+
+    static int local = 5;
+
+    public static void main(String[] args) {
+        System.out.println(A.a);
+        System.out.println(B.b);
+        System.out.println(Config.size);
+        System.out.println(Config.volume);
+        System.out.println(Config.table[2]);
+        System.out.println(MathUtil.square(local));
+        System.out.println(MathUtil.calls);
+        MathUtil.calls = 100;
+        System.out.println(MathUtil.calls);
+        Config.size += 1;
+        System.out.println(Config.size);
+        System.out.println(Derived.twice(21));
+        System.out.println(Derived.quadruple(3));
+    }
+}
@@ -0,0 +1,74 @@
+public class Exceptions {
+    static class ValidationException extends Exception {
+        int code;
+
+        ValidationException(String message, int code) {
+            super(message);
+            this.code = code;
+        }
+    }
+
+    static int validate(int value) throws ValidationException {
+        if (value < 0) {
+            throw new ValidationException("negative", value);
+        }
+        return value * 2;
+    }
+
+    static int depth(int n) {
+        if (n == 0) {
+            throw new IllegalStateException("bottom");
+        }
+        return depth(n - 1) + 1;
+    }
+
+    static int withFinally(int value) {
+        try {
+            return validate(value);
+        } catch (ValidationException e) {
+            System.out.println(e.code);
+            return -1;
+        } finally {
+            System.out.println("finally");
+        }
+    }
+
+    static void rethrow() {
+        try {
+            throw new IllegalArgumentException("inner");
+        } catch (RuntimeException e) {
+            System.out.println("rethrowing");
+            throw new RuntimeException(e.getMessage());
+        }
+    }
+
+    public static void main(String[] args) throws Exception {
+        System.out.println(withFinally(21));
+        System.out.println(withFinally(-5));
+
+        try {
+            System.out.println(depth(3));
+        } catch (IllegalArgumentException e) {
+            System.out.println("unreachable");
+        } catch (IllegalStateException e) {
+            System.out.println(e.getMessage());
+        }
+
+        try {
+            rethrow();
+        } catch (Exception e) {
+            System.out.println(e.getMessage());
+        }
+
+        try {
+            throw new Error();
+        } catch (Throwable t) {
+            System.out.println("caught error");
+        }
+
+        if (args.length > 0) {
+            throw new IllegalArgumentException(args[0]);
+        }
+        System.out.println("done");
+    }
+}
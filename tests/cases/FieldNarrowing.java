@@ -0,0 +1,26 @@
+// The class file is patched after compiling so that the fields get the ints out of their ranges: setFlag stores
+// the int to flag instead of bits, and setSmall stores it to small instead of wide.
+public class FieldNarrowing {
+    int bits;
+    boolean flag;
+    int wide;
+    byte small;
+
+    static void setFlag(FieldNarrowing f, int v) {
+        f.bits = v;
+    }
+
+    static void setSmall(FieldNarrowing f, int v) {
+        f.wide = v;
+    }
+
+    public static void main(String[] args) {
+        FieldNarrowing f = new FieldNarrowing();
+        setFlag(f, 2);
+        System.out.println(f.flag ? 1 : 0);
+        setFlag(f, 3);
+        System.out.println(f.flag ? 1 : 0);
+        setSmall(f, 300);
+        System.out.println(f.small);
+    }
+}
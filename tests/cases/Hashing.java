@@ -0,0 +1,109 @@
+public class Hashing {
+    // 32-bit FNV-1a.
+    static int fnv1a32(byte[] data) {
+        int hash = 0x811c9dc5;
+        for (int i = 0; i < data.length; i++) {
+            hash ^= data[i] & 0xff;
+            hash *= 0x01000193;
+        }
+        return hash;
+    }
+
+    // 64-bit FNV-1a.
+    static void printFnv1a64(long[] data) {
+        long hash = 0xcbf29ce484222325L;
+        for (int i = 0; i < data.length; i++) {
+            hash ^= data[i] & 0xffL;
+            hash *= 0x100000001b3L;
+        }
+        System.out.println(hash);
+    }
+
+    // The finalization mix of MurmurHash3 (32 bits).
+    static int fmix32(int h) {
+        h ^= h >>> 16;
+        h *= 0x85ebca6b;
+        h ^= h >>> 13;
+        h *= 0xc2b2ae35;
+        h ^= h >>> 16;
+        return h;
+    }
+
+    // The finalization mix of MurmurHash3 (64 bits).
+    static void printFmix64(long k) {
+        k ^= k >>> 33;
+        k *= 0xff51afd7ed558ccdL;
+        k ^= k >>> 33;
+        k *= 0xc4ceb9fe1a85ec53L;
+        k ^= k >>> 33;
+        System.out.println(k);
+    }
+
+    // The same as String.hashCode.
+    static int polynomialHash(char[] chars) {
+        int hash = 0;
+        for (int i = 0; i < chars.length; i++) {
+            hash = 31 * hash + chars[i];
+        }
+        return hash;
+    }
+
+    // The same as HashMap.hash followed by the bucket index calculation.
+    static int bucketIndex(int hashCode, int capacity) {
+        int h = hashCode ^ (hashCode >>> 16);
+        return h & (capacity - 1);
+    }
+
+    static int rotateLeft(int value, int distance) {
+        return (value << distance) | (value >>> -distance);
+    }
+
+    // djb2 with xor, reduced into the table size with a non-negative remainder.
+    static int djb2Slot(byte[] data, int tableSize) {
+        int hash = 5381;
+        for (int i = 0; i < data.length; i++) {
+            hash = ((hash << 5) + hash) ^ data[i];
+        }
+        int slot = hash % tableSize;
+        if (slot < 0) {
+            slot += tableSize;
+        }
+        return slot;
+    }
+
+    public static void main(String[] args) {
+        byte[] data = new byte[5];
+        data[0] = 'y';
+        data[1] = 'a';
+        data[2] = 'j';
+        data[3] = 'v';
+        data[4] = 'm';
+        char[] chars = new char[5];
+        chars[0] = 'y';
+        chars[1] = 'a';
+        chars[2] = 'j';
+        chars[3] = 'v';
+        chars[4] = 'm';
+
+        System.out.println(fnv1a32(data));
+        long[] longs = new long[5];
+        longs[0] = 'y';
+        longs[1] = 'a';
+        longs[2] = 'j';
+        longs[3] = 'v';
+        longs[4] = 'm';
+        printFnv1a64(longs);
+        System.out.println(fmix32(42));
+        System.out.println(fmix32(-1));
+        printFmix64(42L);
+        printFmix64(-1L);
+        System.out.println(polynomialHash(chars));
+        System.out.println(bucketIndex(polynomialHash(chars), 16));
+        System.out.println(bucketIndex(fmix32(7), 64));
+        System.out.println(rotateLeft(0x12345678, 8));
+        System.out.println(rotateLeft(0x80000001, 33));
+        System.out.println(djb2Slot(data, 97));
+        data[4] = (byte) 0xf0;
+        System.out.println(djb2Slot(data, 97));
+    }
+}
@@ -0,0 +1,54 @@
+// The class files of Partial and Stranger are compiled from the older version in the comments below, so that
+// Partial lacks farewell and Stranger no longer implements Greeter:
+//
+//     static class Partial implements Greeter {
+//         public String greet() { return "partial"; }
+//     }
+//
+//     static class Stranger {
+//         public String greet() { return "stranger"; }
+//     }
+public class InterfaceErrors {
+    interface Greeter {
+        String greet();
+
+        String farewell();
+    }
+
+    static class Partial implements Greeter {
+        public String greet() {
+            return "partial";
+        }
+
+        public String farewell() {
+            return "bye";
+        }
+    }
+
+    static class Stranger implements Greeter {
+        public String greet() {
+            return "stranger";
+        }
+
+        public String farewell() {
+            return "bye";
+        }
+    }
+
+    public static void main(String[] args) {
+        Greeter partial = new Partial();
+        System.out.println(partial.greet());
+        try {
+            System.out.println(partial.farewell());
+        } catch (AbstractMethodError e) {
+            System.out.println(e.getMessage());
+        }
+
+        Greeter stranger = new Stranger();
+        try {
+            System.out.println(stranger.greet());
+        } catch (IncompatibleClassChangeError e) {
+            System.out.println(e.getMessage());
+        }
+    }
+}
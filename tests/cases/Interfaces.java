@@ -0,0 +1,119 @@
+public class Interfaces {
+    interface Shape {
+        int area();
+
+        default int doubleArea() {
+            return area() * 2;
+        }
+
+        default String describe() {
+            return "shape";
+        }
+
+        static int unit() {
+            return 1;
+        }
+    }
+
+    interface Named {
+        String name();
+    }
+
+    interface NamedShape extends Shape, Named {
+        default String describe() {
+            return helper();
+        }
+
+        private String helper() {
+            return name();
+        }
+    }
+
+    static class Square implements NamedShape {
+        int side;
+
+        Square(int side) {
+            this.side = side;
+        }
+
+        public int area() {
+            return side * side;
+        }
+
+        public String name() {
+            return "square";
+        }
+    }
+
+    static abstract class AbstractRect implements Shape {
+        int w;
+        int h;
+
+        AbstractRect(int w, int h) {
+            this.w = w;
+            this.h = h;
+        }
+
+        public int area() {
+            return w * h;
+        }
+    }
+
+    static class Rect extends AbstractRect {
+        Rect(int w, int h) {
+            super(w, h);
+        }
+
+        public String describe() {
+            return "rect";
+        }
+    }
+
+    static class Unit implements Shape {
+        public int area() {
+            return Shape.unit();
+        }
+    }
+
+    static class Counter implements Runnable {
+        int count;
+
+        public void run() {
+            count++;
+        }
+    }
+
+    static class Printer implements Runnable {
+        public void run() {
+            System.out.println("running");
+        }
+    }
+
+    static void runTwice(Runnable r) {
+        r.run();
+        r.run();
+    }
+
+    static void print(Shape s) {
+        System.out.println(s.describe());
+        System.out.println(s.area());
+        System.out.println(s.doubleArea());
+    }
+
+    public static void main(String[] args) {
+        Square square = new Square(3);
+        print(square);
+        print(new Rect(2, 5));
+        print(new Unit());
+        System.out.println(square.doubleArea());
+        System.out.println(square.describe());
+        Named named = square;
+        System.out.println(named.name());
+        Counter counter = new Counter();
+        runTwice(counter);
+        runTwice(new Printer());
+        System.out.println(counter.count);
+        Rect rect = new Rect(4, 4);
+        System.out.println(rect.doubleArea());
+    }
+}
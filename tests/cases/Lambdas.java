@@ -0,0 +1,128 @@
+import java.util.Comparator;
+
+public class Lambdas {
+    @FunctionalInterface
+    interface IntOp {
+        int apply(int a, int b);
+    }
+
+    interface Greeter {
+        String greet(String name);
+    }
+
+    interface PointFactory {
+        Point create(int x, int y);
+    }
+
+    interface PointOp {
+        int apply(Point p);
+    }
+
+    interface IntGetter {
+        int get();
+    }
+
+    interface LongMapper {
+        long map(long v);
+    }
+
+    interface DoubleOp {
+        double apply(double v);
+    }
+
+    interface Named {
+        String name();
+    }
+
+    interface NameFn {
+        String get(Named named);
+    }
+
+    static class Point implements Named {
+        int x;
+        int y;
+
+        Point(int x, int y) {
+            this.x = x;
+            this.y = y;
+        }
+
+        int sum() {
+            return x + y;
+        }
+
+        public String name() {
+            return "point";
+        }
+    }
+
+    int base = 100;
+
+    int addBase(int v) {
+        IntOp op = (a, b) -> a + b + base;
+        return op.apply(v, 1);
+    }
+
+    static int sub(int a, int b) {
+        return a - b;
+    }
+
+    static int apply(IntOp op, int a, int b) {
+        return op.apply(a, b);
+    }
+
+    static void runTwice(Runnable r) {
+        r.run();
+        r.run();
+    }
+
+    static void capturing() {
+        int k = 10;
+        System.out.println(apply((a, b) -> a * b + k, 2, 3));
+        String prefix = "hi ";
+        Greeter greeter = name -> prefix + name;
+        System.out.println(greeter.greet("bob"));
+        for (int i = 0; i < 3; i++) {
+            int j = i;
+            System.out.println(apply((a, b) -> a + b + j, 1, 1));
+        }
+    }
+
+    static void methodReferences() {
+        System.out.println(apply(Lambdas::sub, 9, 4));
+        PointFactory factory = Point::new;
+        Point p = factory.create(3, 4);
+        System.out.println(p.sum());
+        PointOp op = Point::sum;
+        System.out.println(op.apply(new Point(5, 6)));
+        IntGetter getter = p::sum;
+        p.x = 10;
+        System.out.println(getter.get());
+        NameFn nameFn = Named::name;
+        System.out.println(nameFn.get(p));
+    }
+
+    static void comparators() {
+        Comparator<Point> byX = (a, b) -> a.x - b.x;
+        System.out.println(byX.compare(new Point(1, 2), new Point(3, 4)));
+        Comparator<Point> bySum = (a, b) -> a.sum() - b.sum();
+        System.out.println(bySum.compare(new Point(5, 5), new Point(3, 4)));
+    }
+
+    static void wideTypes() {
+        LongMapper mapper = v -> v * 3;
+        System.out.println(mapper.map(5000000000L));
+        DoubleOp op = v -> v / 4;
+        System.out.println(op.apply(3.0));
+    }
+
+    public static void main(String[] args) {
+        System.out.println(apply((a, b) -> a + b, 2, 3));
+        runTwice(() -> System.out.println("run"));
+        capturing();
+        methodReferences();
+        comparators();
+        wideTypes();
+        System.out.println(new Lambdas().addBase(5));
+    }
+}
@@ -0,0 +1,107 @@
+public class NullChecks {
+    static class Node {
+        int value;
+        Node next;
+
+        Node(int value, Node next) {
+            this.value = value;
+            this.next = next;
+        }
+    }
+
+    static Node build(int n) {
+        Node head = null;
+        for (int i = n; i > 0; i--) {
+            head = new Node(i, head);
+        }
+        return head;
+    }
+
+    static int sum(Node node) {
+        int total = 0;
+        while (node != null) {
+            total += node.value;
+            node = node.next;
+        }
+        return total;
+    }
+
+    static Node find(Node node, int value) {
+        for (Node n = node; n != null; n = n.next) {
+            if (n.value == value) {
+                return n;
+            }
+        }
+        return null;
+    }
+
+    static String describe(Node node) {
+        return node == null ? "missing" : "found";
+    }
+
+    static String label(boolean flag) {
+        String s = null;
+        if (flag) {
+            s = "set";
+        }
+        if (s == null) {
+            return "unset";
+        }
+        return s;
+    }
+
+    static int firstLength(boolean flag) {
+        String[] words = null;
+        if (flag) {
+            words = new String[2];
+            words[0] = "hello";
+            words[1] = "hi";
+        }
+        if (words == null) {
+            return -1;
+        }
+        return words[1] != null ? 2 : 1;
+    }
+
+    static int countdown(int from) {
+        int steps = 0;
+        for (int i = from; i >= 0; i--) {
+            steps++;
+        }
+        return steps;
+    }
+
+    public static void main(String[] args) {
+        Node list = build(5);
+        System.out.println(sum(list));
+        System.out.println(sum(null));
+        System.out.println(describe(find(list, 3)));
+        System.out.println(describe(find(list, 7)));
+        System.out.println(find(list, 4).value);
+
+        Node a = new Node(1, null);
+        Node b = a;
+        Node c = new Node(1, null);
+        System.out.println(a == b);
+        System.out.println(a == c);
+        System.out.println(a != c);
+        System.out.println(a.next == null);
+
+        System.out.println(label(true));
+        System.out.println(label(false));
+        System.out.println(firstLength(true));
+        System.out.println(firstLength(false));
+        System.out.println(countdown(3));
+
+        Object nothing = null;
+        System.out.println(nothing instanceof Node);
+        Node none = (Node) nothing;
+        System.out.println(none == null);
+
+        try {
+            System.out.println(none.value);
+        } catch (NullPointerException e) {
+            System.out.println("caught");
+        }
+    }
+}
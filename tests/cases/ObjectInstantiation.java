@@ -0,0 +1,68 @@
+public class ObjectInstantiation {
+    static class Point {
+        int x;
+        int y;
+
+        Point(int x, int y) {
+            this.x = x;
+            this.y = y;
+        }
+    }
+
+    static class Point3D extends Point {
+        int z;
+
+        Point3D(int x, int y, int z) {
+            super(x, y);
+            this.z = z;
+        }
+    }
+
+    static class Account {
+        String owner;
+        long balance;
+        double rate;
+        boolean active;
+
+        Account(String owner) {
+            this.owner = owner;
+            this.rate = 0.5;
+            this.active = true;
+        }
+    }
+
+    int value;
+
+    ObjectInstantiation(int value) {
+        this.value = value;
+    }
+
+    public static void main(String[] args) {
+        Point p = new Point(1, 2);
+        System.out.println(p.x);
+        System.out.println(p.y);
+        p.x = 10;
+        System.out.println(p.x);
+
+        Point3D q = new Point3D(3, 4, 5);
+        System.out.println(q.x);
+        System.out.println(q.y);
+        System.out.println(q.z);
+        System.out.println(sum(q));
+
+        Account a = new Account("alice");
+        a.balance += 100;
+        a.balance -= 30;
+        System.out.println(a.owner);
+        System.out.println(a.balance);
+        System.out.println(a.rate);
+        System.out.println(a.active);
+
+        ObjectInstantiation o = new ObjectInstantiation(42);
+        System.out.println(o.value);
+    }
+
+    static int sum(Point p) {
+        return p.x + p.y;
+    }
+}
@@ -0,0 +1,7 @@
+// The major version of the class file is patched from 61 to 49 after compiling, i.e. it claims to be compiled for
+// Java 5, whose class files have no stack map frames.
+public class OldClassVersion {
+    public static void main(String[] args) {
+        System.out.println("old");
+    }
+}
@@ -0,0 +1,113 @@
+public class Polymorphism {
+    static abstract class Shape {
+        int id;
+
+        Shape(int id) {
+            this.id = id;
+        }
+
+        abstract int area();
+
+        int perimeter() {
+            return 0;
+        }
+
+        String name() {
+            return "shape";
+        }
+
+        // Not overridden by the method of the same name in the subclasses.
+        private int secret() {
+            return 1000;
+        }
+
+        int describe() {
+            return id * 10000 + area() * 10 + secret();
+        }
+
+        public String toString() {
+            return name();
+        }
+    }
+
+    static class Rectangle extends Shape {
+        int width;
+        int height;
+
+        Rectangle(int id, int width, int height) {
+            super(id);
+            this.width = width;
+            this.height = height;
+        }
+
+        int area() {
+            return width * height;
+        }
+
+        int perimeter() {
+            return 2 * (width + height);
+        }
+
+        String name() {
+            return "rectangle";
+        }
+
+        int secret() {
+            return 7;
+        }
+    }
+
+    static class Square extends Rectangle {
+        Square(int id, int side) {
+            super(id, side, side);
+        }
+
+        String name() {
+            return "square";
+        }
+
+        int perimeter() {
+            // Calls Rectangle.perimeter.
+            return super.perimeter() + 1;
+        }
+    }
+
+    static class Circle extends Shape {
+        int radius;
+
+        Circle(int id, int radius) {
+            super(id);
+            this.radius = radius;
+        }
+
+        int area() {
+            return 3 * radius * radius;
+        }
+
+        public String toString() {
+            // Calls Shape.toString which calls the overridden name.
+            return super.toString();
+        }
+
+        String name() {
+            return "circle";
+        }
+    }
+
+    static void print(Shape shape) {
+        System.out.println(shape.toString());
+        System.out.println(shape.area());
+        System.out.println(shape.perimeter());
+        System.out.println(shape.describe());
+    }
+
+    public static void main(String[] args) {
+        print(new Rectangle(1, 2, 3));
+        print(new Square(2, 4));
+        print(new Circle(3, 5));
+        Rectangle r = new Square(4, 1);
+        System.out.println(r.name());
+        System.out.println(r.secret());
+        System.out.println(r.perimeter());
+    }
+}
@@ -0,0 +1,174 @@
+public class ReferenceArrays {
+    static class Point {
+        int x;
+        int y;
+
+        Point(int x, int y) {
+            this.x = x;
+            this.y = y;
+        }
+    }
+
+    static int[][] multiply(int[][] a, int[][] b) {
+        int n = a.length;
+        int m = b[0].length;
+        int[][] c = new int[n][m];
+        for (int i = 0; i < n; i++) {
+            for (int j = 0; j < m; j++) {
+                int sum = 0;
+                for (int k = 0; k < b.length; k++) {
+                    sum += a[i][k] * b[k][j];
+                }
+                c[i][j] = sum;
+            }
+        }
+        return c;
+    }
+
+    static void printMatrix(int[][] m) {
+        for (int i = 0; i < m.length; i++) {
+            int row = 0;
+            for (int j = 0; j < m[i].length; j++) {
+                row = row * 10 + m[i][j];
+            }
+            System.out.println(row);
+        }
+    }
+
+    static int sumGrid(long[][][] grid) {
+        int sum = 0;
+        for (int i = 0; i < grid.length; i++) {
+            for (int j = 0; j < grid[i].length; j++) {
+                for (int k = 0; k < grid[i][j].length; k++) {
+                    sum += (int) grid[i][j][k];
+                }
+            }
+        }
+        return sum;
+    }
+
+    static void matrices() {
+        int[][] a = new int[2][3];
+        int[][] b = new int[3][2];
+        for (int i = 0; i < 2; i++) {
+            for (int j = 0; j < 3; j++) {
+                a[i][j] = i + j;
+                b[j][i] = i * j + 1;
+            }
+        }
+        printMatrix(multiply(a, b));
+    }
+
+    static int[][] pascal(int n) {
+        int[][] triangle = new int[n][];
+        for (int i = 0; i < triangle.length; i++) {
+            triangle[i] = new int[i + 1];
+            triangle[i][0] = 1;
+            triangle[i][i] = 1;
+            for (int j = 1; j < i; j++) {
+                triangle[i][j] = triangle[i - 1][j - 1] + triangle[i - 1][j];
+            }
+        }
+        return triangle;
+    }
+
+    static void grids() {
+        long[][][] grid = new long[2][3][4];
+        grid[1][2][3] = 5;
+        grid[0][1][2] = 7;
+        System.out.println(sumGrid(grid));
+    }
+
+    static void names() {
+        String[][] names = new String[2][2];
+        names[0][0] = "a";
+        names[0][1] = "b";
+        names[1][0] = "c";
+        names[1][1] = "d";
+        for (int i = 0; i < names.length; i++) {
+            for (int j = 0; j < names[i].length; j++) {
+                System.out.println(names[i][j]);
+            }
+        }
+    }
+
+    static void points() {
+        Point[] points = new Point[3];
+        for (int i = 0; i < points.length; i++) {
+            points[i] = new Point(i, i * i);
+        }
+        int total = 0;
+        for (int i = 0; i < points.length; i++) {
+            total += points[i].x + points[i].y;
+        }
+        System.out.println(total);
+    }
+
+    static void arrayStore() {
+        Object[] objects = new String[2];
+        objects[0] = "ok";
+        try {
+            objects[1] = new Point(1, 2);
+        } catch (ArrayStoreException e) {
+            System.out.println(e.getMessage());
+        }
+    }
+
+    static void typeChecks(Object names, Object grid, Object points, Object args) {
+        System.out.println(names instanceof String[][]);
+        System.out.println(names instanceof Object[][]);
+        System.out.println(names instanceof Object[]);
+        System.out.println(names instanceof String[]);
+        System.out.println(grid instanceof long[][][]);
+        System.out.println(grid instanceof Object[]);
+        System.out.println(grid instanceof int[][][]);
+        System.out.println(points instanceof Point[]);
+        System.out.println(points instanceof String[]);
+        System.out.println(args instanceof String[]);
+        System.out.println(args instanceof Object[]);
+    }
+
+    static int[][] newMatrix(int n, int m) {
+        return new int[n][m];
+    }
+
+    static Point[] newPoints(int n) {
+        return new Point[n];
+    }
+
+    static void negativeSizes() {
+        try {
+            newMatrix(2, -1);
+        } catch (NegativeArraySizeException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            newPoints(-3);
+        } catch (NegativeArraySizeException e) {
+            System.out.println(e.getMessage());
+        }
+    }
+
+    static int manyMatrices() {
+        // Runs multianewarray more times than the native stack could hold its sizes if they weren't reused.
+        int total = 0;
+        for (int i = 0; i < 300000; i++) {
+            total += new int[1][2][0].length;
+        }
+        return total;
+    }
+
+    public static void main(String[] args) {
+        matrices();
+        printMatrix(pascal(4));
+        grids();
+        names();
+        points();
+        arrayStore();
+        typeChecks(new String[1][1], new long[1][1][1], new Point[1], args);
+        Object[] rows = (Object[]) pascal(5);
+        System.out.println(rows.length);
+        negativeSizes();
+        System.out.println(manyMatrices());
+    }
+}
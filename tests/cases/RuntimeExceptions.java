@@ -0,0 +1,99 @@
+public class RuntimeExceptions {
+    static class Node {
+        int value;
+    }
+
+    static Node missingNode;
+    static int[] missingValues;
+
+    static int divide(int a, int b) {
+        return a / b;
+    }
+
+    static void printDivideLong(long a, long b) {
+        System.out.println(a / b);
+    }
+
+    static int sum(int[] values, int count) {
+        int total = 0;
+        for (int i = 0; i < count; i++) {
+            total += values[i];
+        }
+        return total;
+    }
+
+    public static void main(String[] args) {
+        int[] values = new int[3];
+        values[0] = 1;
+        values[1] = 2;
+        values[2] = 3;
+        Node node = new Node();
+        node.value = 7;
+
+        try {
+            values[3] = 4;
+        } catch (ArrayIndexOutOfBoundsException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            System.out.println(values[-1]);
+        } catch (IndexOutOfBoundsException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            System.out.println(sum(values, 5));
+        } catch (RuntimeException e) {
+            System.out.println(e.getMessage());
+        }
+        System.out.println(sum(values, 3));
+
+        try {
+            System.out.println(divide(node.value, 0));
+        } catch (ArithmeticException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            printDivideLong(1L, 0L);
+        } catch (ArithmeticException e) {
+            System.out.println(e.getMessage());
+        }
+        System.out.println(divide(Integer.MIN_VALUE, -1));
+        printDivideLong(Long.MIN_VALUE, -1L);
+        System.out.println(divide(-7, 2));
+
+        try {
+            values = new int[node.value - 10];
+        } catch (NegativeArraySizeException e) {
+            System.out.println(e.getMessage());
+        }
+        System.out.println(values.length);
+
+        try {
+            System.out.println(missingNode.value);
+        } catch (NullPointerException e) {
+            System.out.println("npe getfield");
+        }
+        try {
+            missingNode.value = 1;
+        } catch (NullPointerException e) {
+            System.out.println("npe putfield");
+        }
+        try {
+            System.out.println(missingNode.toString());
+        } catch (NullPointerException e) {
+            System.out.println("npe invokevirtual");
+        }
+        try {
+            System.out.println(missingValues.length);
+        } catch (NullPointerException e) {
+            System.out.println("npe arraylength");
+        }
+        try {
+            System.out.println(missingValues[0]);
+        } catch (NullPointerException e) {
+            System.out.println("npe iaload");
+        }
+
+        System.out.println(values[args.length + 2]);
+    }
+}
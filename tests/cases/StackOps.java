@@ -0,0 +1,64 @@
+public class StackOps {
+    static int counter;
+    static long total;
+
+    static class Cell {
+        int value;
+        long wide;
+    }
+
+    static int next() {
+        counter++;
+        return counter;
+    }
+
+    static long nextWide() {
+        total += 10;
+        return total;
+    }
+
+    public static void main(String[] args) {
+        // pop and pop2 on discarded results.
+        next();
+        nextWide();
+        System.out.println(counter);
+        System.out.println(total);
+
+        // dup for assignment chains.
+        int a;
+        int b = a = next();
+        System.out.println(a + b);
+
+        // dup2 for post-increment of a static long.
+        long before = total++;
+        System.out.println(before);
+        System.out.println(total);
+
+        // dup_x1 and dup2_x1 for assignments to instance fields used as values.
+        Cell cell = new Cell();
+        int v = cell.value = 7;
+        long w = cell.wide = 5000000000L;
+        System.out.println(v + cell.value);
+        System.out.println(w + cell.wide);
+        int old = cell.value++;
+        System.out.println(old);
+        System.out.println(cell.value);
+
+        // dup_x2 and dup2_x2 for assignments to array elements used as values.
+        int[] ints = new int[3];
+        long[] longs = new long[3];
+        int x = ints[1] = 42;
+        long y = longs[2] = -9000000000L;
+        System.out.println(x + ints[1]);
+        System.out.println(y + longs[2]);
+
+        // dup2 on an array reference and index for compound assignment.
+        ints[1] += 8;
+        longs[2] *= 2;
+        System.out.println(ints[1]);
+        System.out.println(longs[2]);
+        long z = longs[2]++;
+        System.out.println(z);
+        System.out.println(longs[2]);
+    }
+}
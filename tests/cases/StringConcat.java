@@ -0,0 +1,97 @@
+public class StringConcat {
+    static final String PREFIX = "v";
+    static final int ANSWER = 42;
+
+    static class Point {
+        int x;
+        int y;
+
+        Point(int x, int y) {
+            this.x = x;
+            this.y = y;
+        }
+
+        public String toString() {
+            return "(" + x + ", " + y + ")";
+        }
+    }
+
+    static class Nameless {
+        public String toString() {
+            return null;
+        }
+    }
+
+    static class Broken {
+        public String toString() {
+            throw new IllegalStateException("broken toString");
+        }
+    }
+
+    static String describe(String name, int age) {
+        return name + " is " + age + " years old";
+    }
+
+    static String mixed(boolean b, char c, byte by, short s, long l) {
+        return "b=" + b + " c=" + c + " by=" + by + " s=" + s + " l=" + l;
+    }
+
+    static String floats(float f, double d) {
+        return f + " and " + d;
+    }
+
+    static String nothing() {
+        String s = null;
+        return "value: " + s;
+    }
+
+    static String constants(int x) {
+        return PREFIX + x + "/" + ANSWER;
+    }
+
+    static String repeat(String s, int n) {
+        String result = "";
+        for (int i = 0; i < n; i++) {
+            result = result + s + i;
+        }
+        return result;
+    }
+
+    // javac passes the literals containing the tags of the recipe, \u0001 and \u0002, as the constants.
+    static String tagged(int x) {
+        return "<\1>" + x + "<\2>";
+    }
+
+    static String surrogates(char high, char low) {
+        return "smile " + high + low;
+    }
+
+    // Concatenates in a loop long enough to overflow the stack if each concatenation allocated its arguments there.
+    static String manyConcats() {
+        String last = "";
+        for (int i = 0; i < 300000; i++) {
+            last = "n" + i;
+        }
+        return last;
+    }
+
+    public static void main(String[] args) {
+        System.out.println(describe("Alice", 30));
+        System.out.println(mixed(true, 'x', (byte) -5, (short) 300, 12345678901L));
+        System.out.println(floats(1.5f, -0.25));
+        System.out.println(nothing());
+        System.out.println(constants(7));
+        System.out.println(repeat("ab", 3));
+        System.out.println("point " + new Point(3, -4));
+        System.out.println("" + Integer.MIN_VALUE + Long.MAX_VALUE);
+        System.out.println(tagged(5));
+        System.out.println(surrogates('\uD83D', '\uDE00'));
+        System.out.println("nameless " + new Nameless());
+        try {
+            System.out.println("broken " + new Broken());
+        } catch (IllegalStateException e) {
+            System.out.println("caught " + e.getMessage());
+        }
+        System.out.println(manyConcats());
+    }
+}
@@ -0,0 +1,104 @@
+public class Switches {
+    static int dense(int value) {
+        switch (value) {
+            case 0:
+                return 100;
+            case 1:
+            case 2:
+                return 200;
+            case 3:
+                return 300;
+            case 5:
+                return 500;
+            default:
+                return -1;
+        }
+    }
+
+    static int sparse(int value) {
+        switch (value) {
+            case -1000:
+                return 1;
+            case 7:
+                return 2;
+            case 4096:
+            case 65536:
+                return 3;
+            default:
+                return 0;
+        }
+    }
+
+    static int fallthrough(int value) {
+        int result = 0;
+        switch (value) {
+            case 1:
+                result += 1;
+            case 2:
+                result += 10;
+                break;
+            case 3:
+                result += 100;
+            default:
+                result += 1000;
+        }
+        return result;
+    }
+
+    static int countVowels(char[] chars) {
+        int vowels = 0;
+        int others = 0;
+        for (int i = 0; i < chars.length; i++) {
+            switch (chars[i]) {
+                case 'a':
+                case 'e':
+                case 'i':
+                case 'o':
+                case 'u':
+                    vowels++;
+                    break;
+                default:
+                    others++;
+            }
+        }
+        return vowels * 100 + others;
+    }
+
+    static int collatzSteps(int n) {
+        int steps = 0;
+        while (n != 1) {
+            switch (n - (n / 2) * 2) {
+                case 0:
+                    n = n / 2;
+                    break;
+                default:
+                    n = 3 * n + 1;
+            }
+            steps++;
+        }
+        return steps;
+    }
+
+    public static void main(String[] args) {
+        for (int i = -1; i < 7; i++) {
+            System.out.println(dense(i));
+        }
+        System.out.println(sparse(-1000));
+        System.out.println(sparse(7));
+        System.out.println(sparse(4096));
+        System.out.println(sparse(65536));
+        System.out.println(sparse(8));
+        for (int i = 0; i < 5; i++) {
+            System.out.println(fallthrough(i));
+        }
+        char[] chars = new char[6];
+        chars[0] = 'y';
+        chars[1] = 'a';
+        chars[2] = 'j';
+        chars[3] = 'v';
+        chars[4] = 'o';
+        chars[5] = 'e';
+        System.out.println(countVowels(chars));
+        System.out.println(collatzSteps(27));
+    }
+}
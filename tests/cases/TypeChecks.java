@@ -0,0 +1,104 @@
+public class TypeChecks {
+    interface Animal {
+        String sound();
+    }
+
+    interface Pet extends Animal {
+        default String greet() {
+            return "hello " + "pet";
+        }
+    }
+
+    static class Dog implements Pet {
+        public String sound() {
+            return "woof";
+        }
+    }
+
+    static class Puppy extends Dog {
+        public String sound() {
+            return "yip";
+        }
+    }
+
+    static class Wolf implements Animal {
+        public String sound() {
+            return "howl";
+        }
+    }
+
+    static class Money implements Comparable<Money> {
+        int cents;
+
+        Money(int cents) {
+            this.cents = cents;
+        }
+
+        public int compareTo(Money other) {
+            return cents - other.cents;
+        }
+    }
+
+    static String kind(Object o) {
+        if (o instanceof Puppy) {
+            return "puppy";
+        } else if (o instanceof Dog) {
+            return "dog";
+        } else if (o instanceof Animal) {
+            return "animal";
+        } else if (o instanceof String) {
+            return "string";
+        } else if (o instanceof int[]) {
+            return "int array";
+        } else if (o instanceof Object[]) {
+            return "object array";
+        } else if (o instanceof Runnable) {
+            return "runnable";
+        }
+        return "unknown";
+    }
+
+    static void tryCast(Object o) {
+        try {
+            Dog dog = (Dog) o;
+            System.out.println(dog.sound());
+        } catch (ClassCastException e) {
+            System.out.println("not a dog");
+        }
+    }
+
+    static int compare(Comparable c, Object other) {
+        return c.compareTo(other);
+    }
+
+    public static void main(String[] args) {
+        System.out.println(kind(new Puppy()));
+        System.out.println(kind(new Dog()));
+        System.out.println(kind(new Wolf()));
+        System.out.println(kind("text"));
+        System.out.println(kind(new int[3]));
+        System.out.println(kind(args));
+        System.out.println(kind(new long[1]));
+        System.out.println(kind(new Money(1)));
+
+        System.out.println(new Puppy() instanceof Pet);
+        System.out.println(new Wolf() instanceof Pet);
+        System.out.println(new Money(1) instanceof Comparable);
+
+        tryCast(new Puppy());
+        tryCast(new Wolf());
+        tryCast("text");
+
+        Animal animal = new Puppy();
+        Pet pet = (Pet) animal;
+        System.out.println(pet.greet());
+
+        System.out.println(compare(new Money(250), new Money(100)));
+        System.out.println(compare(new Money(100), new Money(100)));
+        try {
+            compare(new Money(1), "text");
+        } catch (ClassCastException e) {
+            System.out.println("cannot compare");
+        }
+    }
+}
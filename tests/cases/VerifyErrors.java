@@ -0,0 +1,27 @@
+// The class file of Cat is compiled from the older version in the comment below, so that Cat no longer extends
+// Animal and main passes it where an Animal is expected:
+//
+//     static class Cat {
+//         String name() { return "cat"; }
+//     }
+public class VerifyErrors {
+    static class Animal {
+        String name() {
+            return "animal";
+        }
+    }
+
+    static class Cat extends Animal {
+        String name() {
+            return "cat";
+        }
+    }
+
+    static String describe(Animal animal) {
+        return animal.name();
+    }
+
+    public static void main(String[] args) {
+        System.out.println(describe(new Cat()));
+    }
+}
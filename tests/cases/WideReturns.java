@@ -0,0 +1,59 @@
+public class WideReturns {
+    static long fib(int n) {
+        long a = 0;
+        long b = 1;
+        for (int i = 0; i < n; i++) {
+            long t = a + b;
+            a = b;
+            b = t;
+        }
+        return a;
+    }
+
+    static long factorial(int n) {
+        if (n <= 1) {
+            return 1;
+        }
+        return n * factorial(n - 1);
+    }
+
+    static float average(float a, float b) {
+        return (a + b) / 2;
+    }
+
+    static double horner(double x) {
+        // 2x^3 - 3x^2 + 0.5x - 1
+        double result = 2;
+        result = result * x - 3;
+        result = result * x + 0.5;
+        result = result * x - 1;
+        return result;
+    }
+
+    static double abs(double d) {
+        if (d < 0) {
+            return -d;
+        }
+        return d;
+    }
+
+    static int[] squares(int n) {
+        int[] result = new int[n];
+        for (int i = 0; i < n; i++) {
+            result[i] = i * i;
+        }
+        return result;
+    }
+
+    public static void main(String[] args) {
+        System.out.println(fib(10));
+        System.out.println(fib(90));
+        System.out.println(factorial(15));
+        System.out.println(average(1.5f, 2.25f));
+        System.out.println(horner(1.5));
+        System.out.println(abs(-2.75));
+        System.out.println(abs(0.125));
+        int[] s = squares(5);
+        System.out.println(s[4] + s[3]);
+    }
+}
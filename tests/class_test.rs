@@ -7,6 +7,8 @@ struct Case {
     args: Vec<String>,
     stdout: String,
     trace: Option<String>,
+    /// The uncaught exception formatted as Throwable.toString, e.g. "java.lang.RuntimeException: boom".
+    exception: Option<String>,
 }
 
 impl Display for Case {
@@ -18,6 +20,9 @@ impl Display for Case {
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 struct CaseYaml {
     class_name: String,
+    /// Whether the traces of the cases are asserted. Only the suites whose traces are checked by hand opt in.
+    #[serde(default)]
+    assert_trace: bool,
     cases: Vec<Case>,
 }
 
@@ -31,11 +36,15 @@ macro_rules! test_class {
 
         let mut env = JitEnv::new(stringify!($class_name));
         env.enable_tracing();
-        env.compile(path.to_str().unwrap().replace(".yaml", ".class").as_str());
-        env.done_compilation();
+        env.compile(path.to_str().unwrap().replace(".yaml", ".class").as_str())
+            .unwrap();
+        for nested in nested_class_paths(stringify!($class_name)) {
+            env.compile(nested.to_str().unwrap()).unwrap();
+        }
+        env.done_compilation().unwrap();
         env.dump_llvm_module(
-            PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-                .join("out.ll")
+            PathBuf::from(env!("CARGO_TARGET_TMPDIR"))
+                .join(concat!(stringify!($class_name), ".ll"))
                 .to_str()
                 .unwrap(),
         );
@@ -46,13 +55,20 @@ macro_rules! test_class {
         );
         for case in test_suite.cases {
             print!("Case: {} \n", case);
-            let mut isolate = env.new_isolate(StdoutOption::VecOutputStream);
-            env.call(&mut isolate, &case.args);
+            let mut isolate = env.new_isolate(StdoutOption::VecOutputStream).unwrap();
+            let exception = env
+                .call(&mut isolate, &case.args)
+                .err()
+                .map(|e| e.to_string());
             let buf = isolate.stdout_buffer();
             let s = String::from_utf8(buf.to_vec()).unwrap();
-            let traced = isolate.tracer().buffer();
-            println!("Tracing:\n{}", String::from_utf8(traced.to_vec()).unwrap());
+            let traced = String::from_utf8(isolate.tracer().buffer().to_vec()).unwrap();
+            println!("Tracing:\n{}", traced);
             assert_eq!(s, case.stdout, "\nleft:\n{}\nright:\n{}", s, case.stdout);
+            assert_eq!(exception, case.exception);
+            if let (true, Some(trace)) = (test_suite.assert_trace, &case.trace) {
+                assert_eq!(&traced, trace, "\nleft:\n{}\nright:\n{}", traced, trace);
+            }
             println!("\tPassed");
         }
         println!("<<<<<<<<<<<<<< {} Passed\n\n", stringify!($class_name));
@@ -62,9 +78,10 @@ macro_rules! test_class {
 #[cfg(test)]
 mod test {
     use super::*;
+    use std::collections::HashMap;
     use std::fs::File;
     use std::path::PathBuf;
-    use yajvm::{JitEnv, StdoutOption};
+    use yajvm::{Classpath, Jar, JarClasses, JitEnv, StdoutOption, YajvmError};
 
     fn yaml_path(class_name: &str) -> PathBuf {
         let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -74,6 +91,21 @@ mod test {
         path
     }
 
+    /// Returns the paths of the class files of the nested classes, e.g. `Foo$Bar.class` for `Foo`.
+    fn nested_class_paths(class_name: &str) -> Vec<PathBuf> {
+        let prefix = class_name.to_string() + "$";
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(yaml_path(class_name).parent().unwrap())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| {
+                let file_name = path.file_name().unwrap().to_str().unwrap();
+                file_name.starts_with(&prefix) && file_name.ends_with(".class")
+            })
+            .collect();
+        paths.sort();
+        paths
+    }
+
     #[test]
     fn test_hello_world() {
         test_class!(HelloWorld);
@@ -168,4 +200,429 @@ mod test {
     fn test_inc_dec() {
         test_class!(IncDec);
     }
+
+    #[test]
+    fn test_object_instantiation() {
+        test_class!(ObjectInstantiation);
+    }
+
+    #[test]
+    fn test_field_narrowing() {
+        test_class!(FieldNarrowing);
+    }
+
+    #[test]
+    fn test_exceptions() {
+        test_class!(Exceptions);
+    }
+
+    #[test]
+    fn test_runtime_exceptions() {
+        test_class!(RuntimeExceptions);
+    }
+
+    #[test]
+    fn test_switches() {
+        test_class!(Switches);
+    }
+
+    #[test]
+    fn test_hashing() {
+        test_class!(Hashing);
+    }
+
+    #[test]
+    fn test_bit_twiddling() {
+        test_class!(BitTwiddling);
+    }
+
+    #[test]
+    fn test_conversions() {
+        test_class!(Conversions);
+    }
+
+    #[test]
+    fn test_wide_returns() {
+        test_class!(WideReturns);
+    }
+
+    #[test]
+    fn test_stack_ops() {
+        test_class!(StackOps);
+    }
+
+    #[test]
+    fn test_cross_class_statics() {
+        test_class!(CrossClassStatics);
+    }
+
+    #[test]
+    fn test_polymorphism() {
+        test_class!(Polymorphism);
+    }
+
+    #[test]
+    fn test_interfaces() {
+        test_class!(Interfaces);
+    }
+
+    #[test]
+    fn test_interface_errors() {
+        test_class!(InterfaceErrors);
+    }
+
+    #[test]
+    fn test_type_checks() {
+        test_class!(TypeChecks);
+    }
+
+    #[test]
+    fn test_reference_arrays() {
+        test_class!(ReferenceArrays);
+    }
+
+    #[test]
+    fn test_null_checks() {
+        test_class!(NullChecks);
+    }
+
+    #[test]
+    fn test_string_concat() {
+        test_class!(StringConcat);
+    }
+
+    #[test]
+    fn test_boxing() {
+        test_class!(Boxing);
+    }
+
+    #[test]
+    fn test_lambdas() {
+        test_class!(Lambdas);
+    }
+
+    #[test]
+    fn test_class_init() {
+        test_class!(ClassInit);
+    }
+
+    #[test]
+    fn test_constant_values() {
+        test_class!(ConstantValues);
+    }
+
+    #[test]
+    fn test_static_fields_layout() {
+        let path = yaml_path("StaticVariables").with_extension("class");
+        let mut env = JitEnv::new("StaticVariables");
+        env.compile(path.to_str().unwrap()).unwrap();
+        env.done_compilation().unwrap();
+
+        let fields: Vec<(&str, &str, u32)> = env
+            .static_fields("StaticVariables")
+            .unwrap()
+            .iter()
+            .map(|f| (f.name.as_str(), f.descriptor.as_str(), f.offset))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                ("longVar", "J", 0),
+                ("doubleVar", "D", 8),
+                ("intVar", "I", 16),
+                ("floatVar", "F", 20),
+                ("shortVar", "S", 24),
+                ("charVar", "C", 26),
+                ("boolVar", "Z", 28),
+                ("byteVar", "B", 29),
+            ]
+        );
+        assert!(env.static_fields("NoSuchClass").is_none());
+    }
+
+    #[test]
+    fn test_control_flow() {
+        test_class!(ControlFlow);
+    }
+
+    #[test]
+    fn test_compile_errors() {
+        let mut env = JitEnv::new("Missing");
+        match env.compile("no/such/Missing.class") {
+            Err(YajvmError::Io { path, .. }) => assert_eq!(path, "no/such/Missing.class"),
+            r => panic!("{:?}", r),
+        }
+
+        // The source is not a class file.
+        let path = yaml_path("ClassLiteral").with_extension("java");
+        match env.compile(path.to_str().unwrap()) {
+            Err(YajvmError::ClassFormat { .. }) => {}
+            r => panic!("{:?}", r),
+        }
+
+        let path = yaml_path("ClassLiteral").with_extension("class");
+        match env.compile(path.to_str().unwrap()) {
+            Err(YajvmError::UnsupportedOpcode {
+                class_name,
+                method,
+                offset,
+                ..
+            }) => {
+                assert_eq!(class_name, "ClassLiteral");
+                assert_eq!(method, "main([Ljava/lang/String;)V");
+                assert_eq!(offset, 0);
+            }
+            r => panic!("{:?}", r),
+        }
+
+        // The lambdas boxing the primitives are not supported.
+        let path = yaml_path("BoxingLambda").with_extension("class");
+        let mut env = JitEnv::new("BoxingLambda");
+        match env.compile(path.to_str().unwrap()) {
+            Err(YajvmError::UnsupportedOpcode {
+                method,
+                instruction,
+                ..
+            }) => {
+                assert_eq!(method, "main([Ljava/lang/String;)V");
+                assert!(instruction.ends_with("converting Int to java/lang/Object"));
+            }
+            r => panic!("{:?}", r),
+        }
+
+        // The failure left the code of BoxingLambda behind, so the JitEnv is no longer usable.
+        let path = yaml_path("BoxingLambda$ObjectSupplier").with_extension("class");
+        match env.compile(path.to_str().unwrap()) {
+            Err(YajvmError::Poisoned { class_name }) => assert_eq!(class_name, "BoxingLambda"),
+            r => panic!("{:?}", r),
+        }
+        match env.done_compilation() {
+            Err(YajvmError::Poisoned { class_name }) => assert_eq!(class_name, "BoxingLambda"),
+            r => panic!("{:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_verify_errors() {
+        // main passes the array of the arguments where a string is expected.
+        let path = yaml_path("ArrayTypeMismatch").with_extension("class");
+        match JitEnv::new("ArrayTypeMismatch").compile(path.to_str().unwrap()) {
+            Err(YajvmError::Verify(e)) => {
+                assert_eq!(e.class_name, "ArrayTypeMismatch");
+                assert_eq!(e.method.as_deref(), Some("main([Ljava/lang/String;)V"));
+                assert_eq!(e.offset, Some(6));
+                assert_eq!(
+                    e.message,
+                    "expected java/lang/String on the operand stack, found [Ljava/lang/String;"
+                );
+            }
+            r => panic!("{:?}", r),
+        }
+
+        // VerifyErrors$Cat no longer extends VerifyErrors$Animal, which is found against the class hierarchy.
+        let path = yaml_path("VerifyErrors").with_extension("class");
+        let mut env = JitEnv::new("VerifyErrors");
+        env.compile(path.to_str().unwrap()).unwrap();
+        for nested in nested_class_paths("VerifyErrors") {
+            env.compile(nested.to_str().unwrap()).unwrap();
+        }
+        match env.done_compilation() {
+            Err(YajvmError::Verify(e)) => {
+                assert_eq!(e.class_name, "VerifyErrors");
+                assert_eq!(e.method.as_deref(), Some("main([Ljava/lang/String;)V"));
+                assert_eq!(e.offset, Some(10));
+                assert_eq!(
+                    e.message,
+                    "VerifyErrors$Cat is not assignable to VerifyErrors$Animal"
+                );
+            }
+            r => panic!("{:?}", r),
+        }
+
+        // The class file of version 49 has no stack map frames.
+        let path = yaml_path("OldClassVersion").with_extension("class");
+        match JitEnv::new("OldClassVersion").compile(path.to_str().unwrap()) {
+            Err(YajvmError::Verify(e)) => {
+                assert_eq!(e.class_name, "OldClassVersion");
+                assert_eq!(e.method, None);
+                assert_eq!(
+                    e.message,
+                    "unsupported class file version 49 without stack map frames"
+                );
+            }
+            r => panic!("{:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_linkage_error() {
+        // ControlFlow$Node is not compiled.
+        let path = yaml_path("ControlFlow").with_extension("class");
+        let mut env = JitEnv::new("ControlFlow");
+        env.compile(path.to_str().unwrap()).unwrap();
+        match env.done_compilation() {
+            Err(YajvmError::Linkage { message }) => {
+                assert_eq!(message, "class ControlFlow$Node not found")
+            }
+            r => panic!("{:?}", r),
+        }
+        // The classes failed to link, so they can't be run.
+        match env.new_isolate(StdoutOption::VecOutputStream).err() {
+            Some(YajvmError::Linkage { message }) => {
+                assert_eq!(message, "the classes are not linked by done_compilation")
+            }
+            e => panic!("{:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_jar() {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/jar/app.jar");
+        let mut jar = Jar::open(path.to_str().unwrap()).unwrap();
+        assert_eq!(jar.main_class(), Some("com/example/Main"));
+        assert_eq!(
+            jar.class_names(),
+            vec![
+                "com/example/Greeter",
+                "com/example/Main",
+                "com/example/Unused",
+                "com/example/Util"
+            ]
+        );
+
+        let mut env = JitEnv::new(jar.main_class().unwrap());
+        env.compile_jar(&mut jar, JarClasses::Reachable).unwrap();
+        env.done_compilation().unwrap();
+        let mut isolate = env.new_isolate(StdoutOption::VecOutputStream).unwrap();
+        env.call(&mut isolate, &vec![]).unwrap();
+        let stdout = String::from_utf8(isolate.stdout_buffer().to_vec()).unwrap();
+        assert_eq!(stdout, "Hello, jar\n42\n");
+
+        // The JAR file as the class source compiles the same classes.
+        let mut env = JitEnv::new(jar.main_class().unwrap());
+        env.set_class_source(Jar::open(path.to_str().unwrap()).unwrap());
+        env.done_compilation().unwrap();
+        let mut isolate = env.new_isolate(StdoutOption::VecOutputStream).unwrap();
+        env.call(&mut isolate, &vec![]).unwrap();
+        let stdout = String::from_utf8(isolate.stdout_buffer().to_vec()).unwrap();
+        assert_eq!(stdout, "Hello, jar\n42\n");
+
+        // Unused isn't reachable from the main class, and it uses an unsupported instruction.
+        let mut env = JitEnv::new(jar.main_class().unwrap());
+        match env.compile_jar(&mut jar, JarClasses::All) {
+            Err(YajvmError::UnsupportedOpcode { class_name, .. }) => {
+                assert_eq!(class_name, "com/example/Unused")
+            }
+            r => panic!("{:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_classpath() {
+        // ControlFlow$Node is compiled on demand from the classpath.
+        let cases = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/cases");
+        let mut env = JitEnv::new("ControlFlow");
+        env.set_classpath(Classpath::parse(cases.to_str().unwrap()).unwrap());
+        env.done_compilation().unwrap();
+        let mut isolate = env.new_isolate(StdoutOption::VecOutputStream).unwrap();
+        env.call(&mut isolate, &vec![]).unwrap();
+        let contents = std::fs::read_to_string(yaml_path("ControlFlow")).unwrap();
+        let test_suite: CaseYaml = serde_yaml::from_str(&contents).unwrap();
+        let stdout = String::from_utf8(isolate.stdout_buffer().to_vec()).unwrap();
+        assert_eq!(stdout, test_suite.cases[0].stdout);
+
+        // The JAR file is searched after the directory.
+        let jar = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/jar/app.jar");
+        let mut classpath = Classpath::new();
+        classpath.add_directory(&cases);
+        classpath.add_jar(Jar::open(jar.to_str().unwrap()).unwrap());
+        let mut env = JitEnv::new("com/example/Main");
+        env.set_classpath(classpath);
+        env.done_compilation().unwrap();
+        let mut isolate = env.new_isolate(StdoutOption::VecOutputStream).unwrap();
+        env.call(&mut isolate, &vec![]).unwrap();
+        let stdout = String::from_utf8(isolate.stdout_buffer().to_vec()).unwrap();
+        assert_eq!(stdout, "Hello, jar\n42\n");
+    }
+
+    #[test]
+    fn test_no_class_def_found() {
+        let cases = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/cases");
+        let mut env = JitEnv::new("Missing");
+        env.set_classpath(Classpath::parse(cases.to_str().unwrap()).unwrap());
+        match env.done_compilation() {
+            Err(e @ YajvmError::NoClassDefFound { .. }) => {
+                assert_eq!(e.to_string(), "java.lang.NoClassDefFoundError: Missing")
+            }
+            r => panic!("{:?}", r),
+        }
+
+        // Only ControlFlow is in the classpath, and ControlFlow$Node isn't.
+        let classes = PathBuf::from(env!("CARGO_TARGET_TMPDIR"))
+            .join(format!("no_class_def_found_{}", std::process::id()));
+        std::fs::create_dir_all(&classes).unwrap();
+        std::fs::copy(
+            cases.join("ControlFlow.class"),
+            classes.join("ControlFlow.class"),
+        )
+        .unwrap();
+        let mut env = JitEnv::new("ControlFlow");
+        env.set_classpath(Classpath::parse(classes.to_str().unwrap()).unwrap());
+        let result = env.done_compilation();
+        std::fs::remove_dir_all(&classes).unwrap();
+        match result {
+            Err(YajvmError::NoClassDefFound {
+                class_name,
+                referenced_by,
+            }) => {
+                assert_eq!(class_name, "ControlFlow$Node");
+                assert_eq!(referenced_by.as_deref(), Some("ControlFlow"));
+            }
+            r => panic!("{:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_invalid_class_name() {
+        // The names leading out of the directory aren't looked up even though the files exist.
+        let cases = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/cases");
+        let absolute = cases.join("ControlFlow").to_str().unwrap().to_string();
+        for class_name in ["../cases/ControlFlow", "./ControlFlow", absolute.as_str()] {
+            let mut env = JitEnv::new(class_name);
+            env.set_classpath(Classpath::parse(cases.to_str().unwrap()).unwrap());
+            match env.done_compilation() {
+                Err(YajvmError::NoClassDefFound {
+                    class_name: missing,
+                    referenced_by: None,
+                }) => assert_eq!(missing, class_name),
+                r => panic!("{:?}", r),
+            }
+        }
+    }
+
+    #[test]
+    fn test_compile_bytes() {
+        let mut env = JitEnv::new("ControlFlow");
+        env.compile_bytes(include_bytes!("cases/ControlFlow.class"))
+            .unwrap();
+        let mut class_source = HashMap::new();
+        class_source.insert(
+            "ControlFlow$Node".to_string(),
+            include_bytes!("cases/ControlFlow$Node.class").as_slice(),
+        );
+        env.set_class_source(class_source);
+        env.done_compilation().unwrap();
+        let mut isolate = env.new_isolate(StdoutOption::VecOutputStream).unwrap();
+        env.call(&mut isolate, &vec![]).unwrap();
+        let contents = std::fs::read_to_string(yaml_path("ControlFlow")).unwrap();
+        let test_suite: CaseYaml = serde_yaml::from_str(&contents).unwrap();
+        let stdout = String::from_utf8(isolate.stdout_buffer().to_vec()).unwrap();
+        assert_eq!(stdout, test_suite.cases[0].stdout);
+
+        // The truncated class file has no path but the placeholder in the error.
+        match JitEnv::new("Main").compile_bytes(b"\xca\xfe\xba\xbe") {
+            Err(YajvmError::ClassFormat { path, .. }) => assert_eq!(path, "<memory>"),
+            r => panic!("{:?}", r),
+        }
+    }
 }
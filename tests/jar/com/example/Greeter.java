@@ -0,0 +1,13 @@
+package com.example;
+
+class Greeter {
+    private final String name;
+
+    Greeter(String name) {
+        this.name = name;
+    }
+
+    String greet() {
+        return "Hello, " + name;
+    }
+}
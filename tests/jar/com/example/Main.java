@@ -0,0 +1,9 @@
+package com.example;
+
+public class Main {
+    public static void main(String[] args) {
+        Greeter greeter = new Greeter("jar");
+        System.out.println(greeter.greet());
+        System.out.println(Util.twice(21));
+    }
+}
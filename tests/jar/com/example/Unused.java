@@ -0,0 +1,8 @@
+package com.example;
+
+// Not reachable from Main, and uses ldc of a class constant which isn't supported.
+class Unused {
+    static Class<?> self() {
+        return Unused.class;
+    }
+}
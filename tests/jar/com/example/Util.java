@@ -0,0 +1,7 @@
+package com.example;
+
+class Util {
+    static int twice(int n) {
+        return n * 2;
+    }
+}